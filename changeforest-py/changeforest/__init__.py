@@ -1,5 +1,27 @@
-from .changeforest import changeforest
+from .changeforest import (
+    changeforest,
+    changeforest_autoregression,
+    changeforest_categorical,
+    changeforest_conditional,
+    changeforest_panel,
+    changeforest_regression,
+)
+from .classifier import changeforest_classifier, cross_fit
 from .control import Control
 from .plotting import BinarySegmentationResult, OptimizerResult
+from .progress import tqdm_callback
 
-__all__ = ["Control", "changeforest", "BinarySegmentationResult", "OptimizerResult"]
+__all__ = [
+    "Control",
+    "changeforest",
+    "changeforest_autoregression",
+    "changeforest_categorical",
+    "changeforest_classifier",
+    "changeforest_conditional",
+    "changeforest_panel",
+    "changeforest_regression",
+    "cross_fit",
+    "tqdm_callback",
+    "BinarySegmentationResult",
+    "OptimizerResult",
+]
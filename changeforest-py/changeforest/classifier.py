@@ -0,0 +1,78 @@
+import copy
+
+import numpy as np
+
+from .changeforest import changeforest_classifier as _changeforest_classifier
+
+
+def changeforest_classifier(
+    X, classifier, segmentation_type="bs", control=None, n_folds=5
+):
+    """
+    Classifier-based change point detection with a user-supplied classifier.
+
+    Parameters
+    ----------
+    X : numpy.ndarray of shape (n, d)
+        Time series.
+    classifier : callable or estimator
+        Either a callable ``classifier(X, y)`` that receives the features of a segment
+        and binary labels and returns out-of-sample estimates of the probability of
+        label ``1`` for each observation, or an estimator with ``fit`` and
+        ``predict_proba`` methods, e.g. from ``sklearn``. Estimators are cross-fitted,
+        see ``cross_fit``.
+    segmentation_type : str
+        One of ``"bs"``, ``"sbs"`` or ``"wbs"``.
+    control : Control
+        Hyperparameters.
+    n_folds : int
+        Number of folds for cross-fitting if ``classifier`` is an estimator.
+    """
+    if not callable(classifier):
+        seed = getattr(control, "seed", 0)
+        classifier = cross_fit(
+            classifier, n_folds=n_folds, seed=seed if isinstance(seed, int) else 0
+        )
+
+    return _changeforest_classifier(
+        np.asarray(X, dtype=np.float64), classifier, segmentation_type, control
+    )
+
+
+def cross_fit(estimator, n_folds=5, seed=0):
+    """
+    Turn an estimator into a callable returning out-of-sample probabilities.
+
+    Observations are randomly assigned to ``n_folds`` folds. For each fold, a copy of
+    ``estimator`` is fit on the remaining folds and used to predict the probability of
+    label ``1`` of the observations in the fold. If the remaining folds contain a
+    single class, the share of label ``1`` in the remaining folds, shrunk towards
+    ``1/2`` by one pseudo-observation of each class, is predicted instead.
+    """
+    if n_folds < 2:
+        raise ValueError(f"n_folds must be at least 2. Got {n_folds}.")
+
+    def classifier(X, y):
+        rng = np.random.default_rng(seed)
+        folds = rng.permutation(len(y)) % n_folds
+        predictions = np.empty(len(y), dtype=np.float64)
+
+        for fold in range(n_folds):
+            test = folds == fold
+            if not test.any():
+                continue
+
+            train_labels = y[~test]
+            if np.unique(train_labels).size < 2:
+                # Probabilities of zero or one would dominate the log-likelihoods.
+                predictions[test] = (train_labels.sum() + 1) / (len(train_labels) + 2)
+                continue
+
+            model = copy.deepcopy(estimator)
+            model.fit(X[~test], train_labels)
+            classes = list(model.classes_)
+            predictions[test] = model.predict_proba(X[test])[:, classes.index(1)]
+
+        return predictions
+
+    return classifier
@@ -1,3 +1,8 @@
+import datetime
+
+import numpy as np
+
+
 class Control:
     """
     Storage container for hyperparameters.
@@ -9,8 +14,18 @@ class Control:
         self,
         minimal_relative_segment_length="default",
         minimal_gain_to_split="default",
+        penalty="default",
+        standardize="default",
         model_selection_alpha="default",
         model_selection_n_permutations="default",
+        model_selection_permutation_type="default",
+        model_selection_block_length="default",
+        likelihood_shrinkage="default",
+        calibration="default",
+        two_step_search_n_guesses="default",
+        two_step_search_n_refinements="default",
+        two_step_search_bracketing="default",
+        warm_start="default",
         number_of_wild_segments="default",
         seeded_segments_alpha="default",
         seed="default",
@@ -18,14 +33,33 @@ class Control:
         random_forest_max_depth="default",
         random_forest_max_features="default",
         random_forest_n_jobs="default",
+        tree_scaling="default",
         forbidden_segments="default",
+        time_index="default",
+        minimal_segment_duration="default",
+        forbidden_time_segments="default",
+        embedding_lags="default",
+        embedding_windows="default",
+        embedding_differences="default",
     ):
         self.minimal_relative_segment_length = _to_float(
             minimal_relative_segment_length
         )
         self.minimal_gain_to_split = _to_float(minimal_gain_to_split)
+        self.penalty = _to_penalty(penalty)
+        self.standardize = _to_bool(standardize)
         self.model_selection_alpha = _to_float(model_selection_alpha)
         self.model_selection_n_permutations = _to_int(model_selection_n_permutations)
+        self.model_selection_permutation_type = _to_permutation_type(
+            model_selection_permutation_type
+        )
+        self.model_selection_block_length = _to_int(model_selection_block_length)
+        self.likelihood_shrinkage = _to_float(likelihood_shrinkage)
+        self.calibration = _to_calibration(calibration)
+        self.two_step_search_n_guesses = _to_int(two_step_search_n_guesses)
+        self.two_step_search_n_refinements = _to_int(two_step_search_n_refinements)
+        self.two_step_search_bracketing = _to_bool(two_step_search_bracketing)
+        self.warm_start = _to_bool(warm_start)
         self.number_of_wild_segments = _to_int(number_of_wild_segments)
         self.seeded_segments_alpha = _to_float(seeded_segments_alpha)
         self.seed = _to_int(seed)
@@ -33,7 +67,14 @@ class Control:
         self.random_forest_max_depth = _to_int(random_forest_max_depth)
         self.random_forest_max_features = _to_int(random_forest_max_features)
         self.random_forest_n_jobs = _to_int(random_forest_n_jobs)
+        self.tree_scaling = _to_bool(tree_scaling)
         self.forbidden_segments = _to_segments(forbidden_segments)
+        self.time_index = _to_time_index(time_index)
+        self.minimal_segment_duration = _to_duration(minimal_segment_duration)
+        self.forbidden_time_segments = _to_time_segments(forbidden_time_segments)
+        self.embedding_lags = _to_int(embedding_lags)
+        self.embedding_windows = _to_windows(embedding_windows)
+        self.embedding_differences = _to_bool(embedding_differences)
 
 
 def _to_float(value):
@@ -54,6 +95,59 @@ def _to_int(value):
         return int(value)
 
 
+def _to_bool(value):
+    if value is None:
+        return None
+    elif isinstance(value, str):
+        return value
+    else:
+        return bool(value)
+
+
+def _to_penalty(value):
+    if value is None:
+        return "default"
+    elif isinstance(value, str):
+        return value.lower()
+    else:
+        raise TypeError(
+            "penalty must be one of 'bic', 'aic', 'mbic' or 'hqc'. Got {}".format(value)
+        )
+
+
+def _to_permutation_type(value):
+    if value is None:
+        return "default"
+    elif isinstance(value, str):
+        return value.lower()
+    else:
+        raise TypeError(
+            "model_selection_permutation_type must be one of 'observations', 'blocks' "
+            "or 'circular_shift'. Got {}".format(value)
+        )
+
+
+def _to_calibration(value):
+    if value is None:
+        return "none"
+    elif isinstance(value, str):
+        return value.lower()
+    elif isinstance(value, (int, float)) and not isinstance(value, bool):
+        return float(value)
+    else:
+        raise TypeError(
+            "calibration must be one of 'none', 'temperature', 'isotonic' or a "
+            "temperature. Got {}".format(value)
+        )
+
+
+def _to_windows(value):
+    if (value is None) or isinstance(value, str):
+        return value
+    else:
+        return [int(el) for el in value]
+
+
 def _to_segments(value):
     if (value is None) or isinstance(value, str):
         return value
@@ -65,3 +159,43 @@ def _to_segments(value):
                 "forbidden_segments must be provided as [(a,b), ...] where a and b are "
                 "integers."
             )
+
+
+def _to_time(value):
+    # Timestamps are passed to rust as seconds since epoch.
+    if isinstance(value, datetime.datetime):
+        value = np.datetime64(value)
+    value = np.asarray(value)
+    if np.issubdtype(value.dtype, np.datetime64):
+        return value.astype("datetime64[ns]").astype(np.int64) / 1e9
+    else:
+        return value.astype(float)
+
+
+def _to_time_index(value):
+    if (value is None) or isinstance(value, str):
+        return value
+    else:
+        return _to_time(value).tolist()
+
+
+def _to_duration(value):
+    if (value is None) or isinstance(value, str):
+        return value
+    elif isinstance(value, (np.timedelta64, datetime.timedelta)):
+        return np.timedelta64(value, "ns") / np.timedelta64(1, "s")
+    else:
+        return float(value)
+
+
+def _to_time_segments(value):
+    if (value is None) or isinstance(value, str):
+        return value
+    else:
+        try:
+            return [(float(_to_time(el1)), float(_to_time(el2))) for (el1, el2) in value]
+        except Exception:
+            raise SyntaxError(
+                "forbidden_time_segments must be provided as [(a,b), ...] where a and b "
+                "are numbers or datetimes."
+            )
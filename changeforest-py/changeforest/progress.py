@@ -0,0 +1,31 @@
+def tqdm_callback(**kwargs):
+    """
+    Callback for ``changeforest`` displaying the progress with ``tqdm``.
+
+    One progress bar is displayed for each stage of the segmentation: fitting the
+    seeded or wild segments (``"segments"``, for ``segmentation_type="sbs"`` or
+    ``"wbs"``) and growing the tree (``"tree"``), counted in observations belonging
+    to finished segments. Keyword arguments are passed to ``tqdm.auto.tqdm``.
+
+    Example
+    -------
+    >>> changeforest(X, "random_forest", "bs", callback=tqdm_callback())
+    """
+    from tqdm.auto import tqdm
+
+    bars = {}
+
+    def callback(event):
+        if event["event"] != "progress":
+            return
+
+        stage = event["stage"]
+        if stage not in bars:
+            bars[stage] = tqdm(total=event["total"], desc=stage, **kwargs)
+
+        bar = bars[stage]
+        bar.update(event["completed"] - bar.n)
+        if event["completed"] >= event["total"]:
+            bar.close()
+
+    return callback
@@ -1,5 +1,5 @@
 use biosphere::MaxFeatures;
-use changeforest::Control;
+use changeforest::{Calibration, Control, Penalty, PermutationType, TreeScaling};
 use pyo3::exceptions;
 use pyo3::prelude::{pyclass, Bound, FromPyObject, PyAny, PyErr, PyResult};
 use pyo3::prelude::{Py, Python};
@@ -21,6 +21,20 @@ pub fn control_from_pyobj(py: Python, obj: Option<Py<PyAny>>) -> PyResult<Contro
             }
         };
 
+        if let Ok(pyvalue) = obj.getattr(py, "penalty") {
+            if let Ok(value) = pyvalue.extract::<String>(py) {
+                if value != "default" {
+                    control = control.with_penalty(value.parse::<PyPenalty>()?.value);
+                }
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "standardize") {
+            if let Ok(value) = pyvalue.extract::<bool>(py) {
+                control = control.with_standardize(value);
+            }
+        };
+
         if let Ok(pyvalue) = obj.getattr(py, "model_selection_alpha") {
             if let Ok(value) = pyvalue.extract::<f64>(py) {
                 control = control.with_model_selection_alpha(value);
@@ -39,6 +53,62 @@ pub fn control_from_pyobj(py: Python, obj: Option<Py<PyAny>>) -> PyResult<Contro
             }
         };
 
+        if let Ok(pyvalue) = obj.getattr(py, "model_selection_permutation_type") {
+            if let Ok(value) = pyvalue.extract::<String>(py) {
+                if value != "default" {
+                    control = control.with_model_selection_permutation_type(
+                        value.parse::<PyPermutationType>()?.value,
+                    );
+                }
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "model_selection_block_length") {
+            if let Ok(value) = pyvalue.extract::<Option<usize>>(py) {
+                control = control.with_model_selection_block_length(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "likelihood_shrinkage") {
+            if let Ok(value) = pyvalue.extract::<f64>(py) {
+                control = control.with_likelihood_shrinkage(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "calibration") {
+            if let Ok(value) = pyvalue.extract::<String>(py) {
+                if value != "default" {
+                    control = control.with_calibration(value.parse::<PyCalibration>()?.value);
+                }
+            } else if let Ok(value) = pyvalue.extract::<f64>(py) {
+                control = control.with_calibration(Calibration::Temperature(Some(value)));
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "two_step_search_n_guesses") {
+            if let Ok(value) = pyvalue.extract::<usize>(py) {
+                control = control.with_two_step_search_n_guesses(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "two_step_search_n_refinements") {
+            if let Ok(value) = pyvalue.extract::<usize>(py) {
+                control = control.with_two_step_search_n_refinements(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "two_step_search_bracketing") {
+            if let Ok(value) = pyvalue.extract::<bool>(py) {
+                control = control.with_two_step_search_bracketing(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "warm_start") {
+            if let Ok(value) = pyvalue.extract::<bool>(py) {
+                control = control.with_warm_start(value);
+            }
+        };
+
         if let Ok(pyvalue) = obj.getattr(py, "number_of_wild_segments") {
             if let Ok(value) = pyvalue.extract::<usize>(py) {
                 control = control.with_number_of_wild_segments(value);
@@ -50,6 +120,7 @@ pub fn control_from_pyobj(py: Python, obj: Option<Py<PyAny>>) -> PyResult<Contro
                 control = control.with_seed(value);
                 control.random_forest_parameters =
                     control.random_forest_parameters.with_seed(value);
+                control.extra_trees_parameters = control.extra_trees_parameters.with_seed(value);
             }
         };
 
@@ -63,6 +134,8 @@ pub fn control_from_pyobj(py: Python, obj: Option<Py<PyAny>>) -> PyResult<Contro
             if let Ok(value) = pyvalue.extract::<usize>(py) {
                 control.random_forest_parameters =
                     control.random_forest_parameters.with_n_estimators(value);
+                control.extra_trees_parameters =
+                    control.extra_trees_parameters.with_n_estimators(value);
             }
         };
 
@@ -70,6 +143,8 @@ pub fn control_from_pyobj(py: Python, obj: Option<Py<PyAny>>) -> PyResult<Contro
             if let Ok(value) = pyvalue.extract::<Option<usize>>(py) {
                 control.random_forest_parameters =
                     control.random_forest_parameters.with_max_depth(value);
+                control.extra_trees_parameters =
+                    control.extra_trees_parameters.with_max_depth(value);
             }
         };
 
@@ -77,6 +152,9 @@ pub fn control_from_pyobj(py: Python, obj: Option<Py<PyAny>>) -> PyResult<Contro
             if let Ok(value) = pyvalue.extract::<PyMaxFeatures>(py) {
                 control.random_forest_parameters = control
                     .random_forest_parameters
+                    .with_max_features(value.value.clone());
+                control.extra_trees_parameters = control
+                    .extra_trees_parameters
                     .with_max_features(value.value);
             }
         };
@@ -88,11 +166,64 @@ pub fn control_from_pyobj(py: Python, obj: Option<Py<PyAny>>) -> PyResult<Contro
             }
         };
 
+        if let Ok(pyvalue) = obj.getattr(py, "tree_scaling") {
+            if let Ok(true) = pyvalue.extract::<bool>(py) {
+                let mut tree_scaling = TreeScaling::default();
+                if let Ok(pyvalue) = obj.getattr(py, "random_forest_n_estimators") {
+                    if let Ok(value) = pyvalue.extract::<usize>(py) {
+                        tree_scaling.n_estimators = value;
+                    }
+                };
+                if let Ok(pyvalue) = obj.getattr(py, "random_forest_max_depth") {
+                    if let Ok(value) = pyvalue.extract::<Option<usize>>(py) {
+                        tree_scaling.max_depth = value;
+                    }
+                };
+                control = control.with_tree_scaling(Some(tree_scaling));
+            }
+        };
+
         if let Ok(pyvalue) = obj.getattr(py, "forbidden_segments") {
             if let Ok(value) = pyvalue.extract::<Option<Vec<(usize, usize)>>>(py) {
                 control = control.with_forbidden_segments(value);
             }
         };
+
+        if let Ok(pyvalue) = obj.getattr(py, "time_index") {
+            if let Ok(value) = pyvalue.extract::<Option<Vec<f64>>>(py) {
+                control = control.with_time_index(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "minimal_segment_duration") {
+            if let Ok(value) = pyvalue.extract::<Option<f64>>(py) {
+                control = control.with_minimal_segment_duration(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "forbidden_time_segments") {
+            if let Ok(value) = pyvalue.extract::<Option<Vec<(f64, f64)>>>(py) {
+                control = control.with_forbidden_time_segments(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "embedding_lags") {
+            if let Ok(value) = pyvalue.extract::<usize>(py) {
+                control = control.with_embedding_lags(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "embedding_windows") {
+            if let Ok(value) = pyvalue.extract::<Vec<usize>>(py) {
+                control = control.with_embedding_windows(value);
+            }
+        };
+
+        if let Ok(pyvalue) = obj.getattr(py, "embedding_differences") {
+            if let Ok(value) = pyvalue.extract::<bool>(py) {
+                control = control.with_embedding_differences(value);
+            }
+        };
     }
 
     Ok(control)
@@ -141,3 +272,73 @@ impl FromPyObject<'_> for PyMaxFeatures {
         }
     }
 }
+
+pub struct PyPenalty {
+    pub value: Penalty,
+}
+
+impl std::str::FromStr for PyPenalty {
+    type Err = PyErr;
+
+    fn from_str(value: &str) -> PyResult<Self> {
+        let value = match value {
+            "bic" => Penalty::BIC,
+            "aic" => Penalty::AIC,
+            "mbic" => Penalty::MBIC,
+            "hqc" => Penalty::HQC,
+            _ => {
+                return Err(PyErr::new::<exceptions::PyValueError, _>(format!(
+                "Unknown value for penalty: {}. Should be one of 'bic', 'aic', 'mbic' or 'hqc'.",
+                value
+            )))
+            }
+        };
+        Ok(PyPenalty { value })
+    }
+}
+
+pub struct PyPermutationType {
+    pub value: PermutationType,
+}
+
+impl std::str::FromStr for PyPermutationType {
+    type Err = PyErr;
+
+    fn from_str(value: &str) -> PyResult<Self> {
+        let value = match value {
+            "observations" => PermutationType::Observations,
+            "blocks" => PermutationType::Blocks,
+            "circular_shift" => PermutationType::CircularShift,
+            _ => {
+                return Err(PyErr::new::<exceptions::PyValueError, _>(format!(
+                "Unknown value for model_selection_permutation_type: {}. Should be one of 'observations', 'blocks' or 'circular_shift'.",
+                value
+            )))
+            }
+        };
+        Ok(PyPermutationType { value })
+    }
+}
+
+pub struct PyCalibration {
+    pub value: Calibration,
+}
+
+impl std::str::FromStr for PyCalibration {
+    type Err = PyErr;
+
+    fn from_str(value: &str) -> PyResult<Self> {
+        let value = match value {
+            "none" => Calibration::None,
+            "temperature" => Calibration::Temperature(None),
+            "isotonic" => Calibration::Isotonic,
+            _ => {
+                return Err(PyErr::new::<exceptions::PyValueError, _>(format!(
+                "Unknown value for calibration: {}. Should be one of 'none', 'temperature', 'isotonic' or a temperature.",
+                value
+            )))
+            }
+        };
+        Ok(PyCalibration { value })
+    }
+}
@@ -1,36 +1,254 @@
 mod control;
+mod observer;
 mod result;
 use crate::control::control_from_pyobj;
-use crate::result::{MyBinarySegmentationResult, MyOptimizerResult};
+use crate::observer::PyObserver;
+use crate::result::{MyBinarySegmentationResult, MyOptimizerResult, MyPanelResult};
 use ::changeforest::wrapper;
-use numpy::PyReadonlyArray2;
+use ndarray::{Array1, ArrayView1, ArrayView2};
+use numpy::{PyReadonlyArray1, PyReadonlyArray2, PyReadonlyArrayDyn, ToPyArray};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::{pyfunction, pymodule, wrap_pyfunction, Bound, PyModule, PyResult, Python};
-use pyo3::types::PyModuleMethods;
-use pyo3::Py;
-use pyo3::PyAny;
+use pyo3::types::{PyAnyMethods, PyModuleMethods};
+use pyo3::{Py, PyAny, PyErr};
+use std::cell::RefCell;
 
 #[allow(non_snake_case)] // Allow capital X for arrays.
 #[pyfunction(name = "changeforest")]
-#[pyo3(signature = (X, method=None, segmentation_type=None, control=None))]
+#[pyo3(signature = (X, method=None, segmentation_type=None, control=None, callback=None))]
 fn changeforest_fn(
     py: Python<'_>,
     X: PyReadonlyArray2<f64>,
     method: Option<String>,
     segmentation_type: Option<String>,
     control: Option<Py<PyAny>>,
+    callback: Option<Bound<'_, PyAny>>,
 ) -> PyResult<MyBinarySegmentationResult> {
-    let control = control_from_pyobj(py, control).unwrap();
+    let control = control_from_pyobj(py, control)?;
     let method = method.unwrap_or("random_forest".to_string());
     let segmentation_type = segmentation_type.unwrap_or("bs".to_string());
+
+    let mut observer = PyObserver::new(py, callback);
+    let result = wrapper::changeforest_with_observer(
+        &X.as_array(),
+        &method,
+        &segmentation_type,
+        &control,
+        &mut observer,
+    );
+
+    match (result, observer.error) {
+        (_, Some(err)) => Err(err),
+        (Ok(result), None) => Ok(MyBinarySegmentationResult { result }),
+        (Err(cancelled), None) => Err(PyRuntimeError::new_err(cancelled.to_string())),
+    }
+}
+
+#[allow(non_snake_case)] // Allow capital X for arrays.
+#[pyfunction(name = "changeforest_regression")]
+#[pyo3(signature = (X, y, segmentation_type=None, control=None))]
+fn changeforest_regression_fn(
+    py: Python<'_>,
+    X: PyReadonlyArray2<f64>,
+    y: PyReadonlyArray1<f64>,
+    segmentation_type: Option<String>,
+    control: Option<Py<PyAny>>,
+) -> PyResult<MyBinarySegmentationResult> {
+    let control = control_from_pyobj(py, control)?;
+    let segmentation_type = segmentation_type.unwrap_or("bs".to_string());
     Ok(MyBinarySegmentationResult {
-        result: wrapper::changeforest(&X.as_array(), &method, &segmentation_type, &control),
+        result: wrapper::changeforest_regression(
+            &X.as_array(),
+            &y.as_array(),
+            &segmentation_type,
+            &control,
+        ),
     })
 }
 
+#[allow(non_snake_case)] // Allow capital X for arrays.
+#[pyfunction(name = "changeforest_conditional")]
+#[pyo3(signature = (X, y, method=None, segmentation_type=None, control=None))]
+fn changeforest_conditional_fn(
+    py: Python<'_>,
+    X: PyReadonlyArray2<f64>,
+    y: PyReadonlyArrayDyn<f64>,
+    method: Option<String>,
+    segmentation_type: Option<String>,
+    control: Option<Py<PyAny>>,
+) -> PyResult<MyBinarySegmentationResult> {
+    let control = control_from_pyobj(py, control)?;
+    let method = method.unwrap_or("random_forest".to_string());
+    let segmentation_type = segmentation_type.unwrap_or("bs".to_string());
+
+    // Allow one-dimensional responses.
+    let y = y.as_array();
+    let y = match y.ndim() {
+        1 => {
+            let n = y.len();
+            y.into_shape_with_order((n, 1)).unwrap()
+        }
+        2 => y.into_dimensionality().unwrap(),
+        ndim => {
+            return Err(PyValueError::new_err(format!(
+                "y must be one- or two-dimensional. Got {ndim} dimensions."
+            )))
+        }
+    };
+
+    Ok(MyBinarySegmentationResult {
+        result: wrapper::changeforest_conditional(
+            &X.as_array(),
+            &y,
+            &method,
+            &segmentation_type,
+            &control,
+        ),
+    })
+}
+
+#[allow(non_snake_case)] // Allow capital X for arrays.
+#[pyfunction(name = "changeforest_autoregression")]
+#[pyo3(signature = (X, order=None, segmentation_type=None, control=None))]
+fn changeforest_autoregression_fn(
+    py: Python<'_>,
+    X: PyReadonlyArray2<f64>,
+    order: Option<usize>,
+    segmentation_type: Option<String>,
+    control: Option<Py<PyAny>>,
+) -> PyResult<MyBinarySegmentationResult> {
+    let control = control_from_pyobj(py, control)?;
+    let order = order.unwrap_or(1);
+    let segmentation_type = segmentation_type.unwrap_or("bs".to_string());
+    Ok(MyBinarySegmentationResult {
+        result: wrapper::changeforest_autoregression(
+            &X.as_array(),
+            order,
+            &segmentation_type,
+            &control,
+        ),
+    })
+}
+
+#[allow(non_snake_case)] // Allow capital X for arrays.
+#[pyfunction(name = "changeforest_categorical")]
+#[pyo3(signature = (X, segmentation_type=None, control=None))]
+fn changeforest_categorical_fn(
+    py: Python<'_>,
+    X: PyReadonlyArray2<i64>,
+    segmentation_type: Option<String>,
+    control: Option<Py<PyAny>>,
+) -> PyResult<MyBinarySegmentationResult> {
+    let control = control_from_pyobj(py, control)?;
+    let segmentation_type = segmentation_type.unwrap_or("bs".to_string());
+
+    let X = X.as_array();
+    if let Some(value) = X.iter().find(|x| **x < 0) {
+        return Err(PyValueError::new_err(format!(
+            "X must contain non-negative integer category codes. Got {value}."
+        )));
+    }
+    let X = X.mapv(|x| x as usize);
+
+    Ok(MyBinarySegmentationResult {
+        result: wrapper::changeforest_categorical(&X.view(), &segmentation_type, &control),
+    })
+}
+
+#[allow(non_snake_case)] // Allow capital X for arrays.
+#[pyfunction(name = "changeforest_classifier")]
+#[pyo3(signature = (X, classifier, segmentation_type=None, control=None))]
+fn changeforest_classifier_fn(
+    py: Python<'_>,
+    X: PyReadonlyArray2<f64>,
+    classifier: Bound<'_, PyAny>,
+    segmentation_type: Option<String>,
+    control: Option<Py<PyAny>>,
+) -> PyResult<MyBinarySegmentationResult> {
+    let control = control_from_pyobj(py, control)?;
+    let segmentation_type = segmentation_type.unwrap_or("bs".to_string());
+
+    // Exceptions raised by the classifier cannot be propagated through the rust
+    // `Classifier` trait. Store the first exception, skip all further calls and raise
+    // it once the segmentation has finished.
+    let error: RefCell<Option<PyErr>> = RefCell::new(None);
+    let f = |X: ArrayView2<'_, f64>, y: ArrayView1<'_, f64>| -> Array1<f64> {
+        if error.borrow().is_none() {
+            match call_classifier(py, &classifier, X, y) {
+                Ok(predictions) => return predictions,
+                Err(err) => {
+                    error.replace(Some(err));
+                }
+            }
+        }
+        Array1::from_elem(y.len(), 0.5)
+    };
+
+    let result = wrapper::changeforest_fn(&X.as_array(), f, &segmentation_type, &control);
+
+    match error.into_inner() {
+        Some(err) => Err(err),
+        None => Ok(MyBinarySegmentationResult { result }),
+    }
+}
+
+#[allow(non_snake_case)] // Allow capital X for arrays.
+#[pyfunction(name = "changeforest_panel")]
+#[pyo3(signature = (Xs, method=None, segmentation_type=None, control=None))]
+fn changeforest_panel_fn(
+    py: Python<'_>,
+    Xs: Vec<PyReadonlyArray2<f64>>,
+    method: Option<String>,
+    segmentation_type: Option<String>,
+    control: Option<Py<PyAny>>,
+) -> PyResult<MyPanelResult> {
+    if Xs.is_empty() {
+        return Err(PyValueError::new_err(
+            "Xs must contain at least one series.",
+        ));
+    }
+
+    let control = control_from_pyobj(py, control)?;
+    let method = method.unwrap_or("random_forest".to_string());
+    let segmentation_type = segmentation_type.unwrap_or("bs".to_string());
+    let Xs: Vec<ArrayView2<'_, f64>> = Xs.iter().map(|X| X.as_array()).collect();
+    Ok(MyPanelResult {
+        result: wrapper::changeforest_panel(&Xs, &method, &segmentation_type, &control),
+    })
+}
+
+#[allow(non_snake_case)] // Allow capital X for arrays.
+fn call_classifier(
+    py: Python<'_>,
+    classifier: &Bound<'_, PyAny>,
+    X: ArrayView2<'_, f64>,
+    y: ArrayView1<'_, f64>,
+) -> PyResult<Array1<f64>> {
+    let predictions = classifier.call1((X.to_pyarray(py), y.to_pyarray(py)))?;
+    let predictions = predictions.extract::<PyReadonlyArray1<f64>>()?;
+    let predictions = predictions.as_array();
+
+    if predictions.len() != y.len() {
+        return Err(PyValueError::new_err(format!(
+            "classifier must return one prediction per observation. Got {} predictions for {} observations.",
+            predictions.len(),
+            y.len()
+        )));
+    }
+    Ok(predictions.to_owned())
+}
+
 #[pymodule]
 fn changeforest(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(changeforest_fn, m)?)?;
+    m.add_function(wrap_pyfunction!(changeforest_regression_fn, m)?)?;
+    m.add_function(wrap_pyfunction!(changeforest_conditional_fn, m)?)?;
+    m.add_function(wrap_pyfunction!(changeforest_autoregression_fn, m)?)?;
+    m.add_function(wrap_pyfunction!(changeforest_categorical_fn, m)?)?;
+    m.add_function(wrap_pyfunction!(changeforest_classifier_fn, m)?)?;
+    m.add_function(wrap_pyfunction!(changeforest_panel_fn, m)?)?;
     m.add_class::<MyBinarySegmentationResult>()?;
     m.add_class::<MyOptimizerResult>()?;
+    m.add_class::<MyPanelResult>()?;
     Ok(())
 }
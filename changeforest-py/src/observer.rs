@@ -0,0 +1,98 @@
+use ::changeforest::observer::{Event, Signal, Stage};
+use ::changeforest::Observer;
+use pyo3::prelude::{Bound, PyResult, Python};
+use pyo3::types::{PyAnyMethods, PyDict, PyDictMethods};
+use pyo3::{PyAny, PyErr};
+
+/// Observer passing events as dictionaries to an optional python callback.
+///
+/// Pending signals are checked on each event, such that a `KeyboardInterrupt`
+/// (Ctrl-C) cancels the segmentation. The segmentation is also cancelled if the
+/// callback returns `False` or raises. The first exception is stored in `error`.
+pub struct PyObserver<'py> {
+    py: Python<'py>,
+    callback: Option<Bound<'py, PyAny>>,
+    pub error: Option<PyErr>,
+}
+
+impl<'py> PyObserver<'py> {
+    pub fn new(py: Python<'py>, callback: Option<Bound<'py, PyAny>>) -> Self {
+        PyObserver {
+            py,
+            callback,
+            error: None,
+        }
+    }
+
+    fn try_notify(&self, event: &Event) -> PyResult<Signal> {
+        self.py.check_signals()?;
+
+        if let Some(callback) = &self.callback {
+            let returned = callback.call1((event_to_dict(self.py, event)?,))?;
+            if let Ok(false) = returned.extract::<bool>() {
+                return Ok(Signal::Cancel);
+            }
+        }
+        Ok(Signal::Continue)
+    }
+}
+
+impl Observer for PyObserver<'_> {
+    fn notify(&mut self, event: &Event) -> Signal {
+        match self.try_notify(event) {
+            Ok(signal) => signal,
+            Err(err) => {
+                self.error.get_or_insert(err);
+                Signal::Cancel
+            }
+        }
+    }
+}
+
+fn event_to_dict<'py>(py: Python<'py>, event: &Event) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    match event {
+        Event::SegmentEvaluated(optimizer_result) => {
+            dict.set_item("event", "segment_evaluated")?;
+            dict.set_item("start", optimizer_result.start)?;
+            dict.set_item("stop", optimizer_result.stop)?;
+            dict.set_item("best_split", optimizer_result.best_split)?;
+            dict.set_item("max_gain", optimizer_result.max_gain)?;
+        }
+        Event::ModelSelection(optimizer_result, model_selection_result) => {
+            dict.set_item("event", "model_selection")?;
+            dict.set_item("start", optimizer_result.start)?;
+            dict.set_item("stop", optimizer_result.stop)?;
+            dict.set_item("best_split", optimizer_result.best_split)?;
+            dict.set_item("is_significant", model_selection_result.is_significant)?;
+            dict.set_item("p_value", model_selection_result.p_value)?;
+        }
+        Event::NodeSplit {
+            start,
+            stop,
+            split,
+            depth,
+        } => {
+            dict.set_item("event", "node_split")?;
+            dict.set_item("start", start)?;
+            dict.set_item("stop", stop)?;
+            dict.set_item("split", split)?;
+            dict.set_item("depth", depth)?;
+        }
+        Event::Progress(progress) => {
+            dict.set_item("event", "progress")?;
+            dict.set_item(
+                "stage",
+                match progress.stage {
+                    Stage::Segments => "segments",
+                    Stage::Tree => "tree",
+                },
+            )?;
+            dict.set_item("completed", progress.completed)?;
+            dict.set_item("total", progress.total)?;
+            dict.set_item("elapsed", progress.elapsed.as_secs_f64())?;
+            dict.set_item("eta", progress.eta.map(|eta| eta.as_secs_f64()))?;
+        }
+    }
+    Ok(dict)
+}
@@ -1,12 +1,20 @@
 // Wrap GainResult, OptimizerResult and BinarySegmentationResult.
 // See https://github.com/PyO3/pyo3/issues/287.
 
-use ::changeforest::{BinarySegmentationResult, ModelSelectionResult};
+use ::changeforest::{
+    BinarySegmentationResult, ChangePoint, ChangePointRanking, ModelSelectionResult, PanelResult,
+    SegmentSummary,
+};
 use changeforest::gain::GainResult;
 use changeforest::optimizer::OptimizerResult;
-use numpy::{PyArray1, PyArray2, ToPyArray};
+use ndarray::Array1;
+use numpy::{PyArray1, PyArray2, PyReadonlyArray2, ToPyArray};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use pyo3::types::PyModuleMethods;
+use pyo3::types::{PyDict, PyModuleMethods};
+
+/// Accessor of a per-feature statistic of a `SegmentSummary`.
+type SegmentStatistic = fn(&SegmentSummary) -> &Array1<f64>;
 
 #[pyclass(name = "ModelSelectionResult")]
 #[derive(Clone, Debug)]
@@ -214,17 +222,271 @@ impl MyBinarySegmentationResult {
         })
     }
 
+    #[getter]
+    fn scale<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<f64>>> {
+        self.result.scale.as_ref().map(|scale| scale.to_pyarray(py))
+    }
+
     fn split_points(&self) -> Vec<usize> {
         self.result.split_points()
     }
 
+    /// Split points as entries of `control.time_index`, if it was set. Datetimes are
+    /// converted to seconds since epoch.
+    ///
+    /// Use `split_points_at(time_index)` to obtain the original timestamps.
+    fn split_timestamps(&self) -> Option<Vec<f64>> {
+        self.result.split_timestamps()
+    }
+
+    /// Segment label of each observation.
+    fn labels<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        self.result.labels().to_pyarray(py)
+    }
+
+    /// Indicator of each observation being a split point.
+    fn indicator<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        self.result.indicator().to_pyarray(py)
+    }
+
+    /// Segment labels of the observations `0..n` of an original series, where row `i`
+    /// of `X` is observation `index[i]`. Observations that were not selected get the
+    /// label of the preceding selected observation.
+    fn reindex_labels<'py>(
+        &self,
+        py: Python<'py>,
+        index: Vec<usize>,
+        n: usize,
+    ) -> PyResult<Bound<'py, PyArray1<usize>>> {
+        self.check_index_length(index.len())?;
+        if index.windows(2).any(|window| window[0] >= window[1]) {
+            return Err(PyValueError::new_err("index must be strictly increasing."));
+        }
+        if index.last().is_some_and(|&last| last >= n) {
+            return Err(PyValueError::new_err(format!(
+                "index must have entries smaller than n = {n}."
+            )));
+        }
+        Ok(self.result.reindex_labels(&index, n).to_pyarray(py))
+    }
+
+    /// Split points mapped through `index`, e.g. timestamps of the observations.
+    fn split_points_at<'py>(&self, index: &Bound<'py, PyAny>) -> PyResult<Vec<Bound<'py, PyAny>>> {
+        self.check_index_length(index.len()?)?;
+        self.result
+            .split_points()
+            .iter()
+            .map(|&split| index.get_item(split - self.result.start))
+            .collect()
+    }
+
+    /// Summary statistics of the segments between consecutive split points.
+    ///
+    /// Returns a dictionary with keys `start`, `stop`, `length`, `mean_{j}`, `std_{j}`
+    /// and `median_{j}` for each feature `j`, `start_p_value`, `start_max_gain`,
+    /// `stop_p_value` and `stop_max_gain`, suitable for `pandas.DataFrame`.
+    #[allow(non_snake_case)] // Allow capital X for arrays.
+    fn segments_summary<'py>(
+        &self,
+        py: Python<'py>,
+        X: PyReadonlyArray2<f64>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let X = X.as_array();
+        if X.nrows() < self.result.stop {
+            return Err(PyValueError::new_err(format!(
+                "X must have at least {} rows. Got {}.",
+                self.result.stop,
+                X.nrows()
+            )));
+        }
+
+        let summary = self.result.segments_summary(&X);
+        let dict = PyDict::new(py);
+        dict.set_item("start", summary.iter().map(|s| s.start).collect::<Vec<_>>())?;
+        dict.set_item("stop", summary.iter().map(|s| s.stop).collect::<Vec<_>>())?;
+        dict.set_item(
+            "length",
+            summary.iter().map(|s| s.length).collect::<Vec<_>>(),
+        )?;
+        let statistics: [(&str, SegmentStatistic); 3] = [
+            ("mean", |s| &s.mean),
+            ("std", |s| &s.std),
+            ("median", |s| &s.median),
+        ];
+        for (name, statistic) in statistics {
+            for j in 0..X.ncols() {
+                let values: Vec<f64> = summary.iter().map(|s| statistic(s)[j]).collect();
+                dict.set_item(format!("{name}_{j}"), values)?;
+            }
+        }
+        dict.set_item(
+            "start_p_value",
+            summary.iter().map(|s| s.start_p_value).collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "start_max_gain",
+            summary.iter().map(|s| s.start_max_gain).collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "stop_p_value",
+            summary.iter().map(|s| s.stop_p_value).collect::<Vec<_>>(),
+        )?;
+        dict.set_item(
+            "stop_max_gain",
+            summary.iter().map(|s| s.stop_max_gain).collect::<Vec<_>>(),
+        )?;
+        Ok(dict)
+    }
+
+    /// Nodes of the tree together with their depth, as a list of `(depth, node)`
+    /// tuples in preorder or, if `breadth_first`, in breadth-first order.
+    #[pyo3(signature = (breadth_first=false))]
+    fn iter_nodes(&self, breadth_first: bool) -> Vec<(usize, MyBinarySegmentationResult)> {
+        let nodes = if breadth_first {
+            self.result.iter_nodes_breadth_first()
+        } else {
+            self.result.iter_nodes()
+        };
+        nodes
+            .map(|(depth, node)| {
+                (
+                    depth,
+                    MyBinarySegmentationResult {
+                        result: node.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Significant change points in the order of `split_points()`.
+    ///
+    /// Returns a dictionary with keys `split`, `depth`, `start`, `stop`, `max_gain` and
+    /// `p_value`, suitable for `pandas.DataFrame`.
+    fn change_points<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        change_points_dict(py, &self.result.change_points())
+    }
+
+    /// Significant change points, most important first.
+    ///
+    /// `ranking` is one of `"depth"`, `"max_gain"` or `"p_value"`. Returns a dictionary
+    /// as `change_points()`.
+    #[pyo3(signature = (ranking="depth"))]
+    fn ranked_change_points<'py>(
+        &self,
+        py: Python<'py>,
+        ranking: &str,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let ranking = match ranking {
+            "depth" => ChangePointRanking::Depth,
+            "max_gain" => ChangePointRanking::MaxGain,
+            "p_value" => ChangePointRanking::PValue,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "ranking must be one of 'depth', 'max_gain' or 'p_value'. Got {ranking}."
+                )))
+            }
+        };
+        change_points_dict(py, &self.result.ranked_change_points(ranking))
+    }
+
     fn __repr__(&self) -> PyResult<String> {
         Ok(format!("{}", self.result))
     }
 }
 
+fn change_points_dict<'py>(
+    py: Python<'py>,
+    change_points: &[ChangePoint],
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item(
+        "split",
+        change_points.iter().map(|c| c.split).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "depth",
+        change_points.iter().map(|c| c.depth).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "start",
+        change_points.iter().map(|c| c.start).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "stop",
+        change_points.iter().map(|c| c.stop).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "max_gain",
+        change_points.iter().map(|c| c.max_gain).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "p_value",
+        change_points.iter().map(|c| c.p_value).collect::<Vec<_>>(),
+    )?;
+    Ok(dict)
+}
+
+impl MyBinarySegmentationResult {
+    fn check_index_length(&self, length: usize) -> PyResult<()> {
+        let n = self.result.stop - self.result.start;
+        if length != n {
+            return Err(PyValueError::new_err(format!(
+                "index must have one entry per observation. Got {length} entries for {n} observations."
+            )));
+        }
+        Ok(())
+    }
+}
+
 #[pymodule]
 fn my_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<MyBinarySegmentationResult>()?;
     Ok(())
 }
+
+#[pyclass(name = "PanelResult")]
+#[derive(Clone, Debug)]
+pub struct MyPanelResult {
+    pub result: PanelResult,
+}
+
+#[pymethods]
+impl MyPanelResult {
+    /// Segmentation of the panel, with the shared change points as split points.
+    #[getter]
+    fn result(&self) -> MyBinarySegmentationResult {
+        MyBinarySegmentationResult {
+            result: self.result.result.clone(),
+        }
+    }
+
+    /// For each shared split point, the model selection result of each series. `None`
+    /// if the series does not cover the split point.
+    #[getter]
+    fn series_model_selection_results(&self) -> Vec<Vec<Option<MyModelSelectionResult>>> {
+        self.result
+            .series_model_selection_results
+            .iter()
+            .map(|results| {
+                results
+                    .iter()
+                    .map(|result| {
+                        result.as_ref().map(|result| MyModelSelectionResult {
+                            result: result.clone(),
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn split_points(&self) -> Vec<usize> {
+        self.result.split_points()
+    }
+
+    /// For each shared split point, whether it is significant in each series.
+    fn series_is_significant(&self) -> Vec<Vec<bool>> {
+        self.result.series_is_significant()
+    }
+}
@@ -1,10 +1,33 @@
+import signal
+
 import numpy as np
 import pytest
 
-from changeforest import Control, changeforest
+from changeforest import (
+    Control,
+    changeforest,
+    changeforest_autoregression,
+    changeforest_categorical,
+    changeforest_classifier,
+    changeforest_conditional,
+    changeforest_panel,
+    changeforest_regression,
+    cross_fit,
+    tqdm_callback,
+)
 
 
-@pytest.mark.parametrize("method", ["knn", "change_in_mean", "random_forest"])
+@pytest.mark.parametrize(
+    "method",
+    [
+        "knn",
+        "change_in_mean",
+        "change_in_location",
+        "random_forest",
+        "extra_trees",
+        "knn+random_forest",
+    ],
+)
 @pytest.mark.parametrize("segmentation_type", ["sbs", "wbs", "bs"])
 def test_changeforest(iris_dataset, method, segmentation_type):
     result = changeforest(
@@ -16,6 +39,171 @@ def test_changeforest(iris_dataset, method, segmentation_type):
     np.testing.assert_array_equal(result.split_points(), [50, 100])
 
 
+@pytest.mark.parametrize(
+    "method", ["change_in_poisson_rate", "change_in_negative_binomial_rate"]
+)
+def test_changeforest_counts(method):
+    rng = np.random.default_rng(0)
+    X = rng.poisson(2, (300, 2)).astype(np.float64)
+    X[100:200, 0] = rng.poisson(6, 100)
+
+    result = changeforest(X, method, "bs")
+    split_points = result.split_points()
+    assert len(split_points) == 2
+    assert abs(split_points[0] - 100) <= 5
+    assert abs(split_points[1] - 200) <= 5
+
+
+def test_changeforest_counts_invalid_input(iris_dataset):
+    with pytest.raises(BaseException, match="non-negative integer counts"):
+        changeforest(iris_dataset, "change_in_poisson_rate", "bs")
+
+
+@pytest.mark.parametrize("order", [1, 2])
+def test_changeforest_autoregression(order):
+    rng = np.random.default_rng(0)
+    noise = rng.normal(0, 1, 300)
+    X = np.zeros((300, 1))
+    for t in range(1, 300):
+        coefficient = -0.8 if 100 <= t < 200 else 0.8
+        X[t, 0] = coefficient * X[t - 1, 0] + noise[t]
+
+    result = changeforest_autoregression(X, order, "bs")
+    split_points = result.split_points()
+    assert len(split_points) == 2
+    assert abs(split_points[0] - 100) <= 5
+    assert abs(split_points[1] - 200) <= 5
+
+
+@pytest.mark.parametrize("segmentation_type", ["sbs", "wbs", "bs"])
+def test_changeforest_categorical(segmentation_type):
+    rng = np.random.default_rng(0)
+    X = rng.choice(3, (300, 2), p=[0.8, 0.1, 0.1])
+    X[100:200, 0] = rng.choice(3, 100, p=[0.1, 0.1, 0.8])
+
+    result = changeforest_categorical(X, segmentation_type)
+    split_points = result.split_points()
+    assert len(split_points) == 2
+    assert abs(split_points[0] - 100) <= 5
+    assert abs(split_points[1] - 200) <= 5
+
+
+def test_changeforest_categorical_negative_codes():
+    with pytest.raises(ValueError, match="non-negative integer category codes"):
+        changeforest_categorical(np.array([[0], [-1], [1]]))
+
+
+@pytest.mark.parametrize("segmentation_type", ["sbs", "wbs", "bs"])
+def test_changeforest_regression(segmentation_type):
+    rng = np.random.default_rng(0)
+    X = rng.normal(0, 1, (200, 2))
+    y = X[:, 0] + 0.1 * rng.normal(0, 1, 200)
+    y[100:] = -X[100:, 0] + 0.1 * rng.normal(0, 1, 100)
+
+    result = changeforest_regression(
+        X, y, segmentation_type, Control(minimal_relative_segment_length=0.1)
+    )
+    np.testing.assert_array_equal(result.split_points(), [100])
+
+
+@pytest.mark.parametrize("method", ["knn", "random_forest"])
+@pytest.mark.parametrize("y_shape", [(300,), (300, 1)])
+def test_changeforest_conditional(method, y_shape):
+    # The distribution of X changes at 100, the distribution of y | X at 200.
+    rng = np.random.default_rng(0)
+    X = rng.normal(0, 1, (300, 2))
+    X[100:, 0] += 3
+    y = X[:, 1] + 0.5 * rng.normal(0, 1, 300)
+    y[200:] += 2
+
+    result = changeforest_conditional(
+        X,
+        y.reshape(y_shape),
+        method,
+        "bs",
+        Control(minimal_relative_segment_length=0.1),
+    )
+    assert len(result.split_points()) == 1
+    assert abs(result.split_points()[0] - 200) <= 5
+
+
+
+def _nearest_centroid(X, y):
+    # Leave-one-out nearest centroid classifier.
+    n_ones = y.sum()
+    n_zeros = len(y) - n_ones
+    sum_ones = X.T @ y
+    sum_zeros = X.sum(axis=0) - sum_ones
+
+    is_one = (y == 1)[:, np.newaxis]
+    mean_ones = np.where(is_one, (sum_ones - X) / (n_ones - 1), sum_ones / n_ones)
+    mean_zeros = np.where(
+        is_one, sum_zeros / n_zeros, (sum_zeros - X) / (n_zeros - 1)
+    )
+    distance_ones = ((X - mean_ones) ** 2).sum(axis=1)
+    distance_zeros = ((X - mean_zeros) ** 2).sum(axis=1)
+    return 1 / (1 + np.exp(distance_ones - distance_zeros))
+
+
+def test_changeforest_classifier_callable(iris_dataset):
+    result = changeforest_classifier(iris_dataset, _nearest_centroid, "bs")
+    np.testing.assert_array_equal(result.split_points(), [50, 100])
+
+
+def test_changeforest_classifier_estimator(iris_dataset):
+    ensemble = pytest.importorskip("sklearn.ensemble")
+
+    result = changeforest_classifier(
+        iris_dataset,
+        ensemble.RandomForestClassifier(n_estimators=20, random_state=0),
+        "bs",
+        control=Control(minimal_relative_segment_length=0.1),
+    )
+    split_points = result.split_points()
+    assert len(split_points) == 2
+    assert abs(split_points[0] - 50) <= 5
+    assert abs(split_points[1] - 100) <= 5
+
+
+class _MeanEstimator:
+    # Predicts the share of label 1 in the training data.
+    def fit(self, X, y):
+        self.classes_ = np.unique(y)
+        self.mean_ = y.mean()
+        return self
+
+    def predict_proba(self, X):
+        return np.tile([1 - self.mean_, self.mean_], (len(X), 1))
+
+
+def test_cross_fit_single_class():
+    X = np.zeros((10, 1))
+    y = np.zeros(10)
+    y[3] = 1
+    predictions = cross_fit(_MeanEstimator(), n_folds=2, seed=0)(X, y)
+
+    rng = np.random.default_rng(0)
+    folds = rng.permutation(10) % 2
+    # The training folds of the fold containing observation 3 only have label 0.
+    test = folds == folds[3]
+    np.testing.assert_allclose(predictions[test], 1 / 7)
+    np.testing.assert_allclose(predictions[~test], 0.2)
+    assert (predictions > 0).all()
+
+
+def test_changeforest_classifier_raises(iris_dataset):
+    def classifier(X, y):
+        raise ValueError("Something went wrong.")
+
+    with pytest.raises(ValueError, match="Something went wrong."):
+        changeforest_classifier(iris_dataset, classifier, "bs")
+
+
+def test_changeforest_classifier_wrong_length(iris_dataset):
+    with pytest.raises(ValueError, match="one prediction per observation"):
+        changeforest_classifier(iris_dataset, lambda X, y: np.zeros(3), "bs")
+
+
 def test_changeforest_repr(iris_dataset):
     result = changeforest(iris_dataset, "random_forest", "bs")
     assert (
@@ -69,3 +257,191 @@ def test_changeforest_repr_segments2(iris_dataset):
      °--(102, 150]         136    1.114    0.36\
 """  # noqa: W291
     )
+
+
+def test_changeforest_segments_summary(iris_dataset):
+    result = changeforest(
+        iris_dataset,
+        "change_in_mean",
+        "bs",
+        control=Control(minimal_relative_segment_length=0.1),
+    )
+    summary = result.segments_summary(iris_dataset)
+
+    assert summary["start"] == [0, 50, 100]
+    assert summary["stop"] == [50, 100, 150]
+    assert summary["length"] == [50, 50, 50]
+    segments = [iris_dataset[0:50], iris_dataset[50:100], iris_dataset[100:150]]
+    for j in range(4):
+        np.testing.assert_allclose(
+            summary[f"mean_{j}"], [segment[:, j].mean() for segment in segments]
+        )
+        np.testing.assert_allclose(
+            summary[f"std_{j}"], [segment[:, j].std() for segment in segments]
+        )
+        np.testing.assert_allclose(
+            summary[f"median_{j}"], [np.median(segment[:, j]) for segment in segments]
+        )
+    assert summary["start_max_gain"][0] is None
+    assert summary["stop_max_gain"][-1] is None
+    assert summary["stop_max_gain"][0] == summary["start_max_gain"][1]
+
+
+def test_changeforest_segments_summary_invalid_input(iris_dataset):
+    result = changeforest(iris_dataset, "change_in_mean", "bs")
+    with pytest.raises(ValueError, match="at least 150 rows"):
+        result.segments_summary(iris_dataset[:100])
+
+
+def test_changeforest_labels(iris_dataset):
+    result = changeforest(
+        iris_dataset,
+        "change_in_mean",
+        "bs",
+        control=Control(minimal_relative_segment_length=0.1),
+    )
+
+    np.testing.assert_array_equal(result.labels(), np.repeat([0, 1, 2], 50))
+    np.testing.assert_array_equal(np.flatnonzero(result.indicator()), [50, 100])
+
+    # Every other observation of an original series of length 300 was selected.
+    labels = result.reindex_labels(list(range(0, 300, 2)), 300)
+    np.testing.assert_array_equal(labels, np.repeat([0, 1, 2], 100))
+
+    timestamps = np.datetime64("2020-01-01") + np.arange(150)
+    assert result.split_points_at(timestamps) == [timestamps[50], timestamps[100]]
+
+
+@pytest.mark.parametrize(
+    "index, n, match",
+    [
+        (list(range(10)), 150, "one entry per observation"),
+        (list(range(149, -1, -1)), 150, "strictly increasing"),
+        (list(range(150)), 100, "smaller than n"),
+    ],
+)
+def test_changeforest_reindex_labels_invalid_input(iris_dataset, index, n, match):
+    result = changeforest(iris_dataset, "change_in_mean", "bs")
+    with pytest.raises(ValueError, match=match):
+        result.reindex_labels(index, n)
+
+
+@pytest.mark.parametrize(
+    "method, expected",
+    [
+        ("change_in_mean", [[True, True, True], [False, True, False]]),
+        ("random_forest", [[True, True, True], [True, True, False]]),
+        ("knn", [[True, True, True], [True, True, False]]),
+    ],
+)
+def test_changeforest_panel(iris_dataset, method, expected):
+    # The third series ends before the second change point.
+    Xs = [iris_dataset[:, :2], iris_dataset[:, 2:], iris_dataset[:80]]
+    result = changeforest_panel(Xs, method, "bs", Control())
+    assert result.split_points() == [50, 100]
+    assert result.result.split_points() == [50, 100]
+    assert result.series_is_significant() == expected
+    assert result.series_model_selection_results[1][2] is None
+
+
+def test_changeforest_panel_empty():
+    with pytest.raises(ValueError, match="at least one series"):
+        changeforest_panel([])
+
+
+def test_changeforest_iter_nodes(iris_dataset):
+    result = changeforest(iris_dataset, "random_forest", "bs")
+
+    nodes = [(depth, node.start, node.stop) for depth, node in result.iter_nodes()]
+    assert nodes == [(0, 0, 150), (1, 0, 50), (1, 50, 150), (2, 50, 100), (2, 100, 150)]
+
+    nodes = result.iter_nodes(breadth_first=True)
+    assert [depth for depth, _ in nodes] == [0, 1, 1, 2, 2]
+
+
+@pytest.mark.parametrize("ranking", ["depth", "max_gain", "p_value"])
+def test_changeforest_ranked_change_points(iris_dataset, ranking):
+    result = changeforest(iris_dataset, "random_forest", "bs")
+
+    change_points = result.change_points()
+    assert change_points["split"] == [50, 100]
+    assert change_points["depth"] == [0, 1]
+    assert change_points["start"] == [0, 50]
+    assert change_points["stop"] == [150, 150]
+    assert change_points["p_value"] == [0.005, 0.005]
+
+    ranked = result.ranked_change_points(ranking)
+    assert ranked["split"] == [50, 100]
+    assert ranked["max_gain"][0] > ranked["max_gain"][1]
+
+    with pytest.raises(ValueError, match="ranking must be one of"):
+        result.ranked_change_points("foo")
+
+
+def test_changeforest_callback(iris_dataset):
+    events = []
+    result = changeforest(iris_dataset, "random_forest", "bs", callback=events.append)
+    np.testing.assert_array_equal(result.split_points(), [50, 100])
+
+    node_splits = [
+        (e["start"], e["stop"], e["split"], e["depth"])
+        for e in events
+        if e["event"] == "node_split"
+    ]
+    assert node_splits == [(0, 150, 50, 0), (50, 150, 100, 1)]
+
+    progress = [e for e in events if e["event"] == "progress"]
+    assert [e["stage"] for e in progress] == ["tree"] * 3
+    assert [e["completed"] for e in progress] == [50, 100, 150]
+    assert all(e["total"] == 150 for e in progress)
+    assert progress[-1]["eta"] == 0
+
+
+def test_changeforest_callback_segments(iris_dataset):
+    events = []
+    changeforest(
+        iris_dataset,
+        "random_forest",
+        "sbs",
+        control=Control(minimal_relative_segment_length=0.1),
+        callback=events.append,
+    )
+    progress = [e for e in events if e["event"] == "progress"]
+    assert progress[0]["stage"] == "segments"
+    assert progress[-1]["stage"] == "tree"
+
+    segments = [e for e in progress if e["stage"] == "segments"]
+    assert segments[-1]["completed"] == segments[-1]["total"]
+
+
+def test_changeforest_callback_cancel(iris_dataset):
+    def callback(event):
+        return event["event"] != "node_split"
+
+    with pytest.raises(RuntimeError, match="cancelled"):
+        changeforest(iris_dataset, "random_forest", "bs", callback=callback)
+
+
+def test_changeforest_callback_raises(iris_dataset):
+    def callback(event):
+        raise ValueError("foo")
+
+    with pytest.raises(ValueError, match="foo"):
+        changeforest(iris_dataset, "random_forest", "bs", callback=callback)
+
+
+def test_changeforest_keyboard_interrupt(iris_dataset):
+    def callback(event):
+        if event["event"] == "node_split":
+            signal.raise_signal(signal.SIGINT)
+
+    with pytest.raises(KeyboardInterrupt):
+        changeforest(iris_dataset, "random_forest", "bs", callback=callback)
+
+
+def test_changeforest_tqdm_callback(iris_dataset):
+    pytest.importorskip("tqdm")
+    result = changeforest(
+        iris_dataset, "random_forest", "sbs", callback=tqdm_callback(disable=True)
+    )
+    np.testing.assert_array_equal(result.split_points(), [50, 100])
@@ -28,6 +28,11 @@ from changeforest import Control, changeforest
             {"minimal_gain_to_split": None},
             [50, 100],
         ),  # log(150) * 4 / 150
+        # penalty
+        ("iris", "bs", "change_in_mean", {"penalty": "bic"}, [50, 100]),
+        ("iris", "bs", "change_in_mean", {"penalty": "aic"}, [50, 100]),
+        ("iris", "bs", "change_in_mean", {"penalty": "mbic"}, [50, 100]),
+        ("iris", "bs", "change_in_mean", {"penalty": "HQC"}, [50, 100]),
         # model_selection_alpha
         ("iris", "bs", "knn", {"model_selection_alpha": 0.001}, []),
         ("iris", "bs", "knn", {"model_selection_alpha": 0.05}, [50, 100]),
@@ -49,6 +54,54 @@ from changeforest import Control, changeforest
             [49],
         ),
         ("iris", "bs", "random_forest", {"model_selection_n_permutations": 10}, []),
+        # tree_scaling
+        ("iris", "bs", "random_forest", {"tree_scaling": True}, [50, 100]),
+        ("iris", "bs", "extra_trees", {"tree_scaling": True}, [50, 100]),
+        # two_step_search
+        ("iris", "bs", "random_forest", {"two_step_search_n_guesses": 5}, [50, 100]),
+        (
+            "iris",
+            "bs",
+            "random_forest",
+            {"two_step_search_bracketing": True, "two_step_search_n_refinements": 3},
+            [50, 100],
+        ),
+        # warm_start
+        ("iris", "bs", "random_forest", {"warm_start": True}, [50, 100]),
+        ("iris", "bs", "knn", {"warm_start": True}, [50, 100]),
+        # model_selection_permutation_type
+        (
+            "iris",
+            "bs",
+            "random_forest",
+            {"model_selection_permutation_type": "blocks"},
+            [50, 100],
+        ),
+        (
+            "iris",
+            "bs",
+            "random_forest",
+            {
+                "model_selection_permutation_type": "blocks",
+                "model_selection_block_length": 10,
+            },
+            [50, 100],
+        ),
+        (
+            "iris",
+            "bs",
+            "random_forest",
+            {"model_selection_permutation_type": "circular_shift"},
+            [50, 100],
+        ),
+        # likelihood_shrinkage
+        ("iris", "bs", "random_forest", {"likelihood_shrinkage": 0.05}, [50, 100]),
+        # calibration
+        ("iris", "bs", "random_forest", {"calibration": 2.0}, [50, 100]),
+        ("iris", "bs", "random_forest", {"calibration": "temperature"}, [50, 100]),
+        ("iris", "bs", "random_forest", {"calibration": "isotonic"}, [52, 100]),
+        ("iris", "bs", "knn", {"calibration": "isotonic"}, [50, 100]),
+        ("iris", "bs", "knn", {"calibration": None}, [50, 100]),
     ],
 )
 def test_control_model_selection_parameters(
@@ -148,3 +201,103 @@ def test_control_segments():
 
     with pytest.raises(SyntaxError):
         Control(forbidden_segments=[2, 3])
+
+
+def test_control_penalty(iris_dataset):
+    with pytest.raises(ValueError):
+        changeforest(iris_dataset, "change_in_mean", "bs", Control(penalty="foo"))
+
+    with pytest.raises(TypeError):
+        Control(penalty=1)
+
+
+def test_control_standardize(iris_dataset):
+    result = changeforest(
+        iris_dataset, "change_in_mean", "bs", Control(standardize=True)
+    )
+    np.testing.assert_array_equal(result.split_points(), [50, 100])
+    assert result.scale.shape == (4,)
+    assert (result.scale > 0).all()
+
+    result = changeforest(iris_dataset, "change_in_mean", "bs", Control())
+    assert result.scale is None
+
+
+@pytest.mark.parametrize("method", ["knn", "random_forest"])
+def test_control_embedding(method):
+    # AR(1) with coefficient 0.7 before and -0.7 after 150. The marginal distribution
+    # is unchanged.
+    rng = np.random.default_rng(0)
+    noise = rng.normal(0, 1, 300)
+    X = np.zeros((300, 1))
+    for t in range(1, 300):
+        coefficient = 0.7 if t < 150 else -0.7
+        X[t, 0] = coefficient * X[t - 1, 0] + noise[t]
+
+    control = Control(
+        minimal_relative_segment_length=0.1,
+        embedding_lags=1,
+        embedding_windows=[5],
+        embedding_differences=True,
+    )
+    assert control.embedding_windows == [5]
+
+    result = changeforest(X, method, "bs", control)
+    assert any(abs(split - 150) <= 5 for split in result.split_points())
+
+
+def test_control_permutation_type(iris_dataset):
+    with pytest.raises(ValueError):
+        changeforest(
+            iris_dataset,
+            "random_forest",
+            "bs",
+            Control(model_selection_permutation_type="foo"),
+        )
+
+    with pytest.raises(TypeError):
+        Control(model_selection_permutation_type=1)
+
+
+def test_control_time_index(iris_dataset):
+    time_index = np.datetime64("2024-01-01") + np.arange(150) * np.timedelta64(1, "h")
+    start = np.datetime64("2024-01-01").astype("datetime64[s]").astype(np.int64)
+
+    result = changeforest(
+        iris_dataset,
+        "random_forest",
+        "bs",
+        Control(
+            time_index=time_index, minimal_segment_duration=np.timedelta64(40, "h")
+        ),
+    )
+    assert result.split_points() == [50, 100]
+    assert result.split_timestamps() == [start + 50 * 3600.0, start + 100 * 3600.0]
+    assert result.split_points_at(time_index) == list(time_index[[50, 100]])
+
+    # The first and last observations of each segment are at least 60 hours apart.
+    result = changeforest(
+        iris_dataset,
+        "random_forest",
+        "bs",
+        Control(
+            time_index=time_index, minimal_segment_duration=np.timedelta64(60, "h")
+        ),
+    )
+    boundaries = np.array([0] + result.split_points() + [150])
+    assert np.all(np.diff(boundaries) > 60)
+
+    result = changeforest(iris_dataset, "random_forest", "bs", Control())
+    assert result.split_timestamps() is None
+
+
+def test_control_forbidden_time_segments(iris_dataset):
+    time_index = np.arange(150) / 10
+    result = changeforest(
+        iris_dataset,
+        "random_forest",
+        "bs",
+        Control(time_index=time_index, forbidden_time_segments=[(9.0, 11.0)]),
+    )
+    assert 100 not in result.split_points()
+    assert result.split_timestamps() == [time_index[s] for s in result.split_points()]
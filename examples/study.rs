@@ -0,0 +1,38 @@
+// Calibration and power study of classifier-based methods.
+//
+// cargo run --release --example study -- [n_replications] [output.csv|output.json]
+use changeforest::simulate;
+use changeforest::study::Study;
+use std::env;
+use std::fs;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let n_replications = args.get(1).map_or(20, |arg| arg.parse().unwrap());
+
+    let result = Study::new(n_replications)
+        .with_simulation("null", simulate::mean_shift(500, &[], 5, 0., 0))
+        .with_simulation(
+            "null_ar",
+            simulate::mean_shift(500, &[], 5, 0., 0).with_ar_coefficient(0.5),
+        )
+        .with_simulation("mean_shift", simulate::mean_shift(500, &[250], 5, 0.5, 0))
+        .with_simulation(
+            "covariance_change",
+            simulate::covariance_change(500, &[250], 5, 0.7, 0),
+        )
+        .with_simulation(
+            "distribution_switch",
+            simulate::distribution_switch(500, &[250], 5, 0),
+        )
+        .with_methods(&["knn", "random_forest"])
+        .with_segmentation_types(&["bs"])
+        .with_margin(10)
+        .run();
+
+    match args.get(2) {
+        Some(path) if path.ends_with(".json") => fs::write(path, result.to_json()).unwrap(),
+        Some(path) => fs::write(path, result.to_csv()).unwrap(),
+        None => print!("{}", result.to_csv()),
+    }
+}
@@ -1,5 +1,10 @@
+use crate::observer::{Event, Progress, Stage};
 use crate::optimizer::OptimizerResult;
+use crate::utils::median;
 use crate::{ModelSelectionResult, Segmentation};
+use ndarray::{s, Array1, ArrayView2, Axis};
+use std::collections::VecDeque;
+use std::time::Instant;
 
 pub struct BinarySegmentationTree {
     pub start: usize,
@@ -54,22 +59,47 @@ impl BinarySegmentationTree {
     /// `right` until segments are smaller then the minimal segment length
     /// (`n * control.minimal_relative_segment_length`) or the `OptimizerResult` is no
     /// longer significant.
+    ///
+    /// Splits and progress are reported to the observer of the `segmentation`. If it
+    /// cancels, no further nodes are split.
     pub fn grow(&mut self, segmentation: &mut Segmentation) {
+        self.grow_at_depth(segmentation, 0, Instant::now());
+    }
+
+    fn grow_at_depth(&mut self, segmentation: &mut Segmentation, depth: usize, started: Instant) {
+        if segmentation.is_cancelled() {
+            return;
+        }
+
         if let Ok(optimizer_result) = segmentation.find_best_split(self.start, self.stop) {
             self.model_selection_result = segmentation.model_selection(&optimizer_result);
 
-            if self.model_selection_result.is_significant {
+            if self.model_selection_result.is_significant && !segmentation.is_cancelled() {
+                segmentation.notify(&Event::NodeSplit {
+                    start: self.start,
+                    stop: self.stop,
+                    split: optimizer_result.best_split,
+                    depth,
+                });
+
                 let mut left = self.new_left(optimizer_result.best_split);
-                left.grow(segmentation);
+                left.grow_at_depth(segmentation, depth + 1, started);
                 self.left = Some(left);
 
                 let mut right = self.new_right(optimizer_result.best_split);
-                right.grow(segmentation);
+                right.grow_at_depth(segmentation, depth + 1, started);
                 self.right = Some(right);
             }
 
             self.optimizer_result = Some(optimizer_result);
         }
+
+        if self.left.is_none() {
+            // Leaves are finished from left to right, such that all observations
+            // before `self.stop` belong to finished leaves.
+            let progress = Progress::new(Stage::Tree, self.stop, self.n, started);
+            segmentation.notify(&Event::Progress(progress));
+        }
     }
 }
 
@@ -83,6 +113,10 @@ pub struct BinarySegmentationResult {
     pub left: Option<Box<BinarySegmentationResult>>,
     pub right: Option<Box<BinarySegmentationResult>>,
     pub segments: Option<Vec<OptimizerResult>>,
+    /// Estimated noise level of each feature if features were standardized.
+    pub scale: Option<Array1<f64>>,
+    /// Time index of the observations if `control.time_index` was set.
+    pub time_index: Option<Vec<f64>>,
 }
 
 impl BinarySegmentationResult {
@@ -103,6 +137,8 @@ impl BinarySegmentationResult {
             left,
             right,
             segments: None,
+            scale: None,
+            time_index: None,
         }
     }
 
@@ -126,19 +162,341 @@ impl BinarySegmentationResult {
         split_points
     }
 
+    /// Segment label of each observation in `[start, stop)`.
+    ///
+    /// Labels are `0` for observations before the first split point, `1` for
+    /// observations between the first and second split point and so on.
+    pub fn labels(&self) -> Array1<usize> {
+        let mut labels = Array1::zeros(self.stop - self.start);
+        let mut boundaries = self.split_points();
+        boundaries.push(self.stop);
+        let mut start = self.start;
+        for (label, stop) in boundaries.into_iter().enumerate() {
+            labels
+                .slice_mut(s![(start - self.start)..(stop - self.start)])
+                .fill(label);
+            start = stop;
+        }
+        labels
+    }
+
+    /// Indicator of each observation in `[start, stop)` being a split point, that is,
+    /// the first observation of a new segment.
+    pub fn indicator(&self) -> Array1<bool> {
+        let mut indicator = Array1::from_elem(self.stop - self.start, false);
+        for split in self.split_points() {
+            indicator[split - self.start] = true;
+        }
+        indicator
+    }
+
+    /// Segment labels of the observations `0..n` of an original series, from which
+    /// the rows of `X` were selected.
+    ///
+    /// Row `i` of `X` corresponds to observation `index[i]` of the original series.
+    /// Observations that were not selected get the label of the preceding selected
+    /// observation, or `0` if there is none. Panics if `index` is not strictly
+    /// increasing, does not have one entry per observation or has entries `>= n`.
+    pub fn reindex_labels(&self, index: &[usize], n: usize) -> Array1<usize> {
+        if index.len() != self.stop - self.start {
+            panic!(
+                "index must have one entry per observation. Got {} entries for {} observations.",
+                index.len(),
+                self.stop - self.start
+            );
+        }
+        if index.windows(2).any(|window| window[0] >= window[1]) {
+            panic!("index must be strictly increasing.");
+        }
+        if index.last().is_some_and(|&last| last >= n) {
+            panic!("index must have entries smaller than n = {n}.");
+        }
+
+        let labels = self.labels();
+        let mut reindexed = Array1::zeros(n);
+        let mut idx = 0;
+        for position in 0..n {
+            while idx + 1 < index.len() && index[idx + 1] <= position {
+                idx += 1;
+            }
+            if idx < index.len() && index[idx] <= position {
+                reindexed[position] = labels[idx];
+            }
+        }
+        reindexed
+    }
+
+    /// Split points mapped through `index`, e.g. timestamps of the observations.
+    ///
+    /// Row `i` of `X` corresponds to `index[i]`. Panics if `index` does not have one
+    /// entry per observation.
+    pub fn split_points_at<T: Clone>(&self, index: &[T]) -> Vec<T> {
+        if index.len() != self.stop - self.start {
+            panic!(
+                "index must have one entry per observation. Got {} entries for {} observations.",
+                index.len(),
+                self.stop - self.start
+            );
+        }
+
+        self.split_points()
+            .iter()
+            .map(|&split| index[split - self.start].clone())
+            .collect()
+    }
+
+    /// Split points as entries of the time index, if available.
+    ///
+    /// A split point `s` is reported as the time of observation `s`, the first
+    /// observation of the new segment.
+    pub fn split_timestamps(&self) -> Option<Vec<f64>> {
+        self.time_index
+            .as_ref()
+            .map(|time_index| self.split_points_at(&time_index[self.start..self.stop]))
+    }
+
+    /// Summary statistics of the segments between consecutive split points.
+    ///
+    /// `X` is the time series the result was fitted on. Panics if it has fewer than
+    /// `stop` rows.
+    pub fn segments_summary(&self, X: &ArrayView2<'_, f64>) -> Vec<SegmentSummary> {
+        if X.nrows() < self.stop {
+            panic!(
+                "X must have at least {} rows. Got {}.",
+                self.stop,
+                X.nrows()
+            );
+        }
+
+        let change_points = self.change_points();
+        let mut boundaries = vec![(self.start, None)];
+        boundaries.extend(change_points.iter().map(|change_point| {
+            (
+                change_point.split,
+                Some((change_point.p_value, change_point.max_gain)),
+            )
+        }));
+        boundaries.push((self.stop, None));
+
+        boundaries
+            .windows(2)
+            .map(|window| {
+                let (start, start_change_point) = window[0];
+                let (stop, stop_change_point) = window[1];
+                let segment = X.slice(s![start..stop, ..]);
+                let (start_p_value, start_max_gain) = start_change_point.unzip();
+                let (stop_p_value, stop_max_gain) = stop_change_point.unzip();
+
+                SegmentSummary {
+                    start,
+                    stop,
+                    length: stop - start,
+                    mean: segment.mean_axis(Axis(0)).unwrap(),
+                    std: segment.std_axis(Axis(0), 0.),
+                    median: segment.map_axis(Axis(0), |column| median(&mut column.to_vec())),
+                    start_p_value: start_p_value.flatten(),
+                    start_max_gain,
+                    stop_p_value: stop_p_value.flatten(),
+                    stop_max_gain,
+                }
+            })
+            .collect()
+    }
+
+    /// Significant change points, in the order of `split_points()`.
+    pub fn change_points(&self) -> Vec<ChangePoint> {
+        let mut change_points: Vec<ChangePoint> = self
+            .iter_nodes()
+            .filter(|(_, node)| node.model_selection_result.is_significant)
+            .filter_map(|(depth, node)| {
+                node.optimizer_result.as_ref().map(|result| ChangePoint {
+                    split: result.best_split,
+                    depth,
+                    start: node.start,
+                    stop: node.stop,
+                    max_gain: result.max_gain,
+                    p_value: node.model_selection_result.p_value,
+                })
+            })
+            .collect();
+        change_points.sort_by_key(|change_point| change_point.split);
+        change_points
+    }
+
+    /// Significant change points, most important first.
+    ///
+    /// With `ChangePointRanking::Depth`, change points found closer to the root come
+    /// first. With `ChangePointRanking::PValue`, change points with smaller p-values
+    /// come first, and change points without p-value last. Ties are broken by
+    /// decreasing `max_gain`, which is also used for `ChangePointRanking::MaxGain`.
+    pub fn ranked_change_points(&self, ranking: ChangePointRanking) -> Vec<ChangePoint> {
+        let mut change_points = self.change_points();
+        let by_max_gain = |a: &ChangePoint, b: &ChangePoint| b.max_gain.total_cmp(&a.max_gain);
+
+        match ranking {
+            ChangePointRanking::Depth => {
+                change_points.sort_by(|a, b| a.depth.cmp(&b.depth).then(by_max_gain(a, b)))
+            }
+            ChangePointRanking::MaxGain => change_points.sort_by(by_max_gain),
+            ChangePointRanking::PValue => change_points.sort_by(|a, b| {
+                let p_value =
+                    |change_point: &ChangePoint| change_point.p_value.unwrap_or(f64::INFINITY);
+                p_value(a).total_cmp(&p_value(b)).then(by_max_gain(a, b))
+            }),
+        }
+        change_points
+    }
+
+    /// Iterate over the nodes of the tree in preorder, together with their depth. The
+    /// root has depth `0`.
+    pub fn iter_nodes(&self) -> NodeIter<'_> {
+        NodeIter {
+            nodes: VecDeque::from([(0, self)]),
+            breadth_first: false,
+        }
+    }
+
+    /// Iterate over the nodes of the tree in breadth-first order, together with their
+    /// depth. The root has depth `0`.
+    pub fn iter_nodes_breadth_first(&self) -> NodeIter<'_> {
+        NodeIter {
+            nodes: VecDeque::from([(0, self)]),
+            breadth_first: true,
+        }
+    }
+
     pub fn with_segments(mut self, segmentation: Segmentation) -> Self {
         self.segments = Some(segmentation.segments);
         self
     }
+
+    pub fn with_scale(mut self, scale: Option<Array1<f64>>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_time_index(mut self, time_index: Option<Vec<f64>>) -> Self {
+        self.time_index = time_index;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A significant change point of a `BinarySegmentationResult`.
+pub struct ChangePoint {
+    /// Split point, the first observation after the change.
+    pub split: usize,
+    /// Depth of the node at which the change point was found. The root has depth `0`.
+    pub depth: usize,
+    /// Start of the segment that was split.
+    pub start: usize,
+    /// Stop of the segment that was split.
+    pub stop: usize,
+    pub max_gain: f64,
+    pub p_value: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Criterion to rank change points by importance.
+pub enum ChangePointRanking {
+    /// Depth of the node at which the change point was found.
+    Depth,
+    /// Maximal gain.
+    MaxGain,
+    /// P-value of the model selection.
+    PValue,
+}
+
+/// Iterator over the nodes of a `BinarySegmentationResult` and their depths. See
+/// `BinarySegmentationResult::iter_nodes`.
+pub struct NodeIter<'a> {
+    nodes: VecDeque<(usize, &'a BinarySegmentationResult)>,
+    breadth_first: bool,
+}
+
+impl<'a> Iterator for NodeIter<'a> {
+    type Item = (usize, &'a BinarySegmentationResult);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, node) = self.nodes.pop_front()?;
+        let children = [&node.left, &node.right];
+
+        if self.breadth_first {
+            for child in children.into_iter().flatten() {
+                self.nodes.push_back((depth + 1, child));
+            }
+        } else {
+            for child in children.into_iter().rev().flatten() {
+                self.nodes.push_front((depth + 1, child));
+            }
+        }
+
+        Some((depth, node))
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Summary statistics of a segment `[start, stop)` between consecutive split points.
+pub struct SegmentSummary {
+    pub start: usize,
+    pub stop: usize,
+    pub length: usize,
+    /// Mean of each feature.
+    pub mean: Array1<f64>,
+    /// Standard deviation of each feature, normalized by `length`.
+    pub std: Array1<f64>,
+    /// Median of each feature.
+    pub median: Array1<f64>,
+    /// P-value of the change point at `start`, if any and if available.
+    pub start_p_value: Option<f64>,
+    /// Maximal gain of the change point at `start`, if any.
+    pub start_max_gain: Option<f64>,
+    /// P-value of the change point at `stop`, if any and if available.
+    pub stop_p_value: Option<f64>,
+    /// Maximal gain of the change point at `stop`, if any.
+    pub stop_max_gain: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+/// Result of change point detection on a panel of series with shared change points.
+pub struct PanelResult {
+    /// Segmentation of the panel, with the shared change points as split points.
+    pub result: BinarySegmentationResult,
+    /// For each shared split point, in the order of `split_points()`, the model
+    /// selection result of each series. `None` if the series does not cover the split
+    /// point with at least the minimal segment length on either side.
+    pub series_model_selection_results: Vec<Vec<Option<ModelSelectionResult>>>,
+}
+
+impl PanelResult {
+    /// Shared split points.
+    pub fn split_points(&self) -> Vec<usize> {
+        self.result.split_points()
+    }
+
+    /// For each shared split point, whether it is significant in each series.
+    pub fn series_is_significant(&self) -> Vec<Vec<bool>> {
+        self.series_model_selection_results
+            .iter()
+            .map(|results| {
+                results
+                    .iter()
+                    .map(|result| result.as_ref().is_some_and(|result| result.is_significant))
+                    .collect()
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::control::Control;
     use super::*;
+    use crate::observer::Signal;
     use crate::optimizer::GridSearch;
     use crate::segmentation::{Segmentation, SegmentationType};
     use crate::testing;
+    use rstest::*;
 
     #[test]
     fn test_binary_segmentation_change_in_mean() {
@@ -161,6 +519,89 @@ mod tests {
         assert_eq!(optimizer_result.stop, 100);
     }
 
+    #[test]
+    fn test_binary_segmentation_observer() {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+
+        let mut splits = vec![];
+        let mut progress = vec![];
+        let mut n_model_selections = 0;
+        let mut observer = |event: &Event| {
+            match event {
+                Event::NodeSplit {
+                    start,
+                    stop,
+                    split,
+                    depth,
+                } => splits.push((*start, *stop, *split, *depth)),
+                Event::Progress(p) => {
+                    assert_eq!(p.stage, Stage::Tree);
+                    progress.push((p.completed, p.total))
+                }
+                Event::ModelSelection(..) => n_model_selections += 1,
+                Event::SegmentEvaluated(_) => (),
+            };
+            Signal::Continue
+        };
+
+        let mut segmentation =
+            Segmentation::new_with_observer(SegmentationType::BS, &optimizer, Some(&mut observer));
+        let mut binary_segmentation = BinarySegmentationTree::new(&X_view);
+        binary_segmentation.grow(&mut segmentation);
+        assert!(!segmentation.is_cancelled());
+        drop(segmentation);
+
+        assert_eq!(
+            BinarySegmentationResult::from_tree(binary_segmentation).split_points(),
+            vec![25, 40, 80]
+        );
+        assert_eq!(
+            splits,
+            vec![(0, 100, 80, 0), (0, 80, 25, 1), (25, 80, 40, 2)]
+        );
+        assert_eq!(progress, vec![(25, 100), (40, 100), (80, 100), (100, 100)]);
+        assert_eq!(n_model_selections, 7);
+    }
+
+    #[test]
+    fn test_binary_segmentation_cancelled() {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+
+        let mut observer = |event: &Event| match event {
+            Event::NodeSplit { .. } => Signal::Cancel,
+            _ => Signal::Continue,
+        };
+
+        let mut segmentation =
+            Segmentation::new_with_observer(SegmentationType::BS, &optimizer, Some(&mut observer));
+        let mut binary_segmentation = BinarySegmentationTree::new(&X_view);
+        binary_segmentation.grow(&mut segmentation);
+
+        assert!(segmentation.is_cancelled());
+        assert!(binary_segmentation
+            .left
+            .as_ref()
+            .unwrap()
+            .optimizer_result
+            .is_none());
+        assert!(binary_segmentation
+            .right
+            .as_ref()
+            .unwrap()
+            .optimizer_result
+            .is_none());
+    }
+
     #[test]
     fn test_binary_segmentation_result() {
         let X = testing::array();
@@ -203,4 +644,229 @@ mod tests {
         let result = result.with_segments(segmentation);
         assert!(!result.segments.as_ref().unwrap().is_empty());
     }
+
+    #[test]
+    fn test_segments_summary() {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+        let mut segmentation = Segmentation::new(SegmentationType::SBS, &optimizer);
+        let mut tree = BinarySegmentationTree::new(&X_view);
+        tree.grow(&mut segmentation);
+        let result = BinarySegmentationResult::from_tree(tree);
+
+        let summary = result.segments_summary(&X_view);
+        assert_eq!(summary.len(), 4);
+        assert_eq!(
+            summary
+                .iter()
+                .map(|segment| (segment.start, segment.stop, segment.length))
+                .collect::<Vec<_>>(),
+            vec![(0, 25, 25), (25, 40, 15), (40, 80, 40), (80, 100, 20)]
+        );
+
+        let segment = &summary[1];
+        let expected_mean = X.slice(s![25..40, ..]).mean_axis(Axis(0)).unwrap();
+        assert_eq!(segment.mean, expected_mean);
+        assert_eq!(segment.std.len(), 5);
+        assert_eq!(segment.median.len(), 5);
+        assert_eq!(
+            segment.start_max_gain,
+            Some(
+                result
+                    .left
+                    .as_ref()
+                    .unwrap()
+                    .optimizer_result
+                    .as_ref()
+                    .unwrap()
+                    .max_gain
+            )
+        );
+        assert_eq!(
+            segment.stop_max_gain,
+            Some(result.optimizer_result.as_ref().unwrap().max_gain)
+        );
+
+        assert!(summary[0].start_max_gain.is_none());
+        assert!(summary[0].start_p_value.is_none());
+        assert!(summary[3].stop_max_gain.is_none());
+        assert_eq!(summary[3].start_max_gain, summary[2].stop_max_gain);
+    }
+
+    #[test]
+    fn test_labels_and_indicator() {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+        let mut segmentation = Segmentation::new(SegmentationType::SBS, &optimizer);
+        let mut tree = BinarySegmentationTree::new(&X_view);
+        tree.grow(&mut segmentation);
+        let result = BinarySegmentationResult::from_tree(tree);
+
+        let labels = result.labels();
+        assert_eq!(labels.len(), 100);
+        assert_eq!((labels[0], labels[24], labels[25]), (0, 0, 1));
+        assert_eq!(
+            (labels[39], labels[40], labels[79], labels[80]),
+            (1, 2, 2, 3)
+        );
+        assert_eq!(labels[99], 3);
+
+        let indicator = result.indicator();
+        assert_eq!(
+            indicator
+                .iter()
+                .enumerate()
+                .filter(|(_, &x)| x)
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>(),
+            vec![25, 40, 80]
+        );
+
+        // Every other observation of an original series of length 201 was selected.
+        let index: Vec<usize> = (0..100).map(|i| 2 * i + 1).collect();
+        let reindexed = result.reindex_labels(&index, 201);
+        assert_eq!(reindexed.len(), 201);
+        assert_eq!((reindexed[0], reindexed[50], reindexed[51]), (0, 0, 1));
+        assert_eq!((reindexed[199], reindexed[200]), (3, 3));
+
+        let timestamps: Vec<f64> = (0..100).map(|i| i as f64 / 10.).collect();
+        assert_eq!(result.split_points_at(&timestamps), vec![2.5, 4., 8.]);
+    }
+
+    #[test]
+    fn test_iter_nodes_and_ranked_change_points() {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+        let mut segmentation = Segmentation::new(SegmentationType::SBS, &optimizer);
+        let mut tree = BinarySegmentationTree::new(&X_view);
+        tree.grow(&mut segmentation);
+        let mut result = BinarySegmentationResult::from_tree(tree);
+
+        let preorder: Vec<(usize, usize, usize)> = result
+            .iter_nodes()
+            .map(|(depth, node)| (depth, node.start, node.stop))
+            .collect();
+        assert_eq!(
+            preorder,
+            vec![
+                (0, 0, 100),
+                (1, 0, 40),
+                (2, 0, 25),
+                (2, 25, 40),
+                (1, 40, 100),
+                (2, 40, 80),
+                (2, 80, 100)
+            ]
+        );
+
+        let breadth_first: Vec<(usize, usize, usize)> = result
+            .iter_nodes_breadth_first()
+            .map(|(depth, node)| (depth, node.start, node.stop))
+            .collect();
+        assert_eq!(
+            breadth_first,
+            vec![
+                (0, 0, 100),
+                (1, 0, 40),
+                (1, 40, 100),
+                (2, 0, 25),
+                (2, 25, 40),
+                (2, 40, 80),
+                (2, 80, 100)
+            ]
+        );
+
+        let change_points = result.change_points();
+        assert_eq!(
+            change_points
+                .iter()
+                .map(|change_point| (change_point.split, change_point.depth))
+                .collect::<Vec<_>>(),
+            vec![(25, 1), (40, 0), (80, 1)]
+        );
+        assert_eq!((change_points[1].start, change_points[1].stop), (0, 100));
+
+        let splits = |ranking| {
+            result
+                .ranked_change_points(ranking)
+                .iter()
+                .map(|change_point| change_point.split)
+                .collect::<Vec<_>>()
+        };
+        let gains: Vec<f64> = change_points
+            .iter()
+            .map(|change_point| change_point.max_gain)
+            .collect();
+        // The change at 25 has a larger gain than the change at 40 found at the root.
+        assert!((gains[0] > gains[1]) & (gains[1] > gains[2]));
+        assert_eq!(splits(ChangePointRanking::MaxGain), vec![25, 40, 80]);
+        assert_eq!(splits(ChangePointRanking::Depth), vec![40, 25, 80]);
+        // Without p-values, change points are ranked by their maximal gain.
+        assert_eq!(splits(ChangePointRanking::PValue), vec![25, 40, 80]);
+
+        result
+            .right
+            .as_mut()
+            .unwrap()
+            .model_selection_result
+            .p_value = Some(0.01);
+        result.model_selection_result.p_value = Some(0.02);
+        assert_eq!(
+            result
+                .ranked_change_points(ChangePointRanking::PValue)
+                .iter()
+                .map(|change_point| change_point.split)
+                .collect::<Vec<_>>(),
+            vec![80, 40, 25]
+        );
+    }
+
+    #[rstest]
+    #[case(vec![0, 1], 10)]
+    #[case((0..100).rev().collect(), 100)]
+    #[case((0..100).collect(), 50)]
+    #[should_panic]
+    fn test_reindex_labels_invalid(#[case] index: Vec<usize>, #[case] n: usize) {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+        let mut segmentation = Segmentation::new(SegmentationType::BS, &optimizer);
+        let mut tree = BinarySegmentationTree::new(&X_view);
+        tree.grow(&mut segmentation);
+        let result = BinarySegmentationResult::from_tree(tree);
+
+        result.reindex_labels(&index, n);
+    }
+
+    #[test]
+    #[should_panic(expected = "X must have at least 100 rows")]
+    fn test_segments_summary_too_short() {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+        let mut segmentation = Segmentation::new(SegmentationType::BS, &optimizer);
+        let mut tree = BinarySegmentationTree::new(&X_view);
+        tree.grow(&mut segmentation);
+        let result = BinarySegmentationResult::from_tree(tree);
+
+        result.segments_summary(&X.slice(s![..50, ..]));
+    }
 }
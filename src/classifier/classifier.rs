@@ -1,4 +1,4 @@
-use crate::utils::log_eta;
+use crate::utils::{calibrate, log_eta};
 use crate::Control;
 use ndarray::{s, stack, Array1, Array2, Axis};
 
@@ -18,11 +18,16 @@ pub trait Classifier {
             return 0.;
         }
 
+        let control = self.control();
+        let eta = control.likelihood_shrinkage;
+        let predictions = calibrate(predictions, split - start, &control.calibration);
+
         let (left, right) = predictions.slice(s![..]).split_at(Axis(0), split - start);
         let left_correction = ((stop - start - 1) as f64) / ((split - start - 1) as f64);
         let right_correction = ((stop - start - 1) as f64) / ((stop - split - 1) as f64);
-        left.mapv(|x| log_eta((1. - x) * left_correction)).sum()
-            + right.mapv(|x| log_eta(x * right_correction)).sum()
+        left.mapv(|x| log_eta((1. - x) * left_correction, eta))
+            .sum()
+            + right.mapv(|x| log_eta(x * right_correction, eta)).sum()
     }
 
     fn full_likelihood(
@@ -35,6 +40,10 @@ pub trait Classifier {
         if (stop - split <= 1) || (split - start <= 1) {
             return Array2::zeros((2, stop - start));
         }
+        let control = self.control();
+        let eta = control.likelihood_shrinkage;
+        let predictions = calibrate(predictions, split - start, &control.calibration);
+
         let mut likelihoods = stack(Axis(0), &[predictions.view(), predictions.view()]).unwrap();
         assert!(likelihoods.shape() == [2, stop - start]);
 
@@ -45,16 +54,16 @@ pub trait Classifier {
 
         likelihoods
             .slice_mut(s![0, ..(split - start)])
-            .mapv_inplace(|x| log_eta((1. - x) * prior_00));
+            .mapv_inplace(|x| log_eta((1. - x) * prior_00, eta));
         likelihoods
             .slice_mut(s![0, (split - start)..])
-            .mapv_inplace(|x| log_eta((1. - x) * prior_01));
+            .mapv_inplace(|x| log_eta((1. - x) * prior_01, eta));
         likelihoods
             .slice_mut(s![1, ..(split - start)])
-            .mapv_inplace(|x| log_eta(x * prior_10));
+            .mapv_inplace(|x| log_eta(x * prior_10, eta));
         likelihoods
             .slice_mut(s![1, (split - start)..])
-            .mapv_inplace(|x| log_eta(x * prior_11));
+            .mapv_inplace(|x| log_eta(x * prior_11, eta));
 
         likelihoods
     }
@@ -67,6 +76,7 @@ mod tests {
 
     use super::*;
     use crate::testing::TrivialClassifier;
+    use crate::Calibration;
     use assert_approx_eq::*;
 
     #[test]
@@ -97,4 +107,37 @@ mod tests {
 
         assert_eq!(classifier.full_likelihood(&predictions, 0, 10, 5), expected);
     }
+
+    #[test]
+    fn test_likelihood_shrinkage() {
+        let control = Control::default().with_likelihood_shrinkage(0.1);
+        let classifier = TrivialClassifier {
+            n: 10,
+            control: &control,
+        };
+        let predictions = classifier.predict(0, 10, 5);
+        let likelihoods = classifier.full_likelihood(&predictions, 0, 10, 5);
+        // The log-likelihood of a single observation is bounded by ln(0.1).
+        assert_approx_eq!(likelihoods[[1, 0]], 0.1_f64.ln());
+        assert!(likelihoods.iter().all(|&x| x >= 0.1_f64.ln()));
+    }
+
+    #[test]
+    fn test_calibration() {
+        let predictions = Array1::from_shape_fn(10, |idx| if idx < 5 { 0.2 } else { 0.8 });
+        let likelihood = |calibration: Calibration| {
+            let control = Control::default().with_calibration(calibration);
+            let classifier = TrivialClassifier {
+                n: 10,
+                control: &control,
+            };
+            classifier.single_likelihood(&predictions, 0, 10, 5)
+        };
+        let expected = likelihood(Calibration::None);
+
+        // A temperature of one does not change the probabilities.
+        assert_approx_eq!(likelihood(Calibration::Temperature(Some(1.))), expected);
+        // Higher temperatures shrink probabilities towards 1/2.
+        assert!(likelihood(Calibration::Temperature(Some(10.))) < expected);
+    }
 }
@@ -0,0 +1,316 @@
+use crate::{Classifier, Control};
+use ndarray::Array1;
+
+/// Ensemble of classifiers.
+///
+/// Predictions are the weighted average of the out-of-sample probabilities of the
+/// inner `classifiers`. Different classifiers are sensitive to different kinds of
+/// changes, e.g. `kNN` to changes in the local density and `RandomForest` to changes in
+/// few coordinates. The likelihoods are computed from the averaged probabilities.
+/// Uses the control of the first classifier.
+///
+/// If `stacking` is true, the weights are instead fitted for each split by maximizing
+/// the out-of-sample log-likelihood of the segment labels under the averaged
+/// probabilities. This favours the classifier that separates the two segments best.
+/// The weights are cross-fitted on observations with even and odd indices, such that
+/// the averaged probability of an observation does not depend on its own label.
+/// Otherwise, the observed gain would be inflated compared to the gains of the
+/// permutation test.
+pub struct Ensemble<'a> {
+    classifiers: Vec<Box<dyn Classifier + 'a>>,
+    weights: Vec<f64>,
+    stacking: bool,
+}
+
+impl<'a> Ensemble<'a> {
+    /// Ensemble with equal weights.
+    pub fn new(classifiers: Vec<Box<dyn Classifier + 'a>>) -> Ensemble<'a> {
+        if classifiers.is_empty() {
+            panic!("Ensemble needs at least one classifier.");
+        }
+
+        let n = classifiers[0].n();
+        if classifiers.iter().any(|classifier| classifier.n() != n) {
+            panic!("All classifiers of an ensemble need to have the same number of observations.");
+        }
+
+        let weights = vec![1. / classifiers.len() as f64; classifiers.len()];
+        Ensemble {
+            classifiers,
+            weights,
+            stacking: false,
+        }
+    }
+
+    /// Set the weights of the classifiers. Weights are normalized to sum to one.
+    pub fn with_weights(mut self, weights: Vec<f64>) -> Self {
+        if weights.len() != self.classifiers.len() {
+            panic!(
+                "Got {} weights for {} classifiers.",
+                weights.len(),
+                self.classifiers.len()
+            );
+        }
+        if weights.iter().any(|weight| *weight < 0.) || weights.iter().sum::<f64>() <= 0. {
+            panic!("Weights need to be non-negative with a positive sum. Got {weights:?}");
+        }
+
+        let total: f64 = weights.iter().sum();
+        self.weights = weights.iter().map(|weight| weight / total).collect();
+        self
+    }
+
+    /// Fit the weights for each split instead of using fixed weights.
+    pub fn with_stacking(mut self, stacking: bool) -> Self {
+        self.stacking = stacking;
+        self
+    }
+
+    pub fn weights(&self) -> &Vec<f64> {
+        &self.weights
+    }
+
+    /// Weights maximizing `sum_i log(sum_k w_k q_ik)` over the simplex, where `q_ik`
+    /// is the predicted probability of classifier `k` that observation `i` belongs to
+    /// its actual segment and `i` ranges over `observations`. Predictions are
+    /// probabilities of belonging to the segment after `split`. The objective is
+    /// concave in `w`. The weights are fitted with the EM-algorithm for mixture
+    /// proportions, starting from `self.weights`.
+    fn stacking_weights(
+        &self,
+        predictions: &[Array1<f64>],
+        split: usize,
+        observations: &[usize],
+    ) -> Vec<f64> {
+        if observations.is_empty() {
+            return self.weights.clone();
+        }
+
+        let q: Vec<Array1<f64>> = predictions
+            .iter()
+            .map(|prediction| {
+                observations
+                    .iter()
+                    .map(|&idx| {
+                        let p = if idx < split {
+                            1. - prediction[idx]
+                        } else {
+                            prediction[idx]
+                        };
+                        // Avoid division by zero for observations all classifiers get
+                        // wrong.
+                        p.max(1e-6)
+                    })
+                    .collect()
+            })
+            .collect();
+        let m = q[0].len() as f64;
+
+        let mut weights = self.weights.clone();
+        for _ in 0..50 {
+            let mut mixture = Array1::<f64>::zeros(q[0].len());
+            for (q_k, weight) in q.iter().zip(weights.iter()) {
+                mixture.scaled_add(*weight, q_k);
+            }
+            weights = q
+                .iter()
+                .zip(weights.iter())
+                .map(|(q_k, weight)| weight * (q_k / &mixture).sum() / m)
+                .collect();
+        }
+        weights
+    }
+}
+
+impl<'a> Classifier for Ensemble<'a> {
+    fn n(&self) -> usize {
+        self.classifiers[0].n()
+    }
+
+    fn predict(&self, start: usize, stop: usize, split: usize) -> Array1<f64> {
+        let predictions: Vec<Array1<f64>> = self
+            .classifiers
+            .iter()
+            .map(|classifier| classifier.predict(start, stop, split))
+            .collect();
+
+        let mut result = Array1::<f64>::zeros(stop - start);
+        if !self.stacking {
+            for (prediction, weight) in predictions.iter().zip(self.weights.iter()) {
+                result.scaled_add(*weight, prediction);
+            }
+            return result;
+        }
+
+        for fold in 0..2 {
+            let (train, test): (Vec<usize>, Vec<usize>) =
+                (0..(stop - start)).partition(|idx| idx % 2 != fold);
+            let weights = self.stacking_weights(&predictions, split - start, &train);
+            for idx in test {
+                result[idx] = predictions
+                    .iter()
+                    .zip(weights.iter())
+                    .map(|(prediction, weight)| weight * prediction[idx])
+                    .sum();
+            }
+        }
+        result
+    }
+
+    fn control(&self) -> &Control {
+        self.classifiers[0].control()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::{kNN, RandomForest};
+    use crate::testing;
+    use crate::testing::TrivialClassifier;
+    use assert_approx_eq::*;
+    use ndarray::s;
+    use rstest::*;
+
+    #[rstest]
+    #[case(vec![1., 1.])]
+    #[case(vec![1., 3.])]
+    #[case(vec![0., 1.])]
+    fn test_ensemble_predict(#[case] weights: Vec<f64>) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default();
+
+        let knn = kNN::new(&X_view, &control);
+        let random_forest = RandomForest::new(&X_view, &control);
+        let expected = (weights[0] * knn.predict(0, 100, 40)
+            + weights[1] * random_forest.predict(0, 100, 40))
+            / (weights[0] + weights[1]);
+
+        let ensemble =
+            Ensemble::new(vec![Box::new(knn), Box::new(random_forest)]).with_weights(weights);
+        let predictions = ensemble.predict(0, 100, 40);
+
+        for (prediction, expected) in predictions.iter().zip(expected.iter()) {
+            assert_approx_eq!(prediction, expected);
+        }
+    }
+
+    #[test]
+    fn test_ensemble_stacking_weights() {
+        // The second classifier separates the segments perfectly, the first not at
+        // all. Stacking puts (almost) all weight on the second.
+        let control = Control::default();
+        let ensemble = Ensemble::new(vec![
+            Box::new(TrivialClassifier {
+                n: 10,
+                control: &control,
+            }),
+            Box::new(TrivialClassifier {
+                n: 10,
+                control: &control,
+            }),
+        ])
+        .with_stacking(true);
+
+        let predictions = vec![
+            Array1::from_elem(10, 0.5),
+            ndarray::array![0.1, 0.1, 0.1, 0.1, 0.1, 0.9, 0.9, 0.9, 0.9, 0.9],
+        ];
+        let weights = ensemble.stacking_weights(&predictions, 5, &(0..10).collect::<Vec<_>>());
+        assert_approx_eq!(weights.iter().sum::<f64>(), 1.);
+        assert!(weights[1] > 0.99);
+    }
+
+    struct FixedClassifier<'a> {
+        predictions: Array1<f64>,
+        control: &'a Control,
+    }
+
+    impl Classifier for FixedClassifier<'_> {
+        fn n(&self) -> usize {
+            self.predictions.len()
+        }
+
+        fn predict(&self, start: usize, stop: usize, _: usize) -> Array1<f64> {
+            self.predictions.slice(s![start..stop]).to_owned()
+        }
+
+        fn control(&self) -> &Control {
+            self.control
+        }
+    }
+
+    #[test]
+    fn test_ensemble_stacking_cross_fitted() {
+        // Predictions of even observations use weights fitted on odd observations
+        // only, and vice versa.
+        let control = Control::default();
+        let predictions = vec![
+            ndarray::array![0.01, 0.5, 0.01, 0.5, 0.01, 0.5, 0.99, 0.5, 0.99, 0.5],
+            ndarray::array![0.2, 0.1, 0.3, 0.2, 0.1, 0.8, 0.9, 0.7, 0.8, 0.9],
+        ];
+        let ensemble = Ensemble::new(
+            predictions
+                .iter()
+                .map(|prediction| -> Box<dyn Classifier> {
+                    Box::new(FixedClassifier {
+                        predictions: prediction.clone(),
+                        control: &control,
+                    })
+                })
+                .collect(),
+        )
+        .with_stacking(true);
+        let result = ensemble.predict(0, 10, 5);
+
+        let even_weights = ensemble.stacking_weights(&predictions, 5, &[1, 3, 5, 7, 9]);
+        let odd_weights = ensemble.stacking_weights(&predictions, 5, &[0, 2, 4, 6, 8]);
+        // The first classifier is perfect on even observations only.
+        assert!(odd_weights[0] > 0.9);
+        assert!(even_weights[0] < 0.1);
+
+        for idx in 0..10 {
+            let weights = if idx % 2 == 0 {
+                &even_weights
+            } else {
+                &odd_weights
+            };
+            let expected = weights[0] * predictions[0][idx] + weights[1] * predictions[1][idx];
+            assert_approx_eq!(result[idx], expected);
+        }
+    }
+
+    #[test]
+    fn test_ensemble_single_classifier() {
+        let control = Control::default();
+        let classifier = TrivialClassifier {
+            n: 10,
+            control: &control,
+        };
+        let expected = classifier.predict(0, 10, 5);
+
+        let ensemble = Ensemble::new(vec![Box::new(TrivialClassifier {
+            n: 10,
+            control: &control,
+        })]);
+        assert_eq!(ensemble.n(), 10);
+        assert_eq!(ensemble.predict(0, 10, 5), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "same number of observations")]
+    fn test_ensemble_different_n() {
+        let control = Control::default();
+        Ensemble::new(vec![
+            Box::new(TrivialClassifier {
+                n: 10,
+                control: &control,
+            }),
+            Box::new(TrivialClassifier {
+                n: 11,
+                control: &control,
+            }),
+        ]);
+    }
+}
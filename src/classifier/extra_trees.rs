@@ -0,0 +1,445 @@
+use crate::{Classifier, Control};
+use biosphere::MaxFeatures;
+use ndarray::{s, Array1, ArrayView1, ArrayView2};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Hyperparameters for extremely randomized trees.
+#[derive(Clone, Debug)]
+pub struct ExtraTreesParameters {
+    /// Number of trees.
+    pub n_estimators: usize,
+    /// Maximal depth of each tree. If `None`, nodes are expanded until they are pure.
+    pub max_depth: Option<usize>,
+    /// Number of (non-constant) features to draw a random threshold for at each node.
+    pub max_features: MaxFeatures,
+    /// Minimal number of samples in each leaf.
+    pub min_samples_leaf: usize,
+    /// Seed for bootstrap samples, features and thresholds.
+    pub seed: u64,
+}
+
+impl Default for ExtraTreesParameters {
+    fn default() -> Self {
+        ExtraTreesParameters {
+            n_estimators: 100,
+            max_depth: Some(8),
+            max_features: MaxFeatures::Sqrt,
+            min_samples_leaf: 1,
+            seed: 0,
+        }
+    }
+}
+
+impl ExtraTreesParameters {
+    pub fn with_n_estimators(mut self, n_estimators: usize) -> Self {
+        self.n_estimators = n_estimators;
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_max_features(mut self, max_features: MaxFeatures) -> Self {
+        self.max_features = max_features;
+        self
+    }
+
+    pub fn with_min_samples_leaf(mut self, min_samples_leaf: usize) -> Self {
+        if min_samples_leaf == 0 {
+            panic!("min_samples_leaf needs to be strictly positive. Got 0");
+        }
+        self.min_samples_leaf = min_samples_leaf;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Extremely randomized trees (Geurts, P., Ernst, D. and Wehenkel, L. (2006).
+/// Extremely randomized trees. Machine Learning 63 3–42).
+///
+/// As `RandomForest`, but the threshold of each candidate feature is drawn uniformly
+/// between the minimum and maximum of the feature in the node instead of optimized.
+/// Only the best of these random splits is kept. This is faster and results in
+/// smoother probability estimates. Trees are fit on bootstrap samples such that
+/// out-of-bag predictions are available.
+pub struct ExtraTrees<'a, 'b> {
+    X: &'a ArrayView2<'b, f64>,
+    control: &'a Control,
+}
+
+impl<'a, 'b> ExtraTrees<'a, 'b> {
+    pub fn new(X: &'a ArrayView2<'b, f64>, control: &'a Control) -> ExtraTrees<'a, 'b> {
+        ExtraTrees { X, control }
+    }
+}
+
+impl<'a, 'b> Classifier for ExtraTrees<'a, 'b> {
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn predict(&self, start: usize, stop: usize, split: usize) -> Array1<f64> {
+        let mut y = Array1::<f64>::zeros(stop - start);
+        y.slice_mut(s![(split - start)..]).fill(1.);
+
+        let X_slice = self.X.slice(s![start..stop, ..]);
+        let mut parameters = self.control.extra_trees_parameters.clone();
+        if let Some(tree_scaling) = &self.control.tree_scaling {
+            parameters.n_estimators = tree_scaling.n_estimators(stop - start, self.n());
+            parameters.max_depth = tree_scaling.max_depth(stop - start);
+        }
+
+        let mut predictions = fit_predict_oob(&X_slice, &y.view(), &parameters);
+
+        // Observations that are in-bag for every tree get the prior. Note that we need
+        // to adjust by -1 because the predictions are oob.
+        predictions
+            .slice_mut(s![0..(split - start)])
+            .map_inplace(|x| {
+                if x.is_nan() {
+                    *x = (stop - split) as f64 / (stop - start - 1) as f64
+                }
+            });
+        predictions
+            .slice_mut(s![(split - start)..])
+            .map_inplace(|x| {
+                if x.is_nan() {
+                    *x = (stop - split - 1) as f64 / (stop - start - 1) as f64
+                }
+            });
+        predictions
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+/// Out-of-bag predictions of extremely randomized trees fit on bootstrap samples.
+/// Observations that are in-bag for every tree get a prediction of `NaN`.
+fn fit_predict_oob(
+    X: &ArrayView2<'_, f64>,
+    y: &ArrayView1<'_, f64>,
+    parameters: &ExtraTreesParameters,
+) -> Array1<f64> {
+    let n = X.nrows();
+    let mut rng = StdRng::seed_from_u64(parameters.seed);
+
+    let mut predictions = Array1::<f64>::zeros(n);
+    let mut counts = Array1::<usize>::zeros(n);
+
+    for _ in 0..parameters.n_estimators {
+        let mut in_bag = vec![false; n];
+        let mut samples: Vec<usize> = (0..n)
+            .map(|_| {
+                let idx = rng.gen_range(0..n);
+                in_bag[idx] = true;
+                idx
+            })
+            .collect();
+
+        let tree = ExtraTree::fit(X, y, &mut samples, parameters, &mut rng);
+
+        for (idx, _) in in_bag.iter().enumerate().filter(|(_, in_bag)| !**in_bag) {
+            predictions[idx] += tree.predict_row(&X.row(idx));
+            counts[idx] += 1;
+        }
+    }
+
+    predictions
+        .iter_mut()
+        .zip(counts.iter())
+        .for_each(|(prediction, count)| *prediction /= *count as f64);
+    predictions
+}
+
+enum Node {
+    Leaf(f64),
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+    },
+}
+
+struct ExtraTree {
+    nodes: Vec<Node>,
+}
+
+impl ExtraTree {
+    fn fit(
+        X: &ArrayView2<'_, f64>,
+        y: &ArrayView1<'_, f64>,
+        samples: &mut [usize],
+        parameters: &ExtraTreesParameters,
+        rng: &mut StdRng,
+    ) -> ExtraTree {
+        let mut tree = ExtraTree { nodes: vec![] };
+        tree.grow(X, y, samples, 0, parameters, rng);
+        tree
+    }
+
+    /// Grow the subtree for `samples` and return the index of its root node.
+    fn grow(
+        &mut self,
+        X: &ArrayView2<'_, f64>,
+        y: &ArrayView1<'_, f64>,
+        samples: &mut [usize],
+        depth: usize,
+        parameters: &ExtraTreesParameters,
+        rng: &mut StdRng,
+    ) -> usize {
+        let idx = self.nodes.len();
+        let sum: f64 = samples.iter().map(|sample| y[*sample]).sum();
+        let mean = sum / samples.len() as f64;
+        self.nodes.push(Node::Leaf(mean));
+
+        let is_pure = (sum == 0.) | (sum == samples.len() as f64);
+        let is_max_depth = parameters
+            .max_depth
+            .is_some_and(|max_depth| depth >= max_depth);
+        if is_pure | is_max_depth | (samples.len() < 2 * parameters.min_samples_leaf) {
+            return idx;
+        }
+
+        if let Some((feature, threshold)) = Self::best_random_split(X, y, samples, parameters, rng)
+        {
+            let n_left = partition(X, samples, feature, threshold);
+            let (left_samples, right_samples) = samples.split_at_mut(n_left);
+            let left = self.grow(X, y, left_samples, depth + 1, parameters, rng);
+            let right = self.grow(X, y, right_samples, depth + 1, parameters, rng);
+            self.nodes[idx] = Node::Split {
+                feature,
+                threshold,
+                left,
+                right,
+            };
+        }
+
+        idx
+    }
+
+    /// Draw a random threshold for up to `max_features` non-constant features and
+    /// return the one with the largest decrease in the sum of squared errors.
+    fn best_random_split(
+        X: &ArrayView2<'_, f64>,
+        y: &ArrayView1<'_, f64>,
+        samples: &[usize],
+        parameters: &ExtraTreesParameters,
+        rng: &mut StdRng,
+    ) -> Option<(usize, f64)> {
+        let mut features: Vec<usize> = (0..X.ncols()).collect();
+        features.shuffle(rng);
+        let max_features = parameters.max_features.from_n_features(X.ncols());
+
+        let mut best: Option<(usize, f64)> = None;
+        let mut best_score = f64::NEG_INFINITY;
+        let mut n_visited = 0;
+
+        for feature in features {
+            if n_visited >= max_features {
+                break;
+            }
+
+            let (min, max) =
+                samples
+                    .iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), sample| {
+                        let value = X[[*sample, feature]];
+                        (min.min(value), max.max(value))
+                    });
+            if min >= max {
+                continue;
+            }
+            n_visited += 1;
+
+            let threshold = rng.gen_range(min..max);
+            let (mut n_left, mut sum_left, mut sum_right) = (0, 0., 0.);
+            for sample in samples.iter() {
+                if X[[*sample, feature]] <= threshold {
+                    n_left += 1;
+                    sum_left += y[*sample];
+                } else {
+                    sum_right += y[*sample];
+                }
+            }
+            let n_right = samples.len() - n_left;
+            if (n_left < parameters.min_samples_leaf) | (n_right < parameters.min_samples_leaf) {
+                continue;
+            }
+
+            // Maximizing the decrease in the sum of squared errors is equivalent to
+            // maximizing sum_left^2 / n_left + sum_right^2 / n_right.
+            let score =
+                sum_left * sum_left / n_left as f64 + sum_right * sum_right / n_right as f64;
+            if score > best_score {
+                best_score = score;
+                best = Some((feature, threshold));
+            }
+        }
+
+        best
+    }
+
+    fn predict_row(&self, row: &ArrayView1<'_, f64>) -> f64 {
+        let mut idx = 0;
+        loop {
+            match self.nodes[idx] {
+                Node::Leaf(value) => return value,
+                Node::Split {
+                    feature,
+                    threshold,
+                    left,
+                    right,
+                } => {
+                    idx = if row[feature] <= threshold {
+                        left
+                    } else {
+                        right
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Reorder `samples` such that those with `X[sample, feature] <= threshold` come first
+/// and return their number.
+fn partition(
+    X: &ArrayView2<'_, f64>,
+    samples: &mut [usize],
+    feature: usize,
+    threshold: f64,
+) -> usize {
+    let mut n_left = 0;
+    for idx in 0..samples.len() {
+        if X[[samples[idx], feature]] <= threshold {
+            samples.swap(idx, n_left);
+            n_left += 1;
+        }
+    }
+    n_left
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gain::ClassifierGain;
+    use crate::optimizer::{Optimizer, TwoStepSearch};
+    use crate::testing;
+    use crate::TreeScaling;
+    use csv::ReaderBuilder;
+    use ndarray::Array2;
+    use ndarray_csv::Array2Reader;
+    use rstest::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_partition() {
+        let X = ndarray::array![[3.], [1.], [4.], [1.], [5.]];
+        let mut samples = vec![0, 1, 2, 3, 4];
+        let n_left = partition(&X.view(), &mut samples, 0, 3.);
+        assert_eq!(n_left, 3);
+        assert!(samples[..n_left].iter().all(|sample| X[[*sample, 0]] <= 3.));
+        assert!(samples[n_left..].iter().all(|sample| X[[*sample, 0]] > 3.));
+    }
+
+    #[test]
+    fn test_extra_tree_fits_separable_data() {
+        let X = ndarray::array![[0.], [1.], [2.], [3.], [10.], [11.], [12.], [13.]];
+        let y = ndarray::array![0., 0., 0., 0., 1., 1., 1., 1.];
+        let mut samples: Vec<usize> = (0..8).collect();
+        let parameters = ExtraTreesParameters::default().with_max_depth(None);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let tree = ExtraTree::fit(&X.view(), &y.view(), &mut samples, &parameters, &mut rng);
+        for (row, expected) in X.rows().into_iter().zip(y.iter()) {
+            assert_eq!(tree.predict_row(&row), *expected);
+        }
+    }
+
+    #[rstest]
+    #[case(1000, 1000, 100, Some(8))]
+    #[case(500, 1000, 50, Some(8))]
+    #[case(50, 1000, 10, Some(6))]
+    #[case(3, 3, 100, Some(2))]
+    fn test_tree_scaling(
+        #[case] segment_length: usize,
+        #[case] n: usize,
+        #[case] expected_n_estimators: usize,
+        #[case] expected_max_depth: Option<usize>,
+    ) {
+        let tree_scaling = TreeScaling::default();
+        assert_eq!(
+            tree_scaling.n_estimators(segment_length, n),
+            expected_n_estimators
+        );
+        assert_eq!(tree_scaling.max_depth(segment_length), expected_max_depth);
+    }
+
+    #[rstest]
+    #[case(1000)]
+    #[case(3)]
+    fn test_tree_scaling_unbounded_depth(#[case] segment_length: usize) {
+        let tree_scaling = TreeScaling {
+            max_depth: None,
+            ..TreeScaling::default()
+        };
+        assert_eq!(tree_scaling.max_depth(segment_length), None);
+    }
+
+    #[rstest]
+    #[case(0, 50, 100, None)]
+    #[case(0, 100, 150, None)]
+    #[case(50, 100, 150, None)]
+    #[case(0, 50, 150, None)]
+    #[case(0, 50, 100, Some(TreeScaling::default()))]
+    fn test_predictions(
+        #[case] start: usize,
+        #[case] split: usize,
+        #[case] stop: usize,
+        #[case] tree_scaling: Option<TreeScaling>,
+    ) {
+        let file = File::open("testdata/iris.csv").unwrap();
+        let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
+        let X: Array2<f64> = reader.deserialize_array2((150, 4)).unwrap();
+        let X_view = X.view();
+
+        let control = Control::default().with_tree_scaling(tree_scaling);
+
+        let extra_trees = ExtraTrees::new(&X_view, &control);
+        let predictions = extra_trees.predict(start, stop, split);
+
+        let mut y = Array1::<f64>::zeros(stop - start);
+        y.slice_mut(s![(split - start)..]).fill(1.);
+
+        let mse = (y - predictions).mapv(|x| x.powi(2)).mean().unwrap();
+        assert!(mse < 0.06, "mse = {}", mse);
+    }
+
+    #[rstest]
+    #[case(0, 100, 80)]
+    #[case(10, 90, 40)]
+    fn test_two_step_search(#[case] start: usize, #[case] stop: usize, #[case] expected: usize) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default().with_minimal_relative_segment_length(0.01);
+        let classifier = ExtraTrees::new(&X_view, &control);
+        let gain = ClassifierGain { classifier };
+        let optimizer = TwoStepSearch::new(gain);
+
+        assert_eq!(
+            expected,
+            optimizer.find_best_split(start, stop).unwrap().best_split
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{Classifier, Control};
+use ndarray::{s, Array1, ArrayView1, ArrayView2};
+
+/// Classifier defined through a user-supplied function.
+///
+/// For a segment `[start, stop)` and a split `split`, `f` is called with the features
+/// `X[start..stop, :]` and labels that are `0` for observations before and `1` for
+/// observations after `split`. It must return out-of-sample estimates of the
+/// probability that each observation has label `1`, e.g. through cross-fitting or
+/// out-of-bag estimates. In-sample predictions result in overconfident likelihoods and
+/// spurious change points.
+pub struct FnClassifier<'a, 'b, F>
+where
+    F: Fn(ArrayView2<'_, f64>, ArrayView1<'_, f64>) -> Array1<f64>,
+{
+    X: &'a ArrayView2<'b, f64>,
+    f: F,
+    control: &'a Control,
+}
+
+impl<'a, 'b, F> FnClassifier<'a, 'b, F>
+where
+    F: Fn(ArrayView2<'_, f64>, ArrayView1<'_, f64>) -> Array1<f64>,
+{
+    pub fn new(X: &'a ArrayView2<'b, f64>, f: F, control: &'a Control) -> FnClassifier<'a, 'b, F> {
+        FnClassifier { X, f, control }
+    }
+}
+
+impl<'a, 'b, F> Classifier for FnClassifier<'a, 'b, F>
+where
+    F: Fn(ArrayView2<'_, f64>, ArrayView1<'_, f64>) -> Array1<f64>,
+{
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn predict(&self, start: usize, stop: usize, split: usize) -> Array1<f64> {
+        let mut y = Array1::<f64>::zeros(stop - start);
+        y.slice_mut(s![(split - start)..]).fill(1.);
+
+        let predictions = (self.f)(self.X.slice(s![start..stop, ..]), y.view());
+        if predictions.len() != stop - start {
+            panic!(
+                "The classifier function must return one prediction per observation. Got {} predictions for {} observations.",
+                predictions.len(),
+                stop - start
+            );
+        }
+        predictions
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gain::ClassifierGain;
+    use crate::optimizer::{Optimizer, TwoStepSearch};
+    use crate::testing;
+    use ndarray::Axis;
+    use rstest::*;
+
+    /// Leave-one-out nearest centroid classifier. The probability of label `1` is the
+    /// softmax of the negative squared distances to the two class means, computed
+    /// without the observation itself.
+    fn nearest_centroid(X: ArrayView2<'_, f64>, y: ArrayView1<'_, f64>) -> Array1<f64> {
+        let n_ones = y.sum();
+        let n_zeros = y.len() as f64 - n_ones;
+        let sum_ones = X.t().dot(&y);
+        let sum_zeros = X.sum_axis(Axis(0)) - &sum_ones;
+
+        Array1::from_shape_fn(y.len(), |i| {
+            let x = X.row(i);
+            let (mean_zeros, mean_ones) = if y[i] == 1. {
+                (&sum_zeros / n_zeros, (&sum_ones - &x) / (n_ones - 1.))
+            } else {
+                ((&sum_zeros - &x) / (n_zeros - 1.), &sum_ones / n_ones)
+            };
+            let distance_zeros = (&x - &mean_zeros).mapv(|a| a * a).sum();
+            let distance_ones = (&x - &mean_ones).mapv(|a| a * a).sum();
+            1. / (1. + (distance_ones - distance_zeros).exp())
+        })
+    }
+
+    #[test]
+    fn test_fn_classifier_labels() {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default();
+
+        // Return the labels, shifted away from 0 and 1.
+        let classifier = FnClassifier::new(
+            &X_view,
+            |X: ArrayView2<'_, f64>, y: ArrayView1<'_, f64>| {
+                assert_eq!(X.nrows(), y.len());
+                y.mapv(|x| 0.1 + 0.8 * x)
+            },
+            &control,
+        );
+        let predictions = classifier.predict(10, 30, 25);
+        assert_eq!(predictions.len(), 20);
+        assert_eq!(predictions[14], 0.1);
+        assert_eq!(predictions[15], 0.9);
+    }
+
+    #[test]
+    #[should_panic(expected = "one prediction per observation")]
+    fn test_fn_classifier_wrong_length() {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default();
+
+        let classifier = FnClassifier::new(
+            &X_view,
+            |_: ArrayView2<'_, f64>, _: ArrayView1<'_, f64>| Array1::zeros(3),
+            &control,
+        );
+        classifier.predict(0, 10, 5);
+    }
+
+    #[rstest]
+    #[case(0, 100, 80)]
+    #[case(0, 60, 25)]
+    fn test_fn_classifier_two_step_search(
+        #[case] start: usize,
+        #[case] stop: usize,
+        #[case] expected: usize,
+    ) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default();
+        let classifier = FnClassifier::new(&X_view, nearest_centroid, &control);
+        let gain = ClassifierGain { classifier };
+        let optimizer = TwoStepSearch::new(gain);
+
+        let result = optimizer.find_best_split(start, stop).unwrap();
+        assert_eq!(result.best_split, expected);
+        assert!(optimizer.model_selection(&result).is_significant);
+    }
+}
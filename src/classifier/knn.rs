@@ -186,7 +186,7 @@ mod tests {
 
         let classifier = kNN::new(&X_view, &control);
         let gain = ClassifierGain { classifier };
-        let optimizer = TwoStepSearch { gain };
+        let optimizer = TwoStepSearch::new(gain);
 
         assert_eq!(
             expected,
@@ -1,7 +1,13 @@
 mod classifier;
+mod ensemble;
+mod extra_trees;
+mod fn_classifier;
 mod knn;
 mod random_forest;
 
 pub use classifier::Classifier;
+pub use ensemble::Ensemble;
+pub use extra_trees::{ExtraTrees, ExtraTreesParameters};
+pub use fn_classifier::FnClassifier;
 pub use knn::kNN;
 pub use random_forest::RandomForest;
@@ -24,7 +24,12 @@ impl<'a, 'b> Classifier for RandomForest<'a, 'b> {
         let y_slice = y.slice(s![..]);
 
         let X_slice = self.X.slice(s![start..stop, ..]);
-        let parameters = self.control().random_forest_parameters.clone();
+        let mut parameters = self.control().random_forest_parameters.clone();
+        if let Some(tree_scaling) = &self.control().tree_scaling {
+            parameters = parameters
+                .with_n_estimators(tree_scaling.n_estimators(stop - start, self.n()))
+                .with_max_depth(tree_scaling.max_depth(stop - start));
+        }
 
         let mut forest = BioForest::new(parameters);
         let mut predictions = forest.fit_predict_oob(&X_slice, &y_slice);
@@ -59,7 +64,7 @@ mod tests {
     use crate::gain::ClassifierGain;
     use crate::optimizer::{Optimizer, TwoStepSearch};
     use crate::testing;
-    use crate::Control;
+    use crate::{Control, TreeScaling};
     use csv::ReaderBuilder;
     use ndarray::Array2;
     use ndarray_csv::Array2Reader;
@@ -67,17 +72,24 @@ mod tests {
     use std::fs::File;
 
     #[rstest]
-    #[case(0, 50, 100)]
-    #[case(0, 100, 150)]
-    #[case(50, 100, 150)]
-    #[case(0, 50, 150)]
-    fn test_predictions(#[case] start: usize, #[case] split: usize, #[case] stop: usize) {
+    #[case(0, 50, 100, None)]
+    #[case(0, 100, 150, None)]
+    #[case(50, 100, 150, None)]
+    #[case(0, 50, 150, None)]
+    #[case(0, 50, 100, Some(TreeScaling::default()))]
+    #[case(0, 100, 150, Some(TreeScaling::default()))]
+    fn test_predictions(
+        #[case] start: usize,
+        #[case] split: usize,
+        #[case] stop: usize,
+        #[case] tree_scaling: Option<TreeScaling>,
+    ) {
         let file = File::open("testdata/iris.csv").unwrap();
         let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
         let X: Array2<f64> = reader.deserialize_array2((150, 4)).unwrap();
         let X_view = X.view();
 
-        let control = Control::default();
+        let control = Control::default().with_tree_scaling(tree_scaling);
 
         let rf = RandomForest::new(&X_view, &control);
         let predictions = rf.predict(start, stop, split);
@@ -98,7 +110,7 @@ mod tests {
         let control = Control::default().with_minimal_relative_segment_length(0.01);
         let classifier = RandomForest::new(&X_view, &control);
         let gain = ClassifierGain { classifier };
-        let optimizer = TwoStepSearch { gain };
+        let optimizer = TwoStepSearch::new(gain);
 
         assert_eq!(
             expected,
@@ -1,4 +1,149 @@
+use crate::classifier::ExtraTreesParameters;
+use crate::optimizer::OptimizerResult;
 use biosphere::{MaxFeatures, RandomForestParameters};
+use std::fmt;
+use std::sync::Arc;
+
+/// Information criterion used in model selection for likelihood-based gains.
+///
+/// A split is kept if twice the log-likelihood ratio, the gain, exceeds the penalty.
+/// Here `n` is the total number of observations and `p` the number of additional
+/// parameters introduced through an additional change point.
+#[derive(Clone)]
+pub enum Penalty {
+    /// Bayesian information criterion with penalty `log(n) * p`. See also
+    /// Yao, Y.-C. (1988). Estimating the number of change-points via Schwarz’ criterion.
+    BIC,
+    /// Akaike information criterion with penalty `2 * p`.
+    AIC,
+    /// Modified BIC with penalty `(p + 1) * log(n) + log(n_left * n_right / (n_segment * n))`.
+    /// See Zhang, N. R. and Siegmund, D. O. (2007). A modified Bayes information
+    /// criterion with applications to the analysis of comparative genomic hybridization
+    /// data. Biometrics 63 22–32.
+    MBIC,
+    /// Hannan–Quinn information criterion with penalty `2 * log(log(n)) * p`.
+    HQC,
+    /// User-supplied penalty as a function of `n` and `p`.
+    Custom(Arc<dyn Fn(usize, usize) -> f64 + Send + Sync>),
+}
+
+impl Penalty {
+    /// Minimal gain for the split in `optimizer_result` to be kept.
+    pub fn minimal_gain_to_split(
+        &self,
+        n: usize,
+        n_parameters: usize,
+        optimizer_result: &OptimizerResult,
+    ) -> f64 {
+        let n_float = n as f64;
+        let p = n_parameters as f64;
+
+        match self {
+            Penalty::BIC => n_float.ln() * p,
+            Penalty::AIC => 2. * p,
+            Penalty::MBIC => {
+                let n_left = (optimizer_result.best_split - optimizer_result.start) as f64;
+                let n_right = (optimizer_result.stop - optimizer_result.best_split) as f64;
+                let n_segment = (optimizer_result.stop - optimizer_result.start) as f64;
+                (p + 1.) * n_float.ln() + (n_left * n_right / (n_segment * n_float)).ln()
+            }
+            Penalty::HQC => 2. * n_float.ln().ln() * p,
+            Penalty::Custom(penalty) => penalty(n, n_parameters),
+        }
+    }
+}
+
+impl fmt::Debug for Penalty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Penalty::BIC => write!(f, "BIC"),
+            Penalty::AIC => write!(f, "AIC"),
+            Penalty::MBIC => write!(f, "MBIC"),
+            Penalty::HQC => write!(f, "HQC"),
+            Penalty::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+/// How to permute observations in the permutation test of classifier-based
+/// changepoint detection.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PermutationType {
+    /// Permute individual observations. Assumes exchangeability under the null.
+    Observations,
+    /// Permute blocks of consecutive observations. Retains serial dependence within
+    /// blocks.
+    Blocks,
+    /// Circularly shift the observations by a random offset. Retains serial dependence
+    /// except at the wrap-around.
+    CircularShift,
+}
+
+/// Recalibration of classifier probabilities before computing log-likelihood ratios.
+///
+/// Probabilities are recalibrated per classifier fit against the labels implied by the
+/// split, i.e., `0` for observations before and `1` for observations after the split.
+/// Fitted calibrations are cross-fitted on observations with even and odd indices, such
+/// that the calibration does not inflate the gain.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Calibration {
+    /// Use the out-of-sample probabilities as is.
+    None,
+    /// Temperature scaling `p -> sigmoid(logit(p) / temperature)`. Temperatures above
+    /// one shrink overconfident probabilities towards `1/2`. If `None`, the temperature
+    /// is fitted by minimizing the log loss.
+    Temperature(Option<f64>),
+    /// Isotonic regression of the labels on the probabilities.
+    Isotonic,
+}
+
+/// Scale the number of trees and their maximal depth with the segment length.
+///
+/// For a segment of length `m` out of `n` observations, tree ensembles use
+/// `max(min_n_estimators, ceil(n_estimators * m / n))` trees of depth at most
+/// `min(max_depth, ceil(log2(m)))`. Short segments, which are fit most often in binary
+/// segmentation, then do not pay for as many and as deep trees as the full data. When
+/// used, this overrides the number of trees and maximal depth of
+/// `Control.random_forest_parameters` and `Control.extra_trees_parameters`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeScaling {
+    /// Number of trees for a segment of length `n`.
+    pub n_estimators: usize,
+    /// Minimal number of trees for short segments.
+    pub min_n_estimators: usize,
+    /// Maximal depth of trees for long segments. `None` means unbounded.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for TreeScaling {
+    fn default() -> Self {
+        TreeScaling {
+            n_estimators: 100,
+            min_n_estimators: 10,
+            max_depth: Some(8),
+        }
+    }
+}
+
+impl TreeScaling {
+    /// Number of trees for a segment of length `segment_length` out of `n`.
+    pub fn n_estimators(&self, segment_length: usize, n: usize) -> usize {
+        let scaled = (self.n_estimators * segment_length).div_ceil(n.max(1));
+        scaled
+            .max(self.min_n_estimators)
+            .min(self.n_estimators.max(self.min_n_estimators))
+    }
+
+    /// Maximal depth of trees for a segment of length `segment_length`.
+    ///
+    /// This is `max_depth`, but at most `ceil(log2(segment_length))`, or `None` if
+    /// `max_depth` is `None`.
+    pub fn max_depth(&self, segment_length: usize) -> Option<usize> {
+        // ceil(log2(m)) levels suffice to separate m observations in a balanced tree.
+        let depth = (segment_length.max(2) as f64).log2().ceil() as usize;
+        self.max_depth.map(|max_depth| max_depth.min(depth))
+    }
+}
 
 /// Storage container for hyperparameters.
 #[derive(Clone, Debug)]
@@ -7,16 +152,51 @@ pub struct Control {
     /// not be split.
     pub minimal_relative_segment_length: f64,
     /// Only keep split point if the gain exceeds `minimal_gain_to_split`. Relevant for
-    /// change in mean. Note that this is relative to the number of observations.
-    /// Use value motivated by BIC `minimal_gain_to_split = log(n_samples) * n_features / n_samples`
-    /// if `None`.
+    /// change in mean. Use the value implied by `penalty` if `None`.
     pub minimal_gain_to_split: Option<f64>,
+    /// Information criterion used to determine the minimal gain to split if
+    /// `minimal_gain_to_split` is `None`. Relevant for change in mean.
+    pub penalty: Penalty,
+    /// Whether to standardize each feature with a robust estimate of its noise level,
+    /// the median absolute deviation of first differences, before computing the gain.
+    /// Relevant for change in mean.
+    pub standardize: bool,
     /// Type two error in model selection to be approximated. Relevant for classifier-
     /// based changepoint detection.
     pub model_selection_alpha: f64,
     /// Number of permutations for model selection in classifier-based change point
     /// detection.
     pub model_selection_n_permutations: usize,
+    /// How to permute observations in the permutation test for model selection in
+    /// classifier-based change point detection. Use `PermutationType::Blocks` or
+    /// `PermutationType::CircularShift` for serially dependent data.
+    pub model_selection_permutation_type: PermutationType,
+    /// Block length for `PermutationType::Blocks`. If `None`, the block length is
+    /// chosen automatically from the classifier log-likelihood ratios of the first
+    /// initial guess, after removing their mean shifts at the guess and at the best
+    /// split, see `utils::automatic_block_length`.
+    pub model_selection_block_length: Option<usize>,
+    /// Classifier probabilities `p` are shrunk to `likelihood_shrinkage + (1 -
+    /// likelihood_shrinkage) * p` before taking logarithms, which bounds the
+    /// log-likelihood of a single observation from below by `ln(likelihood_shrinkage)`.
+    /// Larger values dampen the influence of overconfident classifiers. Relevant for
+    /// classifier-based changepoint detection.
+    pub likelihood_shrinkage: f64,
+    /// Recalibration of classifier probabilities before computing log-likelihood
+    /// ratios. Relevant for classifier-based changepoint detection.
+    pub calibration: Calibration,
+    /// Number of equally spaced initial guesses in the first step of `TwoStepSearch`.
+    pub two_step_search_n_guesses: usize,
+    /// Maximal number of refits at the best split in the second step of
+    /// `TwoStepSearch`. Refitting stops early once the best split no longer changes.
+    pub two_step_search_n_refinements: usize,
+    /// Whether to follow the first step of `TwoStepSearch` by a golden-section search
+    /// for the guess with the largest maximal gain.
+    pub two_step_search_bracketing: bool,
+    /// Whether to reuse classifier fits on enclosing segments, e.g. of the parent in
+    /// binary segmentation, instead of fitting classifiers at three initial guesses.
+    /// Relevant for classifier-based changepoint detection with `TwoStepSearch`.
+    pub warm_start: bool,
     /// Number of randomly drawn segments. Corresponds to parameter `M` in
     /// https://arxiv.org/pdf/1411.0858.pdf.
     pub number_of_wild_segments: usize,
@@ -28,8 +208,31 @@ pub struct Control {
     pub seed: u64,
     /// Hyperparameters for random forests.
     pub random_forest_parameters: RandomForestParameters,
+    /// Hyperparameters for extremely randomized trees.
+    pub extra_trees_parameters: ExtraTreesParameters,
+    /// If not `None`, scale the number of trees and their depth with the segment
+    /// length. Relevant for tree-based changepoint detection.
+    pub tree_scaling: Option<TreeScaling>,
     /// Segments of indexes were no segmentation is allowed.
     pub forbidden_segments: Option<Vec<(usize, usize)>>,
+    /// Monotone time index with one entry per observation, e.g. seconds since epoch.
+    /// Required for `minimal_segment_duration` and `forbidden_time_segments`.
+    pub time_index: Option<Vec<f64>>,
+    /// Segments whose first and last observations are less than
+    /// `minimal_segment_duration` apart in `time_index` will not be created. Applies in
+    /// addition to `minimal_relative_segment_length`.
+    pub minimal_segment_duration: Option<f64>,
+    /// Intervals `[a, b]` of `time_index` within which no segmentation is allowed.
+    pub forbidden_time_segments: Option<Vec<(f64, f64)>>,
+    /// Number of lagged copies of the features added before fitting classifiers.
+    /// Relevant for classifier-based changepoint detection.
+    pub embedding_lags: usize,
+    /// Window sizes of rolling means and standard deviations of the features added
+    /// before fitting classifiers. Relevant for classifier-based changepoint detection.
+    pub embedding_windows: Vec<usize>,
+    /// Whether to add first differences of the features before fitting classifiers.
+    /// Relevant for classifier-based changepoint detection.
+    pub embedding_differences: bool,
 }
 
 impl Control {
@@ -38,8 +241,18 @@ impl Control {
         Control {
             minimal_relative_segment_length: 0.01,
             minimal_gain_to_split: None,
+            penalty: Penalty::BIC,
+            standardize: false,
             model_selection_alpha: 0.02,
             model_selection_n_permutations: 199,
+            model_selection_permutation_type: PermutationType::Observations,
+            model_selection_block_length: None,
+            likelihood_shrinkage: (-6_f64).exp(),
+            calibration: Calibration::None,
+            two_step_search_n_guesses: 3,
+            two_step_search_n_refinements: 1,
+            two_step_search_bracketing: false,
+            warm_start: false,
             number_of_wild_segments: 100,
             seeded_segments_alpha: std::f64::consts::FRAC_1_SQRT_2, // 1 / sqrt(2)
             seed: 0,
@@ -47,7 +260,15 @@ impl Control {
                 .with_max_depth(Some(8))
                 .with_max_features(MaxFeatures::Sqrt)
                 .with_n_jobs(Some(-1)),
+            extra_trees_parameters: ExtraTreesParameters::default(),
+            tree_scaling: None,
             forbidden_segments: None,
+            time_index: None,
+            minimal_segment_duration: None,
+            forbidden_time_segments: None,
+            embedding_lags: 0,
+            embedding_windows: vec![],
+            embedding_differences: false,
         }
     }
 
@@ -69,6 +290,16 @@ impl Control {
         self
     }
 
+    pub fn with_penalty(mut self, penalty: Penalty) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    pub fn with_standardize(mut self, standardize: bool) -> Self {
+        self.standardize = standardize;
+        self
+    }
+
     pub fn with_model_selection_alpha(mut self, model_selection_alpha: f64) -> Self {
         if (model_selection_alpha >= 1.) | (model_selection_alpha <= 0.) {
             panic!(
@@ -87,6 +318,74 @@ impl Control {
         self
     }
 
+    pub fn with_model_selection_permutation_type(
+        mut self,
+        model_selection_permutation_type: PermutationType,
+    ) -> Self {
+        self.model_selection_permutation_type = model_selection_permutation_type;
+        self
+    }
+
+    pub fn with_model_selection_block_length(
+        mut self,
+        model_selection_block_length: Option<usize>,
+    ) -> Self {
+        if model_selection_block_length == Some(0) {
+            panic!("model_selection_block_length needs to be strictly positive. Got 0");
+        }
+        self.model_selection_block_length = model_selection_block_length;
+        self
+    }
+
+    pub fn with_likelihood_shrinkage(mut self, likelihood_shrinkage: f64) -> Self {
+        if !(0. < likelihood_shrinkage && likelihood_shrinkage < 1.) {
+            panic!(
+                "likelihood_shrinkage needs to be strictly between 0 and 1. Got {likelihood_shrinkage}"
+            );
+        }
+        self.likelihood_shrinkage = likelihood_shrinkage;
+        self
+    }
+
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        if let Calibration::Temperature(Some(temperature)) = calibration {
+            if !(temperature > 0. && temperature.is_finite()) {
+                panic!("temperature needs to be strictly positive. Got {temperature}");
+            }
+        }
+        self.calibration = calibration;
+        self
+    }
+
+    pub fn with_two_step_search_n_guesses(mut self, two_step_search_n_guesses: usize) -> Self {
+        if two_step_search_n_guesses == 0 {
+            panic!("two_step_search_n_guesses needs to be strictly positive. Got 0");
+        }
+        self.two_step_search_n_guesses = two_step_search_n_guesses;
+        self
+    }
+
+    pub fn with_two_step_search_n_refinements(
+        mut self,
+        two_step_search_n_refinements: usize,
+    ) -> Self {
+        if two_step_search_n_refinements == 0 {
+            panic!("two_step_search_n_refinements needs to be strictly positive. Got 0");
+        }
+        self.two_step_search_n_refinements = two_step_search_n_refinements;
+        self
+    }
+
+    pub fn with_two_step_search_bracketing(mut self, two_step_search_bracketing: bool) -> Self {
+        self.two_step_search_bracketing = two_step_search_bracketing;
+        self
+    }
+
+    pub fn with_warm_start(mut self, warm_start: bool) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
     pub fn with_number_of_wild_segments(mut self, number_of_wild_segments: usize) -> Self {
         self.number_of_wild_segments = number_of_wild_segments;
         self
@@ -115,6 +414,26 @@ impl Control {
         self
     }
 
+    pub fn with_extra_trees_parameters(
+        mut self,
+        extra_trees_parameters: ExtraTreesParameters,
+    ) -> Self {
+        self.extra_trees_parameters = extra_trees_parameters;
+        self
+    }
+
+    pub fn with_tree_scaling(mut self, tree_scaling: Option<TreeScaling>) -> Self {
+        if let Some(ref tree_scaling) = tree_scaling {
+            if (tree_scaling.n_estimators == 0) | (tree_scaling.min_n_estimators == 0) {
+                panic!(
+                    "tree_scaling needs a strictly positive number of trees. Got {tree_scaling:?}"
+                );
+            }
+        }
+        self.tree_scaling = tree_scaling;
+        self
+    }
+
     pub fn with_forbidden_segments(
         mut self,
         forbidden_segments: Option<Vec<(usize, usize)>>,
@@ -130,4 +449,123 @@ impl Control {
         self.forbidden_segments = forbidden_segments;
         self
     }
+
+    pub fn with_time_index(mut self, time_index: Option<Vec<f64>>) -> Self {
+        if let Some(ref _time_index) = time_index {
+            if _time_index.iter().any(|t| t.is_nan())
+                || _time_index.windows(2).any(|window| window[0] > window[1])
+            {
+                panic!("time_index needs to be non-decreasing and must not contain NaN.");
+            }
+        }
+        self.time_index = time_index;
+        self
+    }
+
+    pub fn with_minimal_segment_duration(mut self, minimal_segment_duration: Option<f64>) -> Self {
+        if let Some(duration) = minimal_segment_duration {
+            if duration.is_nan() || duration < 0. {
+                panic!("minimal_segment_duration needs to be non-negative. Got {duration}");
+            }
+        }
+        self.minimal_segment_duration = minimal_segment_duration;
+        self
+    }
+
+    pub fn with_forbidden_time_segments(
+        mut self,
+        forbidden_time_segments: Option<Vec<(f64, f64)>>,
+    ) -> Self {
+        if let Some(ref _forbidden_time_segments) = forbidden_time_segments {
+            for el in _forbidden_time_segments.iter() {
+                if el.0.is_nan() || el.1.is_nan() || el.0 > el.1 {
+                    panic!(
+                        "Forbidden time segments must be specified as [(a,b), ...] where a <= b!"
+                    );
+                }
+            }
+        }
+        self.forbidden_time_segments = forbidden_time_segments;
+        self
+    }
+
+    /// Minimal gain for the split in `optimizer_result` to be kept by a likelihood-based
+    /// gain with `n` observations and `n_parameters` additional parameters through an
+    /// additional change point. This is `minimal_gain_to_split` if set and the value
+    /// implied by `penalty` otherwise.
+    pub fn penalized_minimal_gain_to_split(
+        &self,
+        n: usize,
+        n_parameters: usize,
+        optimizer_result: &OptimizerResult,
+    ) -> f64 {
+        match self.minimal_gain_to_split {
+            Some(minimal_gain_to_split) => minimal_gain_to_split,
+            None => self
+                .penalty
+                .minimal_gain_to_split(n, n_parameters, optimizer_result),
+        }
+    }
+
+    /// Whether split candidates are restricted beyond the minimal segment length, by
+    /// forbidden segments or a minimal segment duration.
+    pub fn restricts_split_candidates(&self) -> bool {
+        self.forbidden_segments.is_some()
+            || self.minimal_segment_duration.is_some()
+            || self.forbidden_time_segments.is_some()
+    }
+
+    /// Check that the time based constraints can be resolved for `n` observations.
+    ///
+    /// Panics if `time_index` does not have `n` entries or is missing while
+    /// `minimal_segment_duration` or `forbidden_time_segments` are set.
+    pub fn check_time_index(&self, n: usize) {
+        match &self.time_index {
+            Some(time_index) if time_index.len() != n => panic!(
+                "time_index must have one entry per observation. Got {} entries for {} observations.",
+                time_index.len(),
+                n
+            ),
+            None if self.minimal_segment_duration.is_some()
+                || self.forbidden_time_segments.is_some() =>
+            {
+                panic!("minimal_segment_duration and forbidden_time_segments require a time_index.")
+            }
+            _ => (),
+        }
+    }
+
+    pub fn with_embedding_lags(mut self, embedding_lags: usize) -> Self {
+        self.embedding_lags = embedding_lags;
+        self
+    }
+
+    pub fn with_embedding_windows(mut self, embedding_windows: Vec<usize>) -> Self {
+        if embedding_windows.contains(&0) {
+            panic!("embedding_windows need to be strictly positive. Got {embedding_windows:?}");
+        }
+        self.embedding_windows = embedding_windows;
+        self
+    }
+
+    pub fn with_embedding_differences(mut self, embedding_differences: bool) -> Self {
+        self.embedding_differences = embedding_differences;
+        self
+    }
+
+    /// Number of initial observations without complete embedded features.
+    ///
+    /// Classifier-based methods do not split within the first `embedding_burn_in`
+    /// observations, see `embedding::burn_in_control`.
+    pub fn embedding_burn_in(&self) -> usize {
+        let windows = self
+            .embedding_windows
+            .iter()
+            .map(|window| window - 1)
+            .max()
+            .unwrap_or(0);
+        self.embedding_lags
+            .max(windows)
+            .max(self.embedding_differences as usize)
+    }
 }
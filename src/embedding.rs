@@ -0,0 +1,156 @@
+use crate::Control;
+use ndarray::{concatenate, s, Array2, ArrayView2, Axis};
+
+/// Augment the features `X` with lagged, windowed and differenced features.
+///
+/// Classifiers such as `kNN` and `RandomForest` treat observations as independent.
+/// Embedding the features allows them to pick up changes in the serial structure of a
+/// time series. The returned array has the same number of rows as `X`, such that split
+/// points refer to the original observations. Its columns are, in order:
+/// - the original features,
+/// - `control.embedding_lags` lagged copies of the features, `x_{t-1}, ..., x_{t-l}`,
+/// - for each window size `w` in `control.embedding_windows`, rolling means and
+///   standard deviations of the features over `x_{t-w+1}, ..., x_t`,
+/// - first differences `x_t - x_{t-1}` if `control.embedding_differences`.
+///
+/// For the first `control.embedding_burn_in()` observations, lags refer to the first
+/// observation, windows are truncated and differences are zero. Methods fit on the
+/// embedded features should not split within the burn-in, see `burn_in_control`.
+pub fn embed(X: &ArrayView2<'_, f64>, control: &Control) -> Array2<f64> {
+    let mut features = vec![X.to_owned()];
+
+    for lag in 1..=control.embedding_lags {
+        features.push(lagged(X, lag));
+    }
+
+    for window in control.embedding_windows.iter() {
+        let (mean, std) = rolling_mean_std(X, *window);
+        features.push(mean);
+        features.push(std);
+    }
+
+    if control.embedding_differences {
+        features.push(X - &lagged(X, 1));
+    }
+
+    let views: Vec<ArrayView2<f64>> = features.iter().map(|feature| feature.view()).collect();
+    concatenate(Axis(1), &views).unwrap()
+}
+
+/// Copy of `control` where splits within the burn-in of the embedding are forbidden.
+///
+/// Splits `x` with `x <= control.embedding_burn_in()` are added to
+/// `forbidden_segments`. Only methods fit on features from `embed` should use it.
+pub fn burn_in_control(control: &Control) -> Control {
+    let burn_in = control.embedding_burn_in();
+    if burn_in == 0 {
+        return control.clone();
+    }
+
+    let mut forbidden_segments = control.forbidden_segments.clone().unwrap_or_default();
+    forbidden_segments.push((0, burn_in));
+    control
+        .clone()
+        .with_forbidden_segments(Some(forbidden_segments))
+}
+
+/// `X` shifted down by `lag` rows. The first `lag` rows repeat the first row of `X`.
+fn lagged(X: &ArrayView2<'_, f64>, lag: usize) -> Array2<f64> {
+    let n = X.nrows();
+    let mut result = Array2::<f64>::zeros(X.raw_dim());
+    if n == 0 {
+        return result;
+    }
+
+    let lag = lag.min(n);
+    for i in 0..lag {
+        result.row_mut(i).assign(&X.row(0));
+    }
+    result
+        .slice_mut(s![lag.., ..])
+        .assign(&X.slice(s![..(n - lag), ..]));
+    result
+}
+
+/// Rolling means and (population) standard deviations over windows of size `window`
+/// ending at each observation. Windows are truncated at the start of `X`.
+fn rolling_mean_std(X: &ArrayView2<'_, f64>, window: usize) -> (Array2<f64>, Array2<f64>) {
+    let (n, d) = X.dim();
+
+    let mut X_cumsum = Array2::<f64>::zeros((n + 1, d));
+    X_cumsum.slice_mut(s![1.., ..]).assign(X);
+    X_cumsum.accumulate_axis_inplace(Axis(0), |&prev, curr| *curr += prev);
+
+    let mut X2_cumsum = Array2::<f64>::zeros((n + 1, d));
+    X2_cumsum.slice_mut(s![1.., ..]).assign(&X.mapv(|x| x * x));
+    X2_cumsum.accumulate_axis_inplace(Axis(0), |&prev, curr| *curr += prev);
+
+    let mut mean = Array2::<f64>::zeros((n, d));
+    let mut std = Array2::<f64>::zeros((n, d));
+    for i in 0..n {
+        let start = (i + 1).saturating_sub(window);
+        let m = (i + 1 - start) as f64;
+        for j in 0..d {
+            let mu = (X_cumsum[[i + 1, j]] - X_cumsum[[start, j]]) / m;
+            let variance = (X2_cumsum[[i + 1, j]] - X2_cumsum[[start, j]]) / m - mu * mu;
+            mean[[i, j]] = mu;
+            // Avoid NaNs from negative variances due to rounding errors.
+            std[[i, j]] = variance.max(0.).sqrt();
+        }
+    }
+
+    (mean, std)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use assert_approx_eq::*;
+    use rstest::*;
+
+    #[test]
+    fn test_embed_default() {
+        let X = ndarray::array![[1., 2.], [3., 4.], [5., 6.]];
+        let control = Control::default();
+        assert_eq!(embed(&X.view(), &control), X);
+    }
+
+    #[rstest]
+    #[case(1, ndarray::array![[1.], [1.], [3.], [6.]])]
+    #[case(2, ndarray::array![[1.], [1.], [1.], [3.]])]
+    #[case(5, ndarray::array![[1.], [1.], [1.], [1.]])]
+    fn test_lagged(#[case] lag: usize, #[case] expected: Array2<f64>) {
+        let X = ndarray::array![[1.], [3.], [6.], [2.]];
+        assert_eq!(lagged(&X.view(), lag), expected);
+    }
+
+    #[test]
+    fn test_rolling_mean_std() {
+        let X = ndarray::array![[1.], [3.], [6.], [2.]];
+        let (mean, std) = rolling_mean_std(&X.view(), 2);
+        assert_eq!(mean, ndarray::array![[1.], [2.], [4.5], [4.]]);
+        assert_eq!(std, ndarray::array![[0.], [1.], [1.5], [2.]]);
+    }
+
+    #[test]
+    fn test_embed() {
+        let X = ndarray::array![[1.], [3.], [6.], [2.]];
+        let control = Control::default()
+            .with_embedding_lags(1)
+            .with_embedding_windows(vec![3])
+            .with_embedding_differences(true);
+
+        let embedded = embed(&X.view(), &control);
+        assert_eq!(embedded.shape(), &[4, 5]);
+        assert_eq!(embedded.column(0), X.column(0));
+        assert_eq!(embedded.column(1), ndarray::array![1., 1., 3., 6.]);
+        assert_eq!(
+            embedded.column(2),
+            ndarray::array![1., 2., 10. / 3., 11. / 3.]
+        );
+        assert_approx_eq!(embedded[[3, 3]], (26_f64 / 9.).sqrt());
+        assert_eq!(embedded.column(4), ndarray::array![0., 2., 3., -4.]);
+        assert_eq!(control.embedding_burn_in(), 2);
+    }
+}
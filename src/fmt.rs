@@ -69,9 +69,9 @@ fn _format_tree(result: &BinarySegmentationResult) -> Vec<Vec<String>> {
         _display_option(&result.model_selection_result.p_value),
     ]];
 
-    if result.left.is_some() {
-        let mut left = _format_tree(result.left.as_ref().unwrap());
-        let mut right = _format_tree(result.right.as_ref().unwrap());
+    if let (Some(left), Some(right)) = (&result.left, &result.right) {
+        let mut left = _format_tree(left);
+        let mut right = _format_tree(right);
         left[0][0] = format!(" ¦--{}", left[0][0]);
         right[0][0] = format!(" °--{}", right[0][0]);
 
@@ -116,6 +116,7 @@ mod tests {
                 best_split: 11,
                 max_gain: 1234567.23456,
                 gain_results: vec![],
+                n_initial_guesses: 0,
             }),
             left: Some(Box::new(BinarySegmentationResult {
                 start: 0,
@@ -127,6 +128,7 @@ mod tests {
                     best_split: 7,
                     max_gain: 0.1,
                     gain_results: vec![],
+                    n_initial_guesses: 0,
                 }),
                 left: Some(Box::new(BinarySegmentationResult {
                     start: 0,
@@ -136,6 +138,8 @@ mod tests {
                     left: None,
                     right: None,
                     segments: None,
+                    scale: None,
+                    time_index: None,
                 })),
                 right: Some(Box::new(BinarySegmentationResult {
                     start: 7,
@@ -145,8 +149,12 @@ mod tests {
                     left: None,
                     right: None,
                     segments: None,
+                    scale: None,
+                    time_index: None,
                 })),
                 segments: None,
+                scale: None,
+                time_index: None,
             })),
             right: Some(Box::new(BinarySegmentationResult {
                 start: 11,
@@ -156,8 +164,12 @@ mod tests {
                 left: None,
                 right: None,
                 segments: None,
+                scale: None,
+                time_index: None,
             })),
             segments: None,
+            scale: None,
+            time_index: None,
         };
 
         let output = _format_tree(&tree);
@@ -0,0 +1,248 @@
+use crate::control::Control;
+use crate::gain::Gain;
+use crate::optimizer::OptimizerResult;
+use crate::utils::residual_sum_of_squares;
+use ndarray::{s, Array1, Array2, Array3, ArrayView2};
+use std::cell::{Ref, RefCell};
+
+/// Gain for changes in the autoregressive structure of a time series.
+///
+/// Each column of `X` is modelled as an independent AR(`order`) process with intercept,
+/// `x_t = c + a_1 x_{t-1} + ... + a_p x_{t-p} + e_t`. The loss of a segment is
+/// `sum_j m log(RSS_j / m)`, where `RSS_j` is the residual sum of squares of the least
+/// squares fit of column `j` and `m` the number of observations in the segment. Lagged
+/// values may lie before the start of the segment, i.e. the likelihood is conditional
+/// on the preceding observations. The first `order` observations of `X` have no
+/// complete set of lagged values and are ignored.
+///
+/// Cumulative sums of the lag products are computed once, such that the loss of any
+/// segment only requires the solution of a `(p + 1) x (p + 1)` system of equations
+/// per column.
+pub struct ChangeInAutoregression<'a, 'b> {
+    X: &'a ArrayView2<'b, f64>,
+    order: usize,
+    cumsums: RefCell<Option<Vec<AutoregressionCumsums>>>,
+    control: &'a Control,
+}
+
+struct AutoregressionCumsums {
+    gram: Array3<f64>,
+    Zy: Array2<f64>,
+    yy: Array1<f64>,
+}
+
+impl<'a, 'b> ChangeInAutoregression<'a, 'b> {
+    pub fn new(
+        X: &'a ArrayView2<'b, f64>,
+        order: usize,
+        control: &'a Control,
+    ) -> ChangeInAutoregression<'a, 'b> {
+        ChangeInAutoregression {
+            X,
+            order,
+            cumsums: RefCell::new(Option::None),
+            control,
+        }
+    }
+
+    /// Order `p` of the autoregressive model.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Number of observations in `[start, stop)` with a complete set of lagged values.
+    fn n_observations(&self, start: usize, stop: usize) -> usize {
+        stop.saturating_sub(usize::max(start, self.order))
+    }
+
+    fn calculate_cumsums(&self) -> Vec<AutoregressionCumsums> {
+        let n = self.X.nrows();
+        let p = self.order + 1;
+
+        self.X
+            .columns()
+            .into_iter()
+            .map(|column| {
+                let mut gram = Array3::<f64>::zeros((n + 1, p, p));
+                let mut Zy = Array2::<f64>::zeros((n + 1, p));
+                let mut yy = Array1::<f64>::zeros(n + 1);
+                // z = (1, x_{t-1}, ..., x_{t-p})
+                let mut z = Array1::<f64>::ones(p);
+
+                for t in self.order..n {
+                    for k in 1..p {
+                        z[k] = column[t - k];
+                    }
+                    let y = column[t];
+
+                    for j in 0..p {
+                        for k in 0..p {
+                            gram[[t + 1, j, k]] = z[j] * z[k];
+                        }
+                        Zy[[t + 1, j]] = z[j] * y;
+                    }
+                    yy[t + 1] = y * y;
+                }
+
+                gram.accumulate_axis_inplace(ndarray::Axis(0), |&prev, curr| *curr += prev);
+                Zy.accumulate_axis_inplace(ndarray::Axis(0), |&prev, curr| *curr += prev);
+                yy.accumulate_axis_inplace(ndarray::Axis(0), |&prev, curr| *curr += prev);
+
+                AutoregressionCumsums { gram, Zy, yy }
+            })
+            .collect()
+    }
+
+    fn get_cumsums(&self) -> Ref<'_, Vec<AutoregressionCumsums>> {
+        if self.cumsums.borrow().is_none() {
+            self.cumsums.replace(Some(self.calculate_cumsums()));
+        }
+
+        Ref::map(self.cumsums.borrow(), |borrow| borrow.as_ref().unwrap())
+    }
+}
+
+impl<'a, 'b> Gain for ChangeInAutoregression<'a, 'b> {
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn loss(&self, start: usize, stop: usize) -> f64 {
+        let m = self.n_observations(start, stop);
+        if m == 0 {
+            return 0.;
+        }
+        let m = m as f64;
+
+        let mut result = 0.;
+        for cumsums in self.get_cumsums().iter() {
+            let gram =
+                &cumsums.gram.slice(s![stop, .., ..]) - &cumsums.gram.slice(s![start, .., ..]);
+            let Zy = &cumsums.Zy.slice(s![stop, ..]) - &cumsums.Zy.slice(s![start, ..]);
+            let yy = cumsums.yy[stop] - cumsums.yy[start];
+
+            let rss = residual_sum_of_squares(&gram.view(), &Zy.view(), yy);
+            // Avoid log(0) for perfect fits.
+            result += m * (f64::max(rss, f64::MIN_POSITIVE) / m).ln();
+        }
+        result
+    }
+
+    /// The gain is zero if any of the two segments has not more observations than
+    /// autoregressive coefficients, as the fit would be perfect.
+    fn gain(&self, start: usize, stop: usize, split: usize) -> f64 {
+        let p = self.order + 1;
+        if (self.n_observations(start, split) <= p) | (self.n_observations(split, stop) <= p) {
+            return 0.;
+        }
+
+        self.loss(start, stop) - self.loss(start, split) - self.loss(split, stop)
+    }
+
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        // An additional change point introduces p + 1 coefficients and one noise
+        // variance per feature and its location as additional parameters.
+        Some(self.control.penalized_minimal_gain_to_split(
+            self.n(),
+            self.X.ncols() * (self.order + 2) + 1,
+            optimizer_result,
+        ))
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::optimizer::{GridSearch, Optimizer};
+    use assert_approx_eq::*;
+    use ndarray_rand::rand_distr::Normal;
+    use ndarray_rand::RandomExt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    #[test]
+    fn test_cumsums() {
+        let X = ndarray::array![[1.], [2.], [4.], [3.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_autoregression = ChangeInAutoregression::new(&X_view, 1, &control);
+        let cumsums = change_in_autoregression.calculate_cumsums();
+
+        // The first observation is ignored. z_1 = (1, 1), z_2 = (1, 2), z_3 = (1, 4),
+        // y = (2, 4, 3).
+        let expected_gram = ndarray::array![
+            [[0., 0.], [0., 0.]],
+            [[0., 0.], [0., 0.]],
+            [[1., 1.], [1., 1.]],
+            [[2., 3.], [3., 5.]],
+            [[3., 7.], [7., 21.]]
+        ];
+        assert_eq!(cumsums[0].gram, expected_gram);
+        assert_eq!(
+            cumsums[0].Zy,
+            ndarray::array![[0., 0.], [0., 0.], [2., 2.], [6., 10.], [9., 22.]]
+        );
+        assert_eq!(cumsums[0].yy, ndarray::array![0., 0., 4., 20., 29.]);
+    }
+
+    #[rstest]
+    #[case(0, 8)]
+    #[case(0, 5)]
+    #[case(3, 8)]
+    fn test_loss(#[case] start: usize, #[case] stop: usize) {
+        // Compare to the loss from the residuals of an explicit AR(1) fit.
+        let X = ndarray::array![[0.5], [1.2], [0.3], [-0.4], [0.8], [1.9], [0.1], [-1.]];
+        let X_view = X.view();
+        let control = Control::default();
+        let change_in_autoregression = ChangeInAutoregression::new(&X_view, 1, &control);
+
+        let first = usize::max(start, 1);
+        let x = X.slice(s![(first - 1)..(stop - 1), 0]);
+        let y = X.slice(s![first..stop, 0]);
+        let x_mean = x.mean().unwrap();
+        let y_mean = y.mean().unwrap();
+        let slope = ((&x - x_mean) * (&y - y_mean)).sum() / (&x - x_mean).mapv(|a| a * a).sum();
+        let intercept = y_mean - slope * x_mean;
+        let rss = (&y - &x.mapv(|a| intercept + slope * a))
+            .mapv(|a| a * a)
+            .sum();
+        let m = (stop - first) as f64;
+
+        assert_approx_eq!(
+            change_in_autoregression.loss(start, stop),
+            m * (rss / m).ln()
+        );
+    }
+
+    #[test]
+    fn test_change_in_autoregression_find_best_split() {
+        // AR(1) with coefficient 0.8 before and -0.8 after 150. The marginal
+        // distribution is unchanged.
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = Array2::random_using((300, 1), Normal::new(0., 1.).unwrap(), &mut rng);
+        let mut X = Array2::<f64>::zeros((300, 1));
+        for t in 1..300 {
+            let coefficient = if t < 150 { 0.8 } else { -0.8 };
+            X[[t, 0]] = coefficient * X[[t - 1, 0]] + noise[[t, 0]];
+        }
+        let X_view = X.view();
+        let control = Control::default();
+
+        let optimizer = GridSearch {
+            gain: ChangeInAutoregression::new(&X_view, 1, &control),
+        };
+        let optimizer_result = optimizer.find_best_split(0, 300).unwrap();
+        assert!((optimizer_result.best_split as i64 - 150).abs() <= 5);
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+
+        let optimizer_result = optimizer.find_best_split(0, 150).unwrap();
+        assert!(!optimizer.model_selection(&optimizer_result).is_significant);
+    }
+}
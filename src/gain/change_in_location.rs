@@ -0,0 +1,278 @@
+use crate::control::Control;
+use crate::gain::{FullGainResult, Gain};
+use crate::optimizer::OptimizerResult;
+use ndarray::{s, Array1, Array2, ArrayView2, Axis};
+
+/// Rank-based (Wilcoxon / Mann–Whitney) gain for changes in location.
+///
+/// Observations are replaced by their ranks within the segment `[start, stop)`. The
+/// gain is the sum over features of the squared standardized rank-sum statistics
+/// `12 W^2 / (k (m - k) (m + 1))`, where `m = stop - start`, `k = split - start` and
+/// `W` is the sum of centered ranks of `[start, split)`. Ties get mid-ranks and the
+/// factor `m + 1` is reduced by `sum_t (t^3 - t) / (m (m - 1))` over groups of `t` tied
+/// observations. Under the null of no change, this is approximately chi-squared
+/// distributed. As ranks are invariant to the scale
+/// of features, and single outliers can shift ranks by at most one, the gain is robust
+/// to heavy-tailed noise.
+pub struct ChangeInLocation<'a, 'b> {
+    X: &'a ArrayView2<'b, f64>,
+    control: &'a Control,
+}
+
+impl<'a, 'b> ChangeInLocation<'a, 'b> {
+    pub fn new(X: &'a ArrayView2<'b, f64>, control: &'a Control) -> ChangeInLocation<'a, 'b> {
+        ChangeInLocation { X, control }
+    }
+
+    /// Cumulative sums of centered ranks within `[start, stop)`.
+    ///
+    /// Returns an array of shape `(stop - start + 1, d)` with a leading row of zeros
+    /// and, for each feature, the tie-corrected factor `m + 1 - sum_t (t^3 - t) / (m (m -
+    /// 1))` of the variance of the rank sums. Ties get the average of their ranks.
+    fn calculate_rank_cumsum(&self, start: usize, stop: usize) -> (Array2<f64>, Array1<f64>) {
+        let m = stop - start;
+        let center = (m as f64 + 1.) / 2.;
+        let mut rank_cumsum = Array2::<f64>::zeros((m + 1, self.X.ncols()));
+        let mut tie_factors = Array1::<f64>::from_elem(self.X.ncols(), m as f64 + 1.);
+
+        for (idx, column) in self
+            .X
+            .slice(s![start..stop, ..])
+            .axis_iter(Axis(1))
+            .enumerate()
+        {
+            let mut order: Vec<usize> = (0..m).collect();
+            order.sort_unstable_by(|a, b| column[*a].total_cmp(&column[*b]));
+
+            let mut lower = 0;
+            while lower < m {
+                let mut upper = lower + 1;
+                while (upper < m) && (column[order[upper]] == column[order[lower]]) {
+                    upper += 1;
+                }
+                // Ranks lower + 1, ..., upper are tied. Assign their average.
+                let rank = (lower + upper + 1) as f64 / 2.;
+                for jdx in order.iter().take(upper).skip(lower) {
+                    rank_cumsum[[jdx + 1, idx]] = rank - center;
+                }
+                let ties = (upper - lower) as f64;
+                if ties > 1. {
+                    tie_factors[idx] -= (ties.powi(3) - ties) / (m * (m - 1)) as f64;
+                }
+                lower = upper;
+            }
+        }
+
+        rank_cumsum.accumulate_axis_inplace(Axis(0), |&prev, curr| *curr += prev);
+        (rank_cumsum, tie_factors)
+    }
+
+    /// Features that are constant within the segment do not contribute to the gain.
+    fn gain_from_rank_cumsum(
+        &self,
+        rank_cumsum: &Array2<f64>,
+        tie_factors: &Array1<f64>,
+        start: usize,
+        split: usize,
+    ) -> f64 {
+        let m = rank_cumsum.nrows() - 1;
+        let k = split - start;
+        if (k == 0) | (k == m) {
+            return 0.;
+        }
+
+        rank_cumsum
+            .slice(s![k, ..])
+            .iter()
+            .zip(tie_factors.iter())
+            .filter(|(_, &tie_factor)| tie_factor > 0.)
+            .map(|(x, tie_factor)| x.powi(2) / ((k * (m - k)) as f64 * tie_factor / 12.))
+            .sum()
+    }
+}
+
+impl<'a, 'b> Gain for ChangeInLocation<'a, 'b> {
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn gain(&self, start: usize, stop: usize, split: usize) -> f64 {
+        let (rank_cumsum, tie_factors) = self.calculate_rank_cumsum(start, stop);
+        self.gain_from_rank_cumsum(&rank_cumsum, &tie_factors, start, split)
+    }
+
+    /// Ranks are computed once per segment. The gain for each split is then
+    /// available in constant time.
+    fn gain_full(&self, start: usize, stop: usize, split_candidates: &[usize]) -> FullGainResult {
+        let (rank_cumsum, tie_factors) = self.calculate_rank_cumsum(start, stop);
+        let mut gain = ndarray::Array::from_elem(stop - start, f64::NAN);
+
+        for split_point in split_candidates {
+            gain[split_point - start] =
+                self.gain_from_rank_cumsum(&rank_cumsum, &tie_factors, start, *split_point);
+        }
+
+        FullGainResult {
+            start,
+            stop,
+            gain,
+            max_gain: None,
+            best_split: None,
+        }
+    }
+
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        // The gain behaves like twice the log-likelihood ratio of a Gaussian change
+        // in mean with d + 1 additional parameters.
+        Some(self.control.penalized_minimal_gain_to_split(
+            self.n(),
+            self.X.ncols() + 1,
+            optimizer_result,
+        ))
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::optimizer::{GridSearch, Optimizer};
+    use crate::testing;
+    use assert_approx_eq::*;
+    use rstest::*;
+
+    #[test]
+    fn test_rank_cumsum() {
+        let X = ndarray::array![[1., 0.], [3., 0.], [2., 5.], [-1., 0.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_location = ChangeInLocation::new(&X_view, &control);
+
+        // Ranks are [2, 4, 3, 1] and [2, 2, 4, 2] (ties). Centered by 2.5. The three
+        // tied observations reduce the factor 5 by (27 - 3) / 12.
+        let expected = ndarray::array![[0., 0.], [-0.5, -0.5], [1., -1.], [1.5, 0.5], [0., 0.]];
+        assert_eq!(
+            change_in_location.calculate_rank_cumsum(0, 4),
+            (expected, ndarray::array![5., 3.])
+        );
+
+        // Ranks of [3, 2] and [0, 5] are [2, 1] and [1, 2], centered by 1.5.
+        let expected = ndarray::array![[0., 0.], [0.5, -0.5], [0., 0.]];
+        assert_eq!(
+            change_in_location.calculate_rank_cumsum(1, 3),
+            (expected, ndarray::array![3., 3.])
+        );
+    }
+
+    #[rstest]
+    #[case(0, 4, 0, 0.)]
+    #[case(0, 4, 1, 12. * 0.25 / 15. + 12. * 0.25 / 9.)]
+    #[case(0, 4, 2, 12. * 1. / 20. + 12. * 1. / 12.)]
+    #[case(0, 4, 3, 12. * 2.25 / 15. + 12. * 0.25 / 9.)]
+    #[case(1, 3, 2, 12. * 0.25 / 3. + 12. * 0.25 / 3.)]
+    fn test_change_in_location_gain(
+        #[case] start: usize,
+        #[case] stop: usize,
+        #[case] split: usize,
+        #[case] expected: f64,
+    ) {
+        let X = ndarray::array![[1., 0.], [3., 0.], [2., 5.], [-1., 0.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_location = ChangeInLocation::new(&X_view, &control);
+        assert_approx_eq!(change_in_location.gain(start, stop, split), expected);
+        assert_approx_eq!(
+            change_in_location.gain_full(start, stop, &[split]).gain[split - start],
+            expected
+        );
+    }
+
+    #[test]
+    fn test_change_in_location_constant_feature() {
+        let X = ndarray::array![[1., 0.], [3., 0.], [2., 0.], [-1., 0.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_location = ChangeInLocation::new(&X_view, &control);
+        assert_approx_eq!(change_in_location.gain(0, 4, 2), 12. * 1. / 20.);
+    }
+
+    #[test]
+    fn test_change_in_location_nan() {
+        let X = ndarray::array![[1.], [f64::NAN], [2.], [-1.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_location = ChangeInLocation::new(&X_view, &control);
+        // NaN is ranked last. Ranks are [2, 4, 3, 1].
+        assert_approx_eq!(change_in_location.gain(0, 4, 2), 12. * 1. / 20.);
+    }
+
+    #[rstest]
+    #[case(None, 4_f64.ln() * 3.)]
+    #[case(Some(1.5), 1.5)]
+    fn test_change_in_location_minimal_gain_to_split(
+        #[case] minimal_gain_to_split: Option<f64>,
+        #[case] expected: f64,
+    ) {
+        let X = ndarray::array![[1., 0.], [3., 0.], [2., 5.], [-1., 0.]];
+        let X_view = X.view();
+        let control = Control::default().with_minimal_gain_to_split(minimal_gain_to_split);
+
+        let change_in_location = ChangeInLocation::new(&X_view, &control);
+        let mut optimizer_result = OptimizerResult {
+            start: 0,
+            stop: 4,
+            best_split: 2,
+            max_gain: expected - 0.1,
+            gain_results: vec![],
+            n_initial_guesses: 0,
+        };
+        assert_approx_eq!(
+            change_in_location
+                .minimal_gain_to_split(&optimizer_result)
+                .unwrap(),
+            expected
+        );
+        assert!(
+            !change_in_location
+                .model_selection(&optimizer_result)
+                .is_significant
+        );
+        optimizer_result.max_gain = expected + 0.1;
+        assert!(
+            change_in_location
+                .model_selection(&optimizer_result)
+                .is_significant
+        );
+    }
+
+    #[test]
+    fn test_change_in_location_outlier() {
+        let mut X = testing::array();
+        X[[60, 0]] = 1000.;
+        X[[61, 3]] = -1000.;
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_location = ChangeInLocation::new(&X_view, &control);
+        let optimizer = GridSearch {
+            gain: change_in_location,
+        };
+        let optimizer_result = optimizer.find_best_split(40, 80).unwrap();
+        assert!(!optimizer.model_selection(&optimizer_result).is_significant);
+
+        let change_in_mean = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch {
+            gain: change_in_mean,
+        };
+        let optimizer_result = optimizer.find_best_split(40, 80).unwrap();
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+    }
+}
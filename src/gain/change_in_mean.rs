@@ -1,28 +1,44 @@
 use crate::control::Control;
 use crate::gain::Gain;
 use crate::optimizer::OptimizerResult;
-use crate::ModelSelectionResult;
+use crate::utils::robust_scale;
 use std::cell::{Ref, RefCell};
 
 pub struct ChangeInMean<'a, 'b> {
     X: &'a ndarray::ArrayView2<'b, f64>,
     X_cumsum: RefCell<Option<ndarray::Array2<f64>>>,
+    scale: Option<ndarray::Array1<f64>>,
     control: &'a Control,
 }
 
 impl<'a, 'b> ChangeInMean<'a, 'b> {
     pub fn new(X: &'a ndarray::ArrayView2<'b, f64>, control: &'a Control) -> ChangeInMean<'a, 'b> {
+        let scale = if control.standardize {
+            Some(robust_scale(X))
+        } else {
+            None
+        };
+
         ChangeInMean {
             X,
             X_cumsum: RefCell::new(Option::None),
+            scale,
             control,
         }
     }
 
+    /// Estimated noise level of each feature if `control.standardize` is `true`.
+    pub fn scale(&self) -> Option<&ndarray::Array1<f64>> {
+        self.scale.as_ref()
+    }
+
     fn calculate_cumsum(&self) -> ndarray::Array2<f64> {
         let mut X_cumsum = ndarray::Array2::zeros((self.X.nrows() + 1, self.X.ncols()));
         let mut slice = X_cumsum.slice_mut(ndarray::s![1.., ..]);
-        slice += &self.X.view();
+        match &self.scale {
+            Some(scale) => slice += &(&self.X.view() / scale),
+            None => slice += &self.X.view(),
+        }
 
         X_cumsum.accumulate_axis_inplace(ndarray::Axis(0), |&prev, curr| *curr += prev);
         X_cumsum
@@ -62,19 +78,14 @@ impl<'a, 'b> Gain for ChangeInMean<'a, 'b> {
         result / (s * s_1 * s_2)
     }
 
-    fn model_selection(&self, optimizer_result: &OptimizerResult) -> ModelSelectionResult {
-        let minimal_gain_to_split = match self.control.minimal_gain_to_split {
-            Some(minimal_gain_to_split) => minimal_gain_to_split,
-            // log(n) * (d + 1), where (d + 1) is the number of additional parameters through
-            // an additional changepoint. See also
-            // Yao, Y.-C. (1988). Estimating the number of change-points via Schwarz’ criterion.
-            None => (self.X.shape()[0] as f64).ln() * (self.X.shape()[1] as f64 + 1.),
-        };
-
-        ModelSelectionResult {
-            is_significant: optimizer_result.max_gain > minimal_gain_to_split,
-            p_value: None,
-        }
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        // (d + 1) is the number of additional parameters through an additional
+        // changepoint.
+        Some(self.control.penalized_minimal_gain_to_split(
+            self.n(),
+            self.X.ncols() + 1,
+            optimizer_result,
+        ))
     }
 
     fn control(&self) -> &Control {
@@ -86,9 +97,11 @@ impl<'a, 'b> Gain for ChangeInMean<'a, 'b> {
 mod tests {
 
     use super::*;
+    use crate::control::Penalty;
     use crate::testing;
     use assert_approx_eq::*;
     use rstest::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_X_cumsum() {
@@ -103,6 +116,36 @@ mod tests {
         assert_eq!(X_cumsum, expected);
     }
 
+    #[test]
+    fn test_X_cumsum_standardize() {
+        let X = ndarray::array![[1., 0.], [3., 0.], [1., 2.], [3., 2.]];
+        let X_view = X.view();
+        let control = Control::default().with_standardize(true);
+
+        let change_in_mean = ChangeInMean::new(&X_view, &control);
+        // First differences are [2, -2, 2] and [0, 2, 0], both with a MAD of zero.
+        // Use a scale of one in that case.
+        assert_eq!(change_in_mean.scale().unwrap(), ndarray::array![1., 1.]);
+
+        let X = ndarray::array![[0., 0.], [2., 0.], [2., 2.], [6., 2.]];
+        let X_view = X.view();
+        let change_in_mean = ChangeInMean::new(&X_view, &control);
+        // First differences of the first column are [2, 0, 4], with median 2 and
+        // MAD 2.
+        let scale = 1.4826 * 2. / std::f64::consts::SQRT_2;
+        assert_eq!(change_in_mean.scale().unwrap(), ndarray::array![scale, 1.]);
+
+        let X_cumsum = change_in_mean.calculate_cumsum();
+        let expected = ndarray::array![
+            [0., 0.],
+            [0., 0.],
+            [2. / scale, 0.],
+            [4. / scale, 2.],
+            [10. / scale, 4.]
+        ];
+        assert!(X_cumsum.abs_diff_eq(&expected, 1e-12));
+    }
+
     #[rstest]
     #[case(0, 100)]
     #[case(0, 75)]
@@ -124,4 +167,45 @@ mod tests {
             );
         }
     }
+
+    #[rstest]
+    #[case(Penalty::BIC, 20., false)]
+    #[case(Penalty::BIC, 29., true)]
+    #[case(Penalty::AIC, 11., false)]
+    #[case(Penalty::AIC, 13., true)]
+    #[case(Penalty::MBIC, 29., false)]
+    #[case(Penalty::MBIC, 31., true)]
+    #[case(Penalty::HQC, 18., false)]
+    #[case(Penalty::HQC, 19., true)]
+    #[case(Penalty::Custom(Arc::new(|_, p| p as f64)), 5., false)]
+    #[case(Penalty::Custom(Arc::new(|_, p| p as f64)), 7., true)]
+    fn test_model_selection_penalty(
+        #[case] penalty: Penalty,
+        #[case] max_gain: f64,
+        #[case] expected: bool,
+    ) {
+        // n = 100, d = 5. The penalties are log(100) * 6 ~ 27.63 (BIC), 12 (AIC),
+        // 7 * log(100) + log(1 / 4) ~ 30.85 (MBIC) and 2 * log(log(100)) * 6 ~ 18.32
+        // (HQC).
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default().with_penalty(penalty);
+
+        let change_in_mean = ChangeInMean::new(&X_view, &control);
+        let optimizer_result = OptimizerResult {
+            start: 0,
+            stop: 100,
+            best_split: 50,
+            max_gain,
+            gain_results: vec![],
+            n_initial_guesses: 0,
+        };
+
+        assert_eq!(
+            change_in_mean
+                .model_selection(&optimizer_result)
+                .is_significant,
+            expected
+        );
+    }
 }
@@ -0,0 +1,196 @@
+use crate::control::Control;
+use crate::gain::Gain;
+use crate::optimizer::OptimizerResult;
+use ndarray::{Array2, ArrayView2, Axis};
+use std::cell::{Ref, RefCell};
+
+/// Gain for changes in the category frequencies of categorical features.
+///
+/// Each column of `X` contains integer codes of a categorical feature. The loss of a
+/// segment is twice the negative maximal multinomial log-likelihood,
+/// `-2 sum_j sum_k c_jk log(c_jk / m)`, where `m` is the segment length and `c_jk` the
+/// number of observations in the segment with category `k` of feature `j`. This only
+/// requires per-category cumulative counts.
+pub struct ChangeInMultinomial<'a, 'b> {
+    X: &'a ArrayView2<'b, usize>,
+    categories: Vec<Vec<usize>>,
+    counts: RefCell<Option<Vec<Array2<f64>>>>,
+    control: &'a Control,
+}
+
+impl<'a, 'b> ChangeInMultinomial<'a, 'b> {
+    pub fn new(X: &'a ArrayView2<'b, usize>, control: &'a Control) -> ChangeInMultinomial<'a, 'b> {
+        let categories = X
+            .axis_iter(Axis(1))
+            .map(|column| {
+                let mut values = column.to_vec();
+                values.sort_unstable();
+                values.dedup();
+                values
+            })
+            .collect();
+
+        ChangeInMultinomial {
+            X,
+            categories,
+            counts: RefCell::new(Option::None),
+            control,
+        }
+    }
+
+    /// Sorted distinct categories of each feature.
+    pub fn categories(&self) -> &Vec<Vec<usize>> {
+        &self.categories
+    }
+
+    /// Number of free parameters of the multinomial distributions, `sum_j (K_j - 1)`.
+    pub fn n_parameters(&self) -> usize {
+        self.categories
+            .iter()
+            .map(|categories| categories.len().saturating_sub(1))
+            .sum()
+    }
+
+    /// Cumulative counts of each category. Element `j` has shape `(n + 1, K_j)`.
+    fn calculate_counts(&self) -> Vec<Array2<f64>> {
+        let n = self.X.nrows();
+
+        self.X
+            .axis_iter(Axis(1))
+            .zip(self.categories.iter())
+            .map(|(column, categories)| {
+                let mut counts = Array2::<f64>::zeros((n + 1, categories.len()));
+                for (i, value) in column.iter().enumerate() {
+                    let idx = categories.binary_search(value).unwrap();
+                    counts[[i + 1, idx]] = 1.;
+                }
+                counts.accumulate_axis_inplace(Axis(0), |&prev, curr| *curr += prev);
+                counts
+            })
+            .collect()
+    }
+
+    fn get_counts(&self) -> Ref<'_, Vec<Array2<f64>>> {
+        if self.counts.borrow().is_none() {
+            self.counts.replace(Some(self.calculate_counts()));
+        }
+
+        Ref::map(self.counts.borrow(), |borrow| borrow.as_ref().unwrap())
+    }
+}
+
+impl<'a, 'b> Gain for ChangeInMultinomial<'a, 'b> {
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn loss(&self, start: usize, stop: usize) -> f64 {
+        if start == stop {
+            return 0.;
+        }
+
+        let counts = self.get_counts();
+        let m = (stop - start) as f64;
+
+        let mut result = 0.;
+        for column_counts in counts.iter() {
+            for idx in 0..column_counts.ncols() {
+                let count = column_counts[[stop, idx]] - column_counts[[start, idx]];
+                // count * log(count / m) -> 0 for count -> 0.
+                if count > 0. {
+                    result -= 2. * count * (count / m).ln();
+                }
+            }
+        }
+        result
+    }
+
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        // An additional changepoint introduces sum_j (K_j - 1) category
+        // probabilities and its location as additional parameters.
+        Some(self.control.penalized_minimal_gain_to_split(
+            self.n(),
+            self.n_parameters() + 1,
+            optimizer_result,
+        ))
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::optimizer::{GridSearch, Optimizer};
+    use assert_approx_eq::*;
+    use ndarray::{s, Array};
+    use ndarray_rand::rand_distr::{Distribution, WeightedIndex};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    #[test]
+    fn test_counts() {
+        let X = ndarray::array![[3, 0], [7, 0], [3, 1], [3, 0]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_multinomial = ChangeInMultinomial::new(&X_view, &control);
+        assert_eq!(
+            change_in_multinomial.categories(),
+            &vec![vec![3, 7], vec![0, 1]]
+        );
+        assert_eq!(change_in_multinomial.n_parameters(), 2);
+
+        let counts = change_in_multinomial.calculate_counts();
+        let expected = ndarray::array![[0., 0.], [1., 0.], [1., 1.], [2., 1.], [3., 1.]];
+        assert_eq!(counts[0], expected);
+        let expected = ndarray::array![[0., 0.], [1., 0.], [2., 0.], [2., 1.], [3., 1.]];
+        assert_eq!(counts[1], expected);
+    }
+
+    #[rstest]
+    #[case(0, 4, -2. * (3. * (0.75_f64).ln() + (0.25_f64).ln()) * 2.)]
+    #[case(0, 2, -2. * 2. * (0.5_f64).ln())]
+    #[case(2, 4, -2. * 2. * (0.5_f64).ln())]
+    #[case(3, 3, 0.)]
+    fn test_change_in_multinomial_loss(
+        #[case] start: usize,
+        #[case] stop: usize,
+        #[case] expected: f64,
+    ) {
+        let X = ndarray::array![[3, 0], [7, 0], [3, 1], [3, 0]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_multinomial = ChangeInMultinomial::new(&X_view, &control);
+        assert_approx_eq!(change_in_multinomial.loss(start, stop), expected);
+    }
+
+    #[test]
+    fn test_change_in_multinomial_find_best_split() {
+        // Machine states 0, 1, 2 with frequencies (0.6, 0.3, 0.1) before and
+        // (0.2, 0.3, 0.5) after 120. A second feature without change.
+        let mut rng = StdRng::seed_from_u64(0);
+        let before = WeightedIndex::new([0.6, 0.3, 0.1]).unwrap();
+        let after = WeightedIndex::new([0.2, 0.3, 0.5]).unwrap();
+        let mut X = Array::from_shape_fn((200, 2), |_| before.sample(&mut rng));
+        X.slice_mut(s![120.., 0])
+            .mapv_inplace(|_| after.sample(&mut rng));
+        let X_view = X.view();
+        let control = Control::default();
+
+        let optimizer = GridSearch {
+            gain: ChangeInMultinomial::new(&X_view, &control),
+        };
+        let optimizer_result = optimizer.find_best_split(0, 200).unwrap();
+        assert!((optimizer_result.best_split as i64 - 120).abs() <= 5);
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+
+        let optimizer_result = optimizer.find_best_split(0, 120).unwrap();
+        assert!(!optimizer.model_selection(&optimizer_result).is_significant);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::control::Control;
+use crate::gain::Gain;
+use crate::optimizer::OptimizerResult;
+use crate::utils::assert_counts;
+use ndarray::{s, Array1, Array2, ArrayView2, Axis};
+use std::cell::{Ref, RefCell};
+
+/// Gain for changes in the means of independent negative binomial distributed counts.
+///
+/// Accounts for overdispersion of counts. The negative binomial distribution with mean
+/// `mu` and size `r` has variance `mu + mu^2 / r`. The size `r_j` of each feature is
+/// assumed constant and estimated once, see `estimate_size`. For fixed size, the
+/// maximum likelihood estimate of the mean is the segment mean, and the loss of a
+/// segment is twice the negative maximal log-likelihood (up to terms that cancel in the
+/// gain), `-2 sum_j (S_j log(S_j / m) - (S_j + m r_j) log(1 + S_j / (m r_j)))`, where
+/// `m` is the segment length and `S_j` the sum of counts of feature `j` in the segment.
+/// For `r_j = inf`, this is the loss of `ChangeInPoissonRate`.
+pub struct ChangeInNegativeBinomialRate<'a, 'b> {
+    X: &'a ArrayView2<'b, f64>,
+    X_cumsum: RefCell<Option<Array2<f64>>>,
+    size: Array1<f64>,
+    control: &'a Control,
+}
+
+impl<'a, 'b> ChangeInNegativeBinomialRate<'a, 'b> {
+    /// Panics if `X` contains entries that are not non-negative integers.
+    pub fn new(
+        X: &'a ArrayView2<'b, f64>,
+        control: &'a Control,
+    ) -> ChangeInNegativeBinomialRate<'a, 'b> {
+        assert_counts(X);
+
+        ChangeInNegativeBinomialRate {
+            X,
+            X_cumsum: RefCell::new(Option::None),
+            size: estimate_size(X),
+            control,
+        }
+    }
+
+    /// Estimated size (inverse dispersion) of each feature.
+    pub fn size(&self) -> &Array1<f64> {
+        &self.size
+    }
+
+    fn calculate_cumsum(&self) -> Array2<f64> {
+        let mut X_cumsum = Array2::zeros((self.X.nrows() + 1, self.X.ncols()));
+        X_cumsum.slice_mut(s![1.., ..]).assign(self.X);
+
+        X_cumsum.accumulate_axis_inplace(Axis(0), |&prev, curr| *curr += prev);
+        X_cumsum
+    }
+
+    fn get_cumsum(&self) -> Ref<'_, Array2<f64>> {
+        if self.X_cumsum.borrow().is_none() {
+            self.X_cumsum.replace(Some(self.calculate_cumsum()));
+        }
+
+        Ref::map(self.X_cumsum.borrow(), |borrow| borrow.as_ref().unwrap())
+    }
+}
+
+/// Method of moments estimate of the size of each column of `X`.
+///
+/// The variance is estimated from first differences, `sum (x_i - x_{i-1})^2 / (2 (n -
+/// 1))`, which is robust to (few) changes in the mean. The size is then `mean^2 /
+/// (variance - mean)`. Columns without overdispersion get a size of `f64::INFINITY`.
+pub fn estimate_size(X: &ArrayView2<'_, f64>) -> Array1<f64> {
+    let n = X.nrows();
+    let mut size = Array1::<f64>::from_elem(X.ncols(), f64::INFINITY);
+
+    if n < 2 {
+        return size;
+    }
+
+    for (idx, column) in X.axis_iter(Axis(1)).enumerate() {
+        let mean = column.sum() / n as f64;
+        let variance = (1..n)
+            .map(|i| (column[i] - column[i - 1]).powi(2))
+            .sum::<f64>()
+            / (2. * (n - 1) as f64);
+        if variance > mean {
+            size[idx] = mean.powi(2) / (variance - mean);
+        }
+    }
+
+    size
+}
+
+impl<'a, 'b> Gain for ChangeInNegativeBinomialRate<'a, 'b> {
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn loss(&self, start: usize, stop: usize) -> f64 {
+        if start == stop {
+            return 0.;
+        }
+
+        let X_cumsum = self.get_cumsum();
+        let m = (stop - start) as f64;
+
+        let mut result = 0.;
+        for idx in 0..self.X.ncols() {
+            let sum = X_cumsum[[stop, idx]] - X_cumsum[[start, idx]];
+            // sum * log(sum / m) -> 0 for sum -> 0.
+            if sum == 0. {
+                continue;
+            }
+
+            let size = self.size[idx];
+            let mean = sum / m;
+            let log_likelihood = if size.is_finite() {
+                sum * mean.ln() - (sum + m * size) * (mean / size).ln_1p()
+            } else {
+                sum * mean.ln() - sum
+            };
+            result -= 2. * log_likelihood;
+        }
+        result
+    }
+
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        // (d + 1) is the number of additional parameters through an additional
+        // changepoint. The sizes are shared across segments.
+        Some(self.control.penalized_minimal_gain_to_split(
+            self.n(),
+            self.X.ncols() + 1,
+            optimizer_result,
+        ))
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::gain::ChangeInPoissonRate;
+    use crate::optimizer::{GridSearch, Optimizer};
+    use assert_approx_eq::*;
+    use ndarray::Array;
+    use ndarray_rand::rand_distr::{Distribution, Gamma, Poisson};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    /// Negative binomial counts with mean `mean` and size `size` as Gamma-Poisson mixture.
+    fn negative_binomial(n: usize, mean: f64, size: f64, rng: &mut StdRng) -> Array1<f64> {
+        let gamma = Gamma::new(size, mean / size).unwrap();
+        Array::from_shape_fn(n, |_| {
+            Poisson::new(gamma.sample(rng) + 1e-12).unwrap().sample(rng)
+        })
+    }
+
+    #[test]
+    fn test_estimate_size() {
+        // Differences are [2, -2, 2, -2, 2], variance 20 / 10 = 2, mean 1 and size 1.
+        // Second column has differences [1, 0, 0, 0, -1], variance 2 / 10 < mean.
+        let X = ndarray::array![[0., 1.], [2., 2.], [0., 2.], [2., 2.], [0., 2.], [2., 1.]];
+        let size = estimate_size(&X.view());
+        assert_approx_eq!(size[0], 1.);
+        assert_eq!(size[1], f64::INFINITY);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-negative integer counts")]
+    fn test_change_in_negative_binomial_rate_invalid_input() {
+        let X = ndarray::array![[1., 0.], [-2., 1.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        ChangeInNegativeBinomialRate::new(&X_view, &control);
+    }
+
+    #[rstest]
+    #[case(0, 4)]
+    #[case(0, 2)]
+    #[case(1, 3)]
+    #[case(2, 2)]
+    fn test_change_in_negative_binomial_rate_loss_poisson(
+        #[case] start: usize,
+        #[case] stop: usize,
+    ) {
+        // Without overdispersion, the loss equals the Poisson loss.
+        let X = ndarray::array![[1., 1.], [1., 1.], [1., 1.], [1., 2.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_negative_binomial_rate = ChangeInNegativeBinomialRate::new(&X_view, &control);
+        assert!(change_in_negative_binomial_rate
+            .size()
+            .iter()
+            .all(|x| x.is_infinite()));
+        let change_in_poisson_rate = ChangeInPoissonRate::new(&X_view, &control);
+
+        assert_approx_eq!(
+            change_in_negative_binomial_rate.loss(start, stop),
+            change_in_poisson_rate.loss(start, stop)
+        );
+    }
+
+    #[test]
+    fn test_change_in_negative_binomial_rate_loss() {
+        let X = ndarray::array![[0., 1.], [2., 2.], [0., 2.], [2., 2.], [0., 2.], [2., 1.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_negative_binomial_rate = ChangeInNegativeBinomialRate::new(&X_view, &control);
+        // Feature 0 has size 1, sum 2 and mean 2 / 3 on [0, 3). Feature 1 has no
+        // overdispersion and sum 5.
+        let expected = -2.
+            * (2. * (2_f64 / 3.).ln() - 5. * (2_f64 / 3.).ln_1p() + 5. * (5_f64 / 3.).ln() - 5.);
+        assert_approx_eq!(change_in_negative_binomial_rate.loss(0, 3), expected);
+    }
+
+    #[test]
+    fn test_change_in_negative_binomial_rate_overdispersion() {
+        // Overdispersed counts with mean 5 and size 2 (variance 17.5), with a change in
+        // the mean to 15 at 150.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut X = Array2::<f64>::zeros((200, 1));
+        X.slice_mut(s![..150, 0])
+            .assign(&negative_binomial(150, 5., 2., &mut rng));
+        X.slice_mut(s![150.., 0])
+            .assign(&negative_binomial(50, 15., 2., &mut rng));
+        let X_view = X.view();
+        let control = Control::default();
+
+        let optimizer = GridSearch {
+            gain: ChangeInNegativeBinomialRate::new(&X_view, &control),
+        };
+        let optimizer_result = optimizer.find_best_split(0, 200).unwrap();
+        assert!((optimizer_result.best_split as i64 - 150).abs() <= 5);
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+
+        let optimizer_result = optimizer.find_best_split(0, 150).unwrap();
+        assert!(!optimizer.model_selection(&optimizer_result).is_significant);
+
+        // The Poisson gain ignores the overdispersion and splits the segment without
+        // change.
+        let optimizer = GridSearch {
+            gain: ChangeInPoissonRate::new(&X_view, &control),
+        };
+        let optimizer_result = optimizer.find_best_split(0, 150).unwrap();
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::control::Control;
+use crate::gain::Gain;
+use crate::optimizer::OptimizerResult;
+use crate::utils::assert_counts;
+use ndarray::{s, Array2, ArrayView2, Axis};
+use std::cell::{Ref, RefCell};
+
+/// Gain for changes in the rates of independent Poisson distributed counts.
+///
+/// The loss of a segment is twice the negative maximal Poisson log-likelihood (up to
+/// terms that cancel in the gain), `-2 sum_j (S_j log(S_j / m) - S_j)`, where `m` is
+/// the segment length and `S_j` the sum of counts of feature `j` in the segment. This
+/// only requires cumulative sums of `X`.
+pub struct ChangeInPoissonRate<'a, 'b> {
+    X: &'a ArrayView2<'b, f64>,
+    X_cumsum: RefCell<Option<Array2<f64>>>,
+    control: &'a Control,
+}
+
+impl<'a, 'b> ChangeInPoissonRate<'a, 'b> {
+    /// Panics if `X` contains entries that are not non-negative integers.
+    pub fn new(X: &'a ArrayView2<'b, f64>, control: &'a Control) -> ChangeInPoissonRate<'a, 'b> {
+        assert_counts(X);
+
+        ChangeInPoissonRate {
+            X,
+            X_cumsum: RefCell::new(Option::None),
+            control,
+        }
+    }
+
+    fn calculate_cumsum(&self) -> Array2<f64> {
+        let mut X_cumsum = Array2::zeros((self.X.nrows() + 1, self.X.ncols()));
+        X_cumsum.slice_mut(s![1.., ..]).assign(self.X);
+
+        X_cumsum.accumulate_axis_inplace(Axis(0), |&prev, curr| *curr += prev);
+        X_cumsum
+    }
+
+    fn get_cumsum(&self) -> Ref<'_, Array2<f64>> {
+        if self.X_cumsum.borrow().is_none() {
+            self.X_cumsum.replace(Some(self.calculate_cumsum()));
+        }
+
+        Ref::map(self.X_cumsum.borrow(), |borrow| borrow.as_ref().unwrap())
+    }
+}
+
+impl<'a, 'b> Gain for ChangeInPoissonRate<'a, 'b> {
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn loss(&self, start: usize, stop: usize) -> f64 {
+        if start == stop {
+            return 0.;
+        }
+
+        let X_cumsum = self.get_cumsum();
+        let m = (stop - start) as f64;
+
+        let mut result = 0.;
+        for idx in 0..self.X.ncols() {
+            let sum = X_cumsum[[stop, idx]] - X_cumsum[[start, idx]];
+            // sum * log(sum / m) -> 0 for sum -> 0.
+            if sum > 0. {
+                result -= 2. * (sum * (sum / m).ln() - sum);
+            }
+        }
+        result
+    }
+
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        // (d + 1) is the number of additional parameters through an additional
+        // changepoint.
+        Some(self.control.penalized_minimal_gain_to_split(
+            self.n(),
+            self.X.ncols() + 1,
+            optimizer_result,
+        ))
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::optimizer::{GridSearch, Optimizer};
+    use assert_approx_eq::*;
+    use ndarray::Array;
+    use ndarray_rand::rand_distr::Poisson;
+    use ndarray_rand::RandomExt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    #[test]
+    fn test_X_cumsum() {
+        let X = ndarray::array![[1., 0.], [2., 0.], [0., 1.], [4., 3.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_poisson_rate = ChangeInPoissonRate::new(&X_view, &control);
+        let expected = ndarray::array![[0., 0.], [1., 0.], [3., 0.], [3., 1.], [7., 4.]];
+        assert_eq!(change_in_poisson_rate.calculate_cumsum(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-negative integer counts")]
+    fn test_change_in_poisson_rate_invalid_input() {
+        let X = ndarray::array![[1., 0.], [2., 0.5]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        ChangeInPoissonRate::new(&X_view, &control);
+    }
+
+    #[rstest]
+    #[case(0, 4, -2. * (7. * (7_f64 / 4.).ln() - 7. + 4. * (1_f64).ln() - 4.))]
+    #[case(0, 2, -2. * (3. * (3_f64 / 2.).ln() - 3.))]
+    #[case(1, 3, -2. * (2. * (1_f64).ln() - 2. + (0.5_f64).ln() - 1.))]
+    #[case(2, 2, 0.)]
+    fn test_change_in_poisson_rate_loss(
+        #[case] start: usize,
+        #[case] stop: usize,
+        #[case] expected: f64,
+    ) {
+        let X = ndarray::array![[1., 0.], [2., 0.], [0., 1.], [4., 3.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_poisson_rate = ChangeInPoissonRate::new(&X_view, &control);
+        assert_approx_eq!(change_in_poisson_rate.loss(start, stop), expected);
+    }
+
+    #[test]
+    fn test_change_in_poisson_rate_find_best_split() {
+        // Rates 2 and 5 for the first feature, 1 throughout for the second feature.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut X = Array::random_using((200, 2), Poisson::new(1.).unwrap(), &mut rng);
+        X.slice_mut(s![..120, 0]).assign(&Array::random_using(
+            120,
+            Poisson::new(2.).unwrap(),
+            &mut rng,
+        ));
+        X.slice_mut(s![120.., 0]).assign(&Array::random_using(
+            80,
+            Poisson::new(5.).unwrap(),
+            &mut rng,
+        ));
+        let X_view = X.view();
+        let control = Control::default();
+
+        let optimizer = GridSearch {
+            gain: ChangeInPoissonRate::new(&X_view, &control),
+        };
+        let optimizer_result = optimizer.find_best_split(0, 200).unwrap();
+        assert_eq!(optimizer_result.best_split, 120);
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+
+        let optimizer_result = optimizer.find_best_split(0, 120).unwrap();
+        assert!(!optimizer.model_selection(&optimizer_result).is_significant);
+    }
+}
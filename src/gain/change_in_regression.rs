@@ -0,0 +1,270 @@
+use crate::control::Control;
+use crate::gain::Gain;
+use crate::optimizer::OptimizerResult;
+use crate::utils::residual_sum_of_squares;
+use ndarray::{s, Array1, Array2, Array3, ArrayView2, Axis};
+use std::cell::{Ref, RefCell};
+
+/// Gain for changes in the coefficients of a linear regression.
+///
+/// The column `response` of `X` is regressed on the remaining columns and an intercept.
+/// The loss of a segment is `m * log(RSS / m)`, where `m` is the segment length and
+/// `RSS` the residual sum of squares of the least squares fit, i.e. twice the negative
+/// profile log-likelihood of a Gaussian linear model with unknown noise variance.
+///
+/// Cumulative sums of `Z^T Z`, `Z^T y` and `y^T y`, where `Z` are the covariates
+/// including intercept, are computed once. The loss of any segment then only requires
+/// the solution of a `p x p` system of equations.
+///
+/// Unless `control.minimal_gain_to_split` is set, a split is kept if the gain exceeds
+/// `control.penalty` for `p + 2` additional parameters: the `p` coefficients including
+/// the intercept, the noise variance, which is estimated within each segment, and the
+/// location of the change point.
+pub struct ChangeInRegression<'a, 'b> {
+    X: &'a ArrayView2<'b, f64>,
+    response: usize,
+    cumsums: RefCell<Option<RegressionCumsums>>,
+    control: &'a Control,
+}
+
+struct RegressionCumsums {
+    gram: Array3<f64>,
+    Zy: Array2<f64>,
+    yy: Array1<f64>,
+}
+
+impl<'a, 'b> ChangeInRegression<'a, 'b> {
+    pub fn new(
+        X: &'a ArrayView2<'b, f64>,
+        response: usize,
+        control: &'a Control,
+    ) -> ChangeInRegression<'a, 'b> {
+        if response >= X.ncols() {
+            panic!(
+                "response needs to be a column index of X. Got {response} for X with {} columns.",
+                X.ncols()
+            );
+        }
+
+        ChangeInRegression {
+            X,
+            response,
+            cumsums: RefCell::new(Option::None),
+            control,
+        }
+    }
+
+    /// Number of regression coefficients, including the intercept.
+    pub fn n_coefficients(&self) -> usize {
+        self.X.ncols()
+    }
+
+    fn calculate_cumsums(&self) -> RegressionCumsums {
+        let n = self.X.nrows();
+        let p = self.n_coefficients();
+
+        let mut gram = Array3::<f64>::zeros((n + 1, p, p));
+        let mut Zy = Array2::<f64>::zeros((n + 1, p));
+        let mut yy = Array1::<f64>::zeros(n + 1);
+        let mut z = Array1::<f64>::ones(p);
+
+        for (i, row) in self.X.axis_iter(Axis(0)).enumerate() {
+            // z = (1, x_1, ..., x_{response - 1}, x_{response + 1}, ..., x_d)
+            let mut jdx = 1;
+            for (idx, value) in row.iter().enumerate() {
+                if idx != self.response {
+                    z[jdx] = *value;
+                    jdx += 1;
+                }
+            }
+            let y = row[self.response];
+
+            for j in 0..p {
+                for k in 0..p {
+                    gram[[i + 1, j, k]] = gram[[i, j, k]] + z[j] * z[k];
+                }
+                Zy[[i + 1, j]] = Zy[[i, j]] + z[j] * y;
+            }
+            yy[i + 1] = yy[i] + y * y;
+        }
+
+        RegressionCumsums { gram, Zy, yy }
+    }
+
+    fn get_cumsums(&self) -> Ref<'_, RegressionCumsums> {
+        if self.cumsums.borrow().is_none() {
+            self.cumsums.replace(Some(self.calculate_cumsums()));
+        }
+
+        Ref::map(self.cumsums.borrow(), |borrow| borrow.as_ref().unwrap())
+    }
+}
+
+impl<'a, 'b> Gain for ChangeInRegression<'a, 'b> {
+    fn n(&self) -> usize {
+        self.X.nrows()
+    }
+
+    fn loss(&self, start: usize, stop: usize) -> f64 {
+        if start == stop {
+            return 0.;
+        }
+
+        let cumsums = self.get_cumsums();
+        let gram = &cumsums.gram.slice(s![stop, .., ..]) - &cumsums.gram.slice(s![start, .., ..]);
+        let Zy = &cumsums.Zy.slice(s![stop, ..]) - &cumsums.Zy.slice(s![start, ..]);
+        let yy = cumsums.yy[stop] - cumsums.yy[start];
+
+        let m = (stop - start) as f64;
+        let rss = residual_sum_of_squares(&gram.view(), &Zy.view(), yy);
+        // Avoid log(0) for perfect fits.
+        m * (f64::max(rss, f64::MIN_POSITIVE) / m).ln()
+    }
+
+    /// The gain is zero if any of the two segments has not more observations than
+    /// regression coefficients, as the fit would be perfect.
+    fn gain(&self, start: usize, stop: usize, split: usize) -> f64 {
+        let p = self.n_coefficients();
+        if (split - start <= p) | (stop - split <= p) {
+            return 0.;
+        }
+
+        self.loss(start, stop) - self.loss(start, split) - self.loss(split, stop)
+    }
+
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        // An additional change point introduces p coefficients, one noise variance
+        // and its location as additional parameters.
+        Some(self.control.penalized_minimal_gain_to_split(
+            self.n(),
+            self.n_coefficients() + 2,
+            optimizer_result,
+        ))
+    }
+
+    fn control(&self) -> &Control {
+        self.control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::control::Penalty;
+    use crate::optimizer::{GridSearch, Optimizer};
+    use crate::testing;
+    use assert_approx_eq::*;
+    use rstest::*;
+
+    #[test]
+    fn test_cumsums() {
+        let X = ndarray::array![[1., 2., 0.], [3., 4., 1.]];
+        let X_view = X.view();
+        let control = Control::default();
+
+        let change_in_regression = ChangeInRegression::new(&X_view, 1, &control);
+        let cumsums = change_in_regression.calculate_cumsums();
+
+        // z_0 = (1, 1, 0), z_1 = (1, 3, 1), y = (2, 4)
+        let expected_gram = ndarray::array![
+            [[0., 0., 0.], [0., 0., 0.], [0., 0., 0.]],
+            [[1., 1., 0.], [1., 1., 0.], [0., 0., 0.]],
+            [[2., 4., 1.], [4., 10., 3.], [1., 3., 1.]]
+        ];
+        assert_eq!(cumsums.gram, expected_gram);
+        assert_eq!(
+            cumsums.Zy,
+            ndarray::array![[0., 0., 0.], [2., 2., 0.], [6., 14., 4.]]
+        );
+        assert_eq!(cumsums.yy, ndarray::array![0., 4., 20.]);
+    }
+
+    #[rstest]
+    #[case(0, 6)]
+    #[case(0, 3)]
+    #[case(2, 6)]
+    fn test_loss(#[case] start: usize, #[case] stop: usize) {
+        // Compare to the loss from the residuals of an explicit fit. y = 1 + 2 x + e.
+        let X = ndarray::array![
+            [0., 1.2],
+            [1., 2.9],
+            [2., 5.1],
+            [3., 7.2],
+            [4., 8.8],
+            [5., 11.]
+        ];
+        let X_view = X.view();
+        let control = Control::default();
+        let change_in_regression = ChangeInRegression::new(&X_view, 1, &control);
+
+        let x = X.slice(s![start..stop, 0]);
+        let y = X.slice(s![start..stop, 1]);
+        let x_mean = x.mean().unwrap();
+        let y_mean = y.mean().unwrap();
+        let slope = ((&x - x_mean) * (&y - y_mean)).sum() / (&x - x_mean).mapv(|a| a * a).sum();
+        let intercept = y_mean - slope * x_mean;
+        let rss = (&y - &x.mapv(|a| intercept + slope * a))
+            .mapv(|a| a * a)
+            .sum();
+        let m = (stop - start) as f64;
+
+        assert_approx_eq!(change_in_regression.loss(start, stop), m * (rss / m).ln());
+    }
+
+    #[rstest]
+    #[case(Penalty::BIC, 6_f64.ln() * 5.)]
+    #[case(Penalty::AIC, 2. * 5.)]
+    fn test_change_in_regression_minimal_gain_to_split(
+        #[case] penalty: Penalty,
+        #[case] expected: f64,
+    ) {
+        // n = 6 observations and p = 3 coefficients, two covariates and an intercept.
+        let X = ndarray::Array2::<f64>::zeros((6, 3));
+        let X_view = X.view();
+        let control = Control::default().with_penalty(penalty);
+
+        let change_in_regression = ChangeInRegression::new(&X_view, 2, &control);
+        let optimizer_result = OptimizerResult {
+            start: 0,
+            stop: 6,
+            best_split: 3,
+            max_gain: 0.,
+            gain_results: vec![],
+            n_initial_guesses: 0,
+        };
+        assert_approx_eq!(
+            change_in_regression
+                .minimal_gain_to_split(&optimizer_result)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_change_in_regression_find_best_split() {
+        // Change in the slope at 50. Marginal distributions of x and y are unchanged.
+        let X = testing::array();
+        let mut data = Array2::<f64>::zeros((100, 2));
+        for i in 0..100 {
+            let x = X[[i, 4]] - 0.5;
+            let slope = if i < 50 { 1. } else { -1. };
+            data[[i, 0]] = x;
+            data[[i, 1]] = slope * x + 0.1 * (X[[i, 3]] - 0.5);
+        }
+        let data_view = data.view();
+        // The noise variance is estimated within each segment. Segments need to be
+        // sufficiently long for this to be stable.
+        let control = Control::default().with_minimal_relative_segment_length(0.1);
+
+        let gain = ChangeInRegression::new(&data_view, 1, &control);
+        let optimizer = GridSearch { gain };
+        let optimizer_result = optimizer.find_best_split(0, 100).unwrap();
+
+        assert_eq!(optimizer_result.best_split, 50);
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+
+        let optimizer_result = optimizer.find_best_split(0, 50).unwrap();
+        assert!(!optimizer.model_selection(&optimizer_result).is_significant);
+    }
+}
@@ -1,10 +1,11 @@
-use crate::control::Control;
+use crate::control::{Control, PermutationType};
 use crate::gain::{ApproxGain, ApproxGainResult, Gain, GainResult};
 use crate::optimizer::OptimizerResult;
+use crate::utils::{automatic_block_length, demean_segments};
 use crate::Classifier;
 use crate::ModelSelectionResult;
 use ndarray::{s, Array1, Array2, Axis};
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 pub struct ClassifierGain<T: Classifier> {
     pub classifier: T,
@@ -27,91 +28,9 @@ where
             .single_likelihood(&predictions, start, stop, split)
     }
 
-    /// Perform a permutation test.
-    ///
-    /// We test whether the maximum observed gain from the first step in the
-    /// `TwoStepSearch` optimizer is significant. Using the maximum gain from the first
-    /// step instead allows us to do a proper permutation test with control of type I
-    /// error without fitting additional classifiers.
-    ///
-    /// In the first step of the `TwoStepSearch` optimizer, three gain curves and
-    /// corresponding maximal gains are computed. The maximum gain of the first step
-    /// of `TwoStepSearch` is the maximum of these three gains, which are available as
-    /// the first three elements of `optimizer_result.gain_results`.
-    ///
-    /// For each permutation, we shuffle the predictions (and thus the likelihoods) of
-    /// each of the three initial classifier fits (using the same permutation), and
-    /// compute the maximum of the three resulting maximal gains. We count the number
-    /// of permutations where the resulting maximal gain was larger than the observed
-    /// maximal gain to compute a p-value.
+    /// Perform a permutation test. See `permutation_test`.
     fn model_selection(&self, optimizer_result: &OptimizerResult) -> ModelSelectionResult {
-        let mut rng = StdRng::seed_from_u64(self.control().seed);
-
-        let mut max_gain = -f64::INFINITY;
-        let mut deltas: Vec<Array1<f64>> = Vec::with_capacity(3);
-        let mut likelihood_0: Vec<f64> = Vec::with_capacity(3);
-
-        for gain_result in optimizer_result.gain_results.split_last().unwrap().1.iter() {
-            let result = match gain_result {
-                GainResult::ApproxGainResult(result) => result,
-                _ => panic!("Not an ApproxGainResult"),
-            };
-
-            deltas
-                .push(&result.likelihoods.slice(s![0, ..]) - &result.likelihoods.slice(s![1, ..]));
-            likelihood_0.push(result.likelihoods.slice(s![1, ..]).sum());
-
-            if result.max_gain.unwrap() > max_gain {
-                max_gain = result.max_gain.unwrap();
-            }
-        }
-
-        let mut p_value: u32 = 1;
-        let segment_length = optimizer_result.stop - optimizer_result.start;
-
-        // ceil(delta * n)
-        let minimal_segment_length =
-            (self.control().minimal_relative_segment_length * (self.n() as f64)).ceil() as usize;
-
-        for _ in 0..self.control().model_selection_n_permutations {
-            let mut values = likelihood_0.clone();
-            let permutation = rand::seq::index::sample(&mut rng, segment_length, segment_length);
-
-            for idx in permutation.iter().take(minimal_segment_length - 1) {
-                for jdx in 0..deltas.len() {
-                    values[jdx] += deltas[jdx][idx];
-                }
-            }
-
-            // Test if for any jdx=1,2,3 the gain (likelihood_0[jdx] + cumsum(deltas[jdx]))
-            // is greater than max_gain. This is the statistic we are comparing against.
-            'outer: for idx in permutation
-                .iter()
-                .skip(minimal_segment_length - 1)
-                .take(segment_length - 2 * minimal_segment_length + 1)
-            {
-                for jdx in 0..deltas.len() {
-                    values[jdx] += deltas[jdx][idx];
-                    if values[jdx] >= max_gain {
-                        p_value += 1;
-                        // break both loops. We only need to check if the maximum of the
-                        // maximal gain after permutation is ever greater than the
-                        // original max_gain (without permutation).
-                        break 'outer;
-                    }
-                }
-            }
-        }
-
-        // Up to here p_value is # of permutations for which the max_gain is higher than
-        // the non-permuted max_gain. From this create a true p_value.
-        let p_value = p_value as f64 / (self.control().model_selection_n_permutations + 1) as f64;
-        let is_significant = p_value <= self.control().model_selection_alpha;
-
-        ModelSelectionResult {
-            is_significant,
-            p_value: Some(p_value),
-        }
+        permutation_test(optimizer_result, self.control(), self.n())
     }
 
     fn control(&self) -> &Control {
@@ -152,6 +71,195 @@ where
             predictions,
         }
     }
+
+    /// Restrict the out-of-bag predictions of the fit on the enclosing segment `parent`
+    /// to `[start, stop)`.
+    ///
+    /// Within `[start, stop)`, the labels of the parent fit agree with those of a fit
+    /// with a split at `parent.guess`. The predicted odds are adjusted for the
+    /// different class proportions of `[start, stop)` before computing likelihoods.
+    fn gain_approx_from_parent(
+        &self,
+        start: usize,
+        stop: usize,
+        parent: &ApproxGainResult,
+    ) -> Option<ApproxGainResult> {
+        let guess = parent.guess;
+        if (start < parent.start) | (stop > parent.stop) | (guess <= start) | (guess >= stop) {
+            return None;
+        }
+
+        let parent_prior = (parent.stop - guess) as f64 / (parent.stop - parent.start) as f64;
+        let prior = (stop - guess) as f64 / (stop - start) as f64;
+        let odds_ratio = (prior / (1. - prior)) / (parent_prior / (1. - parent_prior));
+
+        let predictions = parent
+            .predictions
+            .slice(s![(start - parent.start)..(stop - parent.start)])
+            .mapv(|x| x * odds_ratio / (x * odds_ratio + 1. - x));
+        let likelihoods = self
+            .classifier
+            .full_likelihood(&predictions, start, stop, guess);
+
+        let gain = gain_from_likelihoods(&likelihoods);
+
+        Some(ApproxGainResult {
+            start,
+            stop,
+            guess,
+            gain,
+            best_split: None,
+            max_gain: None,
+            likelihoods,
+            predictions,
+        })
+    }
+}
+
+/// Perform a permutation test.
+///
+/// We test whether the maximum observed gain from the first step in the
+/// `TwoStepSearch` optimizer is significant. Using the maximum gain from the first
+/// step instead allows us to do a proper permutation test with control of type I
+/// error without fitting additional classifiers.
+///
+/// In the first step of the `TwoStepSearch` optimizer, gain curves and corresponding
+/// maximal gains are computed at (by default three) initial guesses. The maximum gain
+/// of the first step of `TwoStepSearch` is the maximum of these gains, which are
+/// available as the first `optimizer_result.n_initial_guesses` elements of
+/// `optimizer_result.gain_results`. Gain curves from bracketing and refinement depend
+/// on the data through the chosen guesses and are not used.
+///
+/// For each permutation, we shuffle the predictions (and thus the likelihoods) of
+/// each of the initial classifier fits (using the same permutation), and compute the
+/// maximum of the resulting maximal gains. We count the number
+/// of permutations where the resulting maximal gain was larger than the observed
+/// maximal gain to compute a p-value.
+///
+/// Permuting individual observations assumes exchangeability under the null. For
+/// serially dependent data, use `control.model_selection_permutation_type` to permute
+/// blocks of observations or to circularly shift observations instead.
+pub fn permutation_test(
+    optimizer_result: &OptimizerResult,
+    control: &Control,
+    n: usize,
+) -> ModelSelectionResult {
+    let mut rng = StdRng::seed_from_u64(control.seed);
+
+    let mut max_gain = -f64::INFINITY;
+    let mut deltas: Vec<Array1<f64>> = Vec::with_capacity(optimizer_result.n_initial_guesses);
+    let mut likelihood_0: Vec<f64> = Vec::with_capacity(optimizer_result.n_initial_guesses);
+    let mut first_guess = optimizer_result.start;
+
+    for gain_result in optimizer_result.gain_results[..optimizer_result.n_initial_guesses].iter() {
+        let result = match gain_result {
+            GainResult::ApproxGainResult(result) => result,
+            _ => panic!("Not an ApproxGainResult"),
+        };
+
+        if deltas.is_empty() {
+            first_guess = result.guess;
+        }
+        deltas.push(&result.likelihoods.slice(s![0, ..]) - &result.likelihoods.slice(s![1, ..]));
+        likelihood_0.push(result.likelihoods.slice(s![1, ..]).sum());
+
+        if result.max_gain.unwrap() > max_gain {
+            max_gain = result.max_gain.unwrap();
+        }
+    }
+
+    let mut p_value: u32 = 1;
+    let segment_length = optimizer_result.stop - optimizer_result.start;
+
+    // ceil(delta * n)
+    let minimal_segment_length =
+        (control.minimal_relative_segment_length * (n as f64)).ceil() as usize;
+
+    let block_length = match control.model_selection_permutation_type {
+        PermutationType::Blocks => control.model_selection_block_length.unwrap_or_else(|| {
+            // Estimate the serial dependence under the null of no change. Remove the
+            // mean shifts of the log-likelihood ratios at the first initial guess and
+            // at the best split.
+            let breaks = [
+                first_guess - optimizer_result.start,
+                optimizer_result.best_split - optimizer_result.start,
+            ];
+            automatic_block_length(&demean_segments(&deltas[0].view(), &breaks).view())
+        }),
+        _ => 1,
+    };
+
+    for _ in 0..control.model_selection_n_permutations {
+        let mut values = likelihood_0.clone();
+        let permutation = sample_permutation(
+            &mut rng,
+            segment_length,
+            &control.model_selection_permutation_type,
+            block_length,
+        );
+
+        for idx in permutation.iter().take(minimal_segment_length - 1) {
+            for jdx in 0..deltas.len() {
+                values[jdx] += deltas[jdx][*idx];
+            }
+        }
+
+        // Test if for any jdx=1,2,3 the gain (likelihood_0[jdx] + cumsum(deltas[jdx]))
+        // is greater than max_gain. This is the statistic we are comparing against.
+        'outer: for idx in permutation
+            .iter()
+            .skip(minimal_segment_length - 1)
+            .take(segment_length - 2 * minimal_segment_length + 1)
+        {
+            for jdx in 0..deltas.len() {
+                values[jdx] += deltas[jdx][*idx];
+                if values[jdx] >= max_gain {
+                    p_value += 1;
+                    // break both loops. We only need to check if the maximum of the
+                    // maximal gain after permutation is ever greater than the
+                    // original max_gain (without permutation).
+                    break 'outer;
+                }
+            }
+        }
+    }
+
+    // Up to here p_value is # of permutations for which the max_gain is higher than
+    // the non-permuted max_gain. From this create a true p_value.
+    let p_value = p_value as f64 / (control.model_selection_n_permutations + 1) as f64;
+    let is_significant = p_value <= control.model_selection_alpha;
+
+    ModelSelectionResult {
+        is_significant,
+        p_value: Some(p_value),
+    }
+}
+
+/// Draw a random permutation of `0..n` according to `permutation_type`.
+///
+/// For `PermutationType::Blocks`, `0..n` is split into consecutive blocks of length
+/// `block_length` (the last block might be shorter), which are then shuffled.
+pub fn sample_permutation(
+    rng: &mut StdRng,
+    n: usize,
+    permutation_type: &PermutationType,
+    block_length: usize,
+) -> Vec<usize> {
+    match permutation_type {
+        PermutationType::Observations => rand::seq::index::sample(rng, n, n).into_vec(),
+        PermutationType::Blocks => {
+            let block_length = block_length.clamp(1, n.max(1));
+            let n_blocks = n.div_ceil(block_length);
+            rand::seq::index::sample(rng, n_blocks, n_blocks)
+                .iter()
+                .flat_map(|block| (block * block_length)..usize::min((block + 1) * block_length, n))
+                .collect()
+        }
+        PermutationType::CircularShift => {
+            let shift = rng.gen_range(0..n.max(1));
+            (0..n).map(|idx| (idx + shift) % n).collect()
+        }
+    }
 }
 
 pub fn gain_from_likelihoods(likelihoods: &Array2<f64>) -> Array1<f64> {
@@ -168,8 +276,47 @@ pub fn gain_from_likelihoods(likelihoods: &Array2<f64>) -> Array1<f64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::classifier::RandomForest;
     use crate::optimizer::{Optimizer, TwoStepSearch};
-    use crate::testing::RandomClassifier;
+    use crate::testing::{RandomClassifier, TrivialClassifier};
+    use ndarray_rand::rand_distr::Normal;
+    use ndarray_rand::RandomExt;
+    use rstest::*;
+
+    #[rstest]
+    #[case(25, 75, true)]
+    #[case(0, 60, false)]
+    fn test_gain_approx_from_parent(
+        #[case] start: usize,
+        #[case] stop: usize,
+        #[case] same_prior: bool,
+    ) {
+        let control = Control::default();
+        let classifier = TrivialClassifier {
+            n: 100,
+            control: &control,
+        };
+        let gain = ClassifierGain { classifier };
+
+        let parent = gain.gain_approx(0, 100, 50, &[]);
+        let result = gain.gain_approx_from_parent(start, stop, &parent).unwrap();
+        let expected = parent.predictions.slice(s![start..stop]);
+
+        assert_eq!(result.guess, 50);
+        assert_eq!(result.gain.len(), stop - start);
+        if same_prior {
+            assert_eq!(result.predictions, expected);
+        } else {
+            // The child has a smaller proportion of observations after the guess.
+            assert!(result
+                .predictions
+                .iter()
+                .zip(expected.iter())
+                .all(|(x, y)| x <= y));
+        }
+
+        assert!(gain.gain_approx_from_parent(0, 50, &parent).is_none());
+    }
 
     #[test]
     fn test_gain_from_likelihoods() {
@@ -201,7 +348,7 @@ mod tests {
                 seed,
             };
             let gain = ClassifierGain { classifier };
-            let optimizer = TwoStepSearch { gain };
+            let optimizer = TwoStepSearch::new(gain);
 
             let optimizer_result = optimizer.find_best_split(0, n).unwrap();
 
@@ -212,4 +359,65 @@ mod tests {
         assert!(p_value >= 0.03);
         assert!(p_value <= 0.07);
     }
+    #[rstest]
+    #[case(PermutationType::Observations, 1)]
+    #[case(PermutationType::Blocks, 1)]
+    #[case(PermutationType::Blocks, 3)]
+    #[case(PermutationType::Blocks, 20)]
+    #[case(PermutationType::CircularShift, 1)]
+    fn test_sample_permutation(
+        #[case] permutation_type: PermutationType,
+        #[case] block_length: usize,
+    ) {
+        let mut rng = StdRng::seed_from_u64(0);
+        let permutation = sample_permutation(&mut rng, 10, &permutation_type, block_length);
+
+        let mut sorted = permutation.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..10).collect::<Vec<usize>>());
+
+        // Consecutive observations within blocks stay consecutive.
+        let n_breaks = permutation
+            .windows(2)
+            .filter(|window| window[1] != (window[0] + 1) % 10)
+            .count();
+        match permutation_type {
+            PermutationType::Blocks => assert!(n_breaks <= 10_usize.div_ceil(block_length)),
+            PermutationType::CircularShift => assert_eq!(n_breaks, 0),
+            PermutationType::Observations => (),
+        }
+    }
+
+    #[rstest]
+    #[case(PermutationType::Observations, None, true)]
+    #[case(PermutationType::Blocks, None, false)]
+    #[case(PermutationType::Blocks, Some(50), false)]
+    #[case(PermutationType::CircularShift, None, false)]
+    fn test_model_selection_serial_dependence(
+        #[case] permutation_type: PermutationType,
+        #[case] block_length: Option<usize>,
+        #[case] expected: bool,
+    ) {
+        // AR(1) with coefficient 0.9 without change. Observations close in time are
+        // similar, such that classifiers can separate segments and the naive
+        // permutation test results in a false positive.
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = Array1::random_using(500, Normal::new(0., 1.).unwrap(), &mut rng);
+        let mut X = Array2::<f64>::zeros((500, 1));
+        for t in 1..500 {
+            X[[t, 0]] = 0.9 * X[[t - 1, 0]] + noise[t];
+        }
+        let X_view = X.view();
+        let control = Control::default()
+            .with_model_selection_permutation_type(permutation_type)
+            .with_model_selection_block_length(block_length);
+
+        let classifier = RandomForest::new(&X_view, &control);
+        let optimizer = TwoStepSearch::new(ClassifierGain { classifier });
+        let optimizer_result = optimizer.find_best_split(0, 500).unwrap();
+        assert_eq!(
+            optimizer.model_selection(&optimizer_result).is_significant,
+            expected
+        );
+    }
 }
@@ -0,0 +1,151 @@
+use crate::control::Control;
+use crate::gain::{gain_from_likelihoods, permutation_test, ApproxGain, ApproxGainResult, Gain};
+use crate::optimizer::OptimizerResult;
+use crate::Classifier;
+use crate::ModelSelectionResult;
+
+/// Classifier-based gain for changes in the conditional distribution of `y` given `X`.
+///
+/// By Bayes' theorem, the log-likelihood ratio of the conditional densities
+/// `p(y | x)` before and after a split is the difference of the classifier
+/// log-likelihood ratios of a classifier predicting segment membership from `(X, y)`
+/// (`joint`) and one predicting segment membership from `X` alone (`marginal`). Changes
+/// in the distribution of `X` only thus result in similar classifier log-likelihood
+/// ratios, which cancel.
+pub struct ConditionalClassifierGain<T: Classifier, S: Classifier> {
+    pub joint: T,
+    pub marginal: S,
+}
+
+impl<T, S> Gain for ConditionalClassifierGain<T, S>
+where
+    T: Classifier,
+    S: Classifier,
+{
+    /// Total number of observations.
+    fn n(&self) -> usize {
+        self.joint.n()
+    }
+
+    /// Return the difference of the classifier log-likelihood ratios of the `joint` and
+    /// the `marginal` classifiers when splitting segment `[start, stop)` at `split`.
+    fn gain(&self, start: usize, stop: usize, split: usize) -> f64 {
+        let joint_predictions = self.joint.predict(start, stop, split);
+        let marginal_predictions = self.marginal.predict(start, stop, split);
+
+        self.joint
+            .single_likelihood(&joint_predictions, start, stop, split)
+            - self
+                .marginal
+                .single_likelihood(&marginal_predictions, start, stop, split)
+    }
+
+    /// Perform a permutation test on the conditional log-likelihoods. See
+    /// `permutation_test`.
+    fn model_selection(&self, optimizer_result: &OptimizerResult) -> ModelSelectionResult {
+        permutation_test(optimizer_result, self.control(), self.n())
+    }
+
+    fn control(&self) -> &Control {
+        self.joint.control()
+    }
+}
+
+impl<T, S> ApproxGain for ConditionalClassifierGain<T, S>
+where
+    T: Classifier,
+    S: Classifier,
+{
+    /// Return an approximation of the conditional log-likelihood ratio when splitting
+    /// segment `[start, stop)` for each split in `split_candidates`.
+    ///
+    /// A single fit of each classifier is generated with a split at `guess`. The
+    /// `predictions` of the result are those of the `joint` classifier.
+    fn gain_approx(
+        &self,
+        start: usize,
+        stop: usize,
+        guess: usize,
+        _: &[usize],
+    ) -> ApproxGainResult {
+        let predictions = self.joint.predict(start, stop, guess);
+        let marginal_predictions = self.marginal.predict(start, stop, guess);
+
+        let likelihoods = self.joint.full_likelihood(&predictions, start, stop, guess)
+            - self
+                .marginal
+                .full_likelihood(&marginal_predictions, start, stop, guess);
+        let gain = gain_from_likelihoods(&likelihoods);
+
+        ApproxGainResult {
+            start,
+            stop,
+            guess,
+            gain,
+            best_split: None,
+            max_gain: None,
+            likelihoods,
+            predictions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::RandomForest;
+    use crate::gain::ClassifierGain;
+    use crate::optimizer::{Optimizer, TwoStepSearch};
+    use crate::testing::TrivialClassifier;
+    use assert_approx_eq::*;
+    use ndarray::{s, Array2};
+    use ndarray_rand::rand_distr::Normal;
+    use ndarray_rand::RandomExt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_conditional_gain_identical_classifiers() {
+        let control = Control::default();
+        let gain = ConditionalClassifierGain {
+            joint: TrivialClassifier {
+                n: 10,
+                control: &control,
+            },
+            marginal: TrivialClassifier {
+                n: 10,
+                control: &control,
+            },
+        };
+
+        assert_approx_eq!(gain.gain(0, 10, 5), 0.);
+        let approx_gain_result = gain.gain_approx(0, 10, 5, &[]);
+        assert!(approx_gain_result.gain.iter().all(|x| x.abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_conditional_gain_covariate_shift() {
+        // The distribution of x has a mean shift at 100. The distribution of y | x is
+        // unchanged.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = Array2::random_using((200, 2), Normal::new(0., 1.).unwrap(), &mut rng);
+        data.slice_mut(s![100.., 0]).mapv_inplace(|x| x + 3.);
+        let x = data.column(0).to_owned();
+        data.column_mut(1).zip_mut_with(&x, |y, x| *y += x);
+
+        let data_view = data.view();
+        let X_view = data.slice(s![.., 0..1]);
+        let control = Control::default();
+
+        let joint = RandomForest::new(&data_view, &control);
+        let optimizer = TwoStepSearch::new(ClassifierGain { classifier: joint });
+        let optimizer_result = optimizer.find_best_split(0, 200).unwrap();
+        assert!(optimizer.model_selection(&optimizer_result).is_significant);
+
+        let joint = RandomForest::new(&data_view, &control);
+        let marginal = RandomForest::new(&X_view, &control);
+        let optimizer = TwoStepSearch::new(ConditionalClassifierGain { joint, marginal });
+        let optimizer_result = optimizer.find_best_split(0, 200).unwrap();
+        assert!(!optimizer.model_selection(&optimizer_result).is_significant);
+    }
+}
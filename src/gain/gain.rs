@@ -42,7 +42,27 @@ pub trait Gain {
     }
 
     /// Does a certain split corresponds to a true change point?
-    fn model_selection(&self, optimizer_result: &OptimizerResult) -> ModelSelectionResult;
+    ///
+    /// By default, compares the maximal gain to `minimal_gain_to_split`.
+    fn model_selection(&self, optimizer_result: &OptimizerResult) -> ModelSelectionResult {
+        match self.minimal_gain_to_split(optimizer_result) {
+            Some(minimal_gain_to_split) => ModelSelectionResult {
+                is_significant: optimizer_result.max_gain > minimal_gain_to_split,
+                p_value: None,
+            },
+            None => panic!("Not implemented."),
+        }
+    }
+
+    #[allow(unused_variables)]
+    /// Minimal gain for the split in `optimizer_result` to be kept.
+    ///
+    /// `None` if the model selection does not compare the maximal gain to a threshold,
+    /// e.g. for permutation tests, which is the default. Likelihood-based gains
+    /// typically return `control.penalized_minimal_gain_to_split`.
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        None
+    }
 
     /// Hyperparameters.
     fn control(&self) -> &Control;
@@ -64,6 +84,22 @@ pub trait ApproxGain {
         guess: usize,
         split_points: &[usize],
     ) -> ApproxGainResult;
+
+    #[allow(unused_variables)]
+    /// An approximation of the gain when splitting segment `[start, stop)`, reusing the
+    /// result `parent` of `gain_approx` on an enclosing segment with `parent.guess` in
+    /// `[start, stop)`.
+    ///
+    /// Returns `None` if results cannot be reused, which is the default. Used by the
+    /// `TwoStepSearch` optimizer if `control.warm_start` is `true`.
+    fn gain_approx_from_parent(
+        &self,
+        start: usize,
+        stop: usize,
+        parent: &ApproxGainResult,
+    ) -> Option<ApproxGainResult> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,25 @@
+mod change_in_autoregression;
+mod change_in_location;
 mod change_in_mean;
+mod change_in_multinomial;
+mod change_in_negative_binomial_rate;
+mod change_in_poisson_rate;
+mod change_in_regression;
 mod classifier_gain;
+mod conditional_classifier_gain;
 mod gain;
 mod gain_result;
+mod panel_gain;
 
+pub use change_in_autoregression::ChangeInAutoregression;
+pub use change_in_location::ChangeInLocation;
 pub use change_in_mean::ChangeInMean;
-pub use classifier_gain::{gain_from_likelihoods, ClassifierGain};
+pub use change_in_multinomial::ChangeInMultinomial;
+pub use change_in_negative_binomial_rate::{estimate_size, ChangeInNegativeBinomialRate};
+pub use change_in_poisson_rate::ChangeInPoissonRate;
+pub use change_in_regression::ChangeInRegression;
+pub use classifier_gain::{gain_from_likelihoods, permutation_test, ClassifierGain};
+pub use conditional_classifier_gain::ConditionalClassifierGain;
 pub use gain::{ApproxGain, Gain};
 pub use gain_result::{ApproxGainResult, FullGainResult, GainResult};
+pub use panel_gain::PanelGain;
@@ -0,0 +1,374 @@
+use crate::control::Control;
+use crate::gain::{
+    gain_from_likelihoods, permutation_test, ApproxGain, ApproxGainResult, Gain, GainResult,
+};
+use crate::optimizer::OptimizerResult;
+use crate::ModelSelectionResult;
+use ndarray::{s, Array1, Array2};
+
+/// Sum of the gains of several aligned series.
+///
+/// Row `i` of each series corresponds to the same point in time. Series may have
+/// different lengths, i.e., end early. A series contributes to the gain when splitting
+/// `[start, stop)` at `split` only if it has at least `minimal_segment_length`
+/// observations on either side of `split`, see `series_stop`.
+///
+/// For classifier-based gains, the log-likelihood ratios of the series are summed and
+/// the model selection is a permutation test permuting all series jointly. Otherwise,
+/// a split is kept if the summed gain exceeds the sum of the minimal gains to split of
+/// the contributing series.
+pub struct PanelGain<T: Gain> {
+    pub gains: Vec<T>,
+}
+
+impl<T> PanelGain<T>
+where
+    T: Gain,
+{
+    pub fn new(gains: Vec<T>) -> PanelGain<T> {
+        if gains.is_empty() {
+            panic!("PanelGain needs at least one series.");
+        }
+        PanelGain { gains }
+    }
+
+    /// Stop of the segment `[start, stop)` restricted to series `idx`, if the series
+    /// has at least `minimal_segment_length` observations on either side of `split`.
+    pub fn series_stop(
+        &self,
+        idx: usize,
+        start: usize,
+        stop: usize,
+        split: usize,
+    ) -> Option<usize> {
+        let minimal_segment_length =
+            (self.control().minimal_relative_segment_length * (self.n() as f64)).ceil() as usize;
+        let series_stop = stop.min(self.gains[idx].n());
+
+        if (split >= start + minimal_segment_length)
+            & (split + minimal_segment_length <= series_stop)
+        {
+            Some(series_stop)
+        } else {
+            None
+        }
+    }
+
+    /// `optimizer_result` restricted to series `idx`, if the series covers its best split.
+    fn series_optimizer_result(
+        &self,
+        idx: usize,
+        optimizer_result: &OptimizerResult,
+    ) -> Option<OptimizerResult> {
+        let stop = self.series_stop(
+            idx,
+            optimizer_result.start,
+            optimizer_result.stop,
+            optimizer_result.best_split,
+        )?;
+        Some(OptimizerResult {
+            start: optimizer_result.start,
+            stop,
+            best_split: optimizer_result.best_split,
+            max_gain: self.gains[idx].gain(
+                optimizer_result.start,
+                stop,
+                optimizer_result.best_split,
+            ),
+            gain_results: vec![],
+            n_initial_guesses: 0,
+        })
+    }
+}
+
+impl<T> Gain for PanelGain<T>
+where
+    T: Gain,
+{
+    /// Length of the longest series.
+    fn n(&self) -> usize {
+        self.gains.iter().map(|gain| gain.n()).max().unwrap()
+    }
+
+    /// Sum of the gains of the series covering `split`.
+    fn gain(&self, start: usize, stop: usize, split: usize) -> f64 {
+        (0..self.gains.len())
+            .filter_map(|idx| {
+                self.series_stop(idx, start, stop, split)
+                    .map(|series_stop| self.gains[idx].gain(start, series_stop, split))
+            })
+            .sum()
+    }
+
+    fn model_selection(&self, optimizer_result: &OptimizerResult) -> ModelSelectionResult {
+        if let Some(GainResult::ApproxGainResult(_)) = optimizer_result.gain_results.first() {
+            return permutation_test(optimizer_result, self.control(), self.n());
+        }
+
+        let minimal_gain_to_split = self.minimal_gain_to_split(optimizer_result).expect(
+            "PanelGain of classifier-based gains needs the approximate gains of TwoStepSearch.",
+        );
+        ModelSelectionResult {
+            is_significant: optimizer_result.max_gain > minimal_gain_to_split,
+            p_value: None,
+        }
+    }
+
+    /// Sum of the minimal gains to split of the series covering the best split.
+    fn minimal_gain_to_split(&self, optimizer_result: &OptimizerResult) -> Option<f64> {
+        (0..self.gains.len())
+            .filter_map(|idx| {
+                self.series_optimizer_result(idx, optimizer_result)
+                    .map(|result| self.gains[idx].minimal_gain_to_split(&result))
+            })
+            .sum()
+    }
+
+    fn control(&self) -> &Control {
+        self.gains[0].control()
+    }
+}
+
+impl<T> ApproxGain for PanelGain<T>
+where
+    T: Gain + ApproxGain,
+{
+    /// Sum of the classifier log-likelihoods of the series covering `guess`.
+    ///
+    /// The likelihoods of series ending before `stop` are padded with zeros, such that
+    /// they contribute a constant gain for splits after their end. The predictions are
+    /// averaged over the contributing series.
+    fn gain_approx(
+        &self,
+        start: usize,
+        stop: usize,
+        guess: usize,
+        split_candidates: &[usize],
+    ) -> ApproxGainResult {
+        let mut likelihoods = Array2::<f64>::zeros((2, stop - start));
+        let mut predictions = Array1::<f64>::zeros(stop - start);
+        let mut counts = Array1::<f64>::zeros(stop - start);
+
+        for (idx, gain) in self.gains.iter().enumerate() {
+            if let Some(series_stop) = self.series_stop(idx, start, stop, guess) {
+                let series_split_candidates: Vec<usize> = split_candidates
+                    .iter()
+                    .filter(|&&split| split < series_stop)
+                    .cloned()
+                    .collect();
+                let result = gain.gain_approx(start, series_stop, guess, &series_split_candidates);
+
+                let mut slice = likelihoods.slice_mut(s![.., ..(series_stop - start)]);
+                slice += &result.likelihoods;
+                let mut slice = predictions.slice_mut(s![..(series_stop - start)]);
+                slice += &result.predictions;
+                counts
+                    .slice_mut(s![..(series_stop - start)])
+                    .mapv_inplace(|x| x + 1.);
+            }
+        }
+
+        let gain = gain_from_likelihoods(&likelihoods);
+
+        ApproxGainResult {
+            start,
+            stop,
+            guess,
+            gain,
+            best_split: None,
+            max_gain: None,
+            likelihoods,
+            predictions: predictions / counts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::RandomForest;
+    use crate::gain::{
+        ChangeInAutoregression, ChangeInLocation, ChangeInMean, ChangeInMultinomial,
+        ChangeInNegativeBinomialRate, ChangeInPoissonRate, ChangeInRegression, ClassifierGain,
+    };
+    use crate::optimizer::{GridSearch, Optimizer};
+    use crate::testing;
+    use assert_approx_eq::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0, 0, 100, 50, Some(100))]
+    #[case(1, 0, 100, 50, Some(60))]
+    #[case(1, 0, 100, 59, Some(60))]
+    #[case(1, 0, 100, 60, None)]
+    #[case(1, 40, 80, 70, None)]
+    #[case(1, 50, 80, 50, None)]
+    fn test_series_stop(
+        #[case] idx: usize,
+        #[case] start: usize,
+        #[case] stop: usize,
+        #[case] split: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let X = testing::array();
+        let X_view = X.view();
+        let X_short = X.slice(s![..60, ..]);
+
+        let control = Control::default();
+        let gain = PanelGain::new(vec![
+            ChangeInMean::new(&X_view, &control),
+            ChangeInMean::new(&X_short, &control),
+        ]);
+        assert_eq!(gain.n(), 100);
+        assert_eq!(gain.series_stop(idx, start, stop, split), expected);
+    }
+
+    #[rstest]
+    #[case(50)]
+    #[case(70)]
+    fn test_panel_gain_change_in_mean(#[case] split: usize) {
+        let X = testing::array();
+        let X_view = X.view();
+        let X_short = X.slice(s![..60, ..]);
+
+        let control = Control::default();
+        let long = ChangeInMean::new(&X_view, &control);
+        let short = ChangeInMean::new(&X_short, &control);
+        let gain = PanelGain::new(vec![
+            ChangeInMean::new(&X_view, &control),
+            ChangeInMean::new(&X_short, &control),
+        ]);
+
+        let optimizer_result = OptimizerResult {
+            start: 0,
+            stop: 100,
+            best_split: split,
+            max_gain: 0.,
+            gain_results: vec![],
+            n_initial_guesses: 0,
+        };
+
+        // The short series only contributes to splits it covers. The BIC penalty is
+        // log(n) * (d + 1) for each series.
+        if split < 60 {
+            assert_approx_eq!(
+                gain.gain(0, 100, split),
+                long.gain(0, 100, split) + short.gain(0, 60, split)
+            );
+            assert_approx_eq!(
+                gain.minimal_gain_to_split(&optimizer_result).unwrap(),
+                6. * (100_f64.ln() + 60_f64.ln())
+            );
+        } else {
+            assert_approx_eq!(gain.gain(0, 100, split), long.gain(0, 100, split));
+            assert_approx_eq!(
+                gain.minimal_gain_to_split(&optimizer_result).unwrap(),
+                6. * 100_f64.ln()
+            );
+        }
+    }
+
+    #[test]
+    fn test_panel_gain_approx() {
+        let X = testing::array();
+        let X_view = X.view();
+        let X_short = X.slice(s![..60, ..]);
+
+        let control = Control::default();
+        let long = ClassifierGain {
+            classifier: RandomForest::new(&X_view, &control),
+        };
+        let short = ClassifierGain {
+            classifier: RandomForest::new(&X_short, &control),
+        };
+        let gain = PanelGain::new(vec![
+            ClassifierGain {
+                classifier: RandomForest::new(&X_view, &control),
+            },
+            ClassifierGain {
+                classifier: RandomForest::new(&X_short, &control),
+            },
+        ]);
+
+        let result = gain.gain_approx(0, 100, 40, &[]);
+        let long_result = long.gain_approx(0, 100, 40, &[]);
+        let short_result = short.gain_approx(0, 60, 40, &[]);
+
+        assert_approx_eq!(
+            result.gain[30],
+            long_result.gain[30] + short_result.gain[30]
+        );
+        // After the end of the short series, all its observations are assigned to the
+        // first segment.
+        assert_approx_eq!(
+            result.gain[80],
+            long_result.gain[80] + short_result.likelihoods.row(0).sum()
+        );
+        assert_approx_eq!(result.predictions[80], long_result.predictions[80]);
+
+        // The short series does not cover a split at 70.
+        let result = gain.gain_approx(0, 100, 70, &[]);
+        let long_result = long.gain_approx(0, 100, 70, &[]);
+        assert_eq!(result.gain, long_result.gain);
+    }
+
+    fn panel_model_selection<T: Gain>(gains: Vec<T>) -> ModelSelectionResult {
+        let optimizer = GridSearch {
+            gain: PanelGain::new(gains),
+        };
+        let optimizer_result = optimizer.find_best_split(0, 100).unwrap();
+        assert!(optimizer_result.best_split.abs_diff(50) <= 2);
+        optimizer.model_selection(&optimizer_result)
+    }
+
+    #[test]
+    fn test_panel_gain_model_selection() {
+        // Counts with a shift of 5 at 50 in the first feature. The second series ends
+        // at 80.
+        let X = Array2::from_shape_fn((100, 2), |(i, j)| {
+            ((i * (j + 1)) % 3 + (i % 7) % 2 + if (i < 50) | (j == 1) { 0 } else { 5 }) as f64
+        });
+        let X_view = X.view();
+        let X_short = X.slice(s![..80, ..]);
+        let X_categorical = X.mapv(|x| x as usize);
+        let X_categorical_view = X_categorical.view();
+        let X_categorical_short = X_categorical.slice(s![..80, ..]);
+        let control = Control::default().with_minimal_relative_segment_length(0.1);
+
+        let results = vec![
+            panel_model_selection(vec![
+                ChangeInLocation::new(&X_view, &control),
+                ChangeInLocation::new(&X_short, &control),
+            ]),
+            panel_model_selection(vec![
+                ChangeInRegression::new(&X_view, 1, &control),
+                ChangeInRegression::new(&X_short, 1, &control),
+            ]),
+            panel_model_selection(vec![
+                ChangeInPoissonRate::new(&X_view, &control),
+                ChangeInPoissonRate::new(&X_short, &control),
+            ]),
+            panel_model_selection(vec![
+                ChangeInNegativeBinomialRate::new(&X_view, &control),
+                ChangeInNegativeBinomialRate::new(&X_short, &control),
+            ]),
+            panel_model_selection(vec![
+                ChangeInMultinomial::new(&X_categorical_view, &control),
+                ChangeInMultinomial::new(&X_categorical_short, &control),
+            ]),
+            panel_model_selection(vec![
+                ChangeInAutoregression::new(&X_view, 1, &control),
+                ChangeInAutoregression::new(&X_short, 1, &control),
+            ]),
+        ];
+        for result in results {
+            assert!(result.is_significant);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "PanelGain needs at least one series.")]
+    fn test_panel_gain_empty() {
+        PanelGain::<ChangeInMean>::new(vec![]);
+    }
+}
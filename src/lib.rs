@@ -7,17 +7,26 @@
 mod binary_segmentation;
 pub mod classifier;
 mod control;
+pub mod embedding;
 mod fmt;
 pub mod gain;
+pub mod metrics;
 mod model_selection_result;
+pub mod observer;
 pub mod optimizer;
 mod segmentation;
+pub mod simulate;
+pub mod study;
 
-pub use binary_segmentation::{BinarySegmentationResult, BinarySegmentationTree};
+pub use binary_segmentation::{
+    BinarySegmentationResult, BinarySegmentationTree, ChangePoint, ChangePointRanking, NodeIter,
+    PanelResult, SegmentSummary,
+};
 pub use classifier::Classifier;
-pub use control::Control;
+pub use control::{Calibration, Control, Penalty, PermutationType, TreeScaling};
 pub use gain::{ClassifierGain, Gain};
 pub use model_selection_result::ModelSelectionResult;
+pub use observer::Observer;
 pub use optimizer::Optimizer;
 pub use segmentation::{Segmentation, SegmentationType};
 pub mod utils;
@@ -0,0 +1,329 @@
+//! Metrics comparing an estimated segmentation to a true segmentation.
+//!
+//! Segmentations are given by their split points, either as a
+//! `BinarySegmentationResult` or as a `Vec<usize>` / `[usize]` of sorted split points.
+//! A segmentation of `n` observations with split points `s_1 < ... < s_k` consists of
+//! the segments `[0, s_1), [s_1, s_2), ..., [s_k, n)`.
+use crate::BinarySegmentationResult;
+
+/// Anything that defines a segmentation through its split points.
+pub trait SplitPoints {
+    /// Sorted split points.
+    fn split_points(&self) -> Vec<usize>;
+}
+
+impl SplitPoints for BinarySegmentationResult {
+    fn split_points(&self) -> Vec<usize> {
+        BinarySegmentationResult::split_points(self)
+    }
+}
+
+impl SplitPoints for [usize] {
+    fn split_points(&self) -> Vec<usize> {
+        self.to_vec()
+    }
+}
+
+impl SplitPoints for Vec<usize> {
+    fn split_points(&self) -> Vec<usize> {
+        self.clone()
+    }
+}
+
+/// Hausdorff distance between two sets of split points.
+///
+/// The largest distance of a split point in one set to the closest split point in the
+/// other set. Is `0` if both sets are empty and `f64::INFINITY` if exactly one is.
+pub fn hausdorff_distance<A, B>(first: &A, second: &B) -> f64
+where
+    A: SplitPoints + ?Sized,
+    B: SplitPoints + ?Sized,
+{
+    let first = first.split_points();
+    let second = second.split_points();
+
+    match (first.is_empty(), second.is_empty()) {
+        (true, true) => 0.,
+        (true, false) | (false, true) => f64::INFINITY,
+        (false, false) => {
+            let directed = |from: &[usize], to: &[usize]| {
+                from.iter()
+                    .map(|&x| to.iter().map(|&y| x.abs_diff(y)).min().unwrap())
+                    .max()
+                    .unwrap()
+            };
+            directed(&first, &second).max(directed(&second, &first)) as f64
+        }
+    }
+}
+
+/// Precision and recall of estimated split points with a tolerance `margin`.
+///
+/// An estimated split point is a true positive if it can be matched to a true split
+/// point at a distance of at most `margin`, where each true split point is matched at
+/// most once. Precision is `1` if there are no estimated split points and recall is `1`
+/// if there are no true split points.
+pub fn precision_recall<A, B>(truth: &A, estimate: &B, margin: usize) -> (f64, f64)
+where
+    A: SplitPoints + ?Sized,
+    B: SplitPoints + ?Sized,
+{
+    let truth = truth.split_points();
+    let estimate = estimate.split_points();
+
+    // For sorted split points and a symmetric margin, greedily matching the smallest
+    // unmatched split points results in a maximum matching.
+    let (mut i, mut j, mut true_positives) = (0, 0, 0);
+    while i < truth.len() && j < estimate.len() {
+        if truth[i].abs_diff(estimate[j]) <= margin {
+            true_positives += 1;
+            i += 1;
+            j += 1;
+        } else if estimate[j] < truth[i] {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+
+    let precision = if estimate.is_empty() {
+        1.
+    } else {
+        true_positives as f64 / estimate.len() as f64
+    };
+    let recall = if truth.is_empty() {
+        1.
+    } else {
+        true_positives as f64 / truth.len() as f64
+    };
+
+    (precision, recall)
+}
+
+/// F1 score, the harmonic mean of precision and recall, with a tolerance `margin`.
+///
+/// See [`precision_recall`].
+pub fn f1_score<A, B>(truth: &A, estimate: &B, margin: usize) -> f64
+where
+    A: SplitPoints + ?Sized,
+    B: SplitPoints + ?Sized,
+{
+    let (precision, recall) = precision_recall(truth, estimate, margin);
+    if precision + recall == 0. {
+        0.
+    } else {
+        2. * precision * recall / (precision + recall)
+    }
+}
+
+/// Adjusted Rand index of the segmentations of `n` observations.
+///
+/// The Rand index is the share of pairs of observations on which the segmentations
+/// agree, i.e., that are either in the same segment in both or in different segments
+/// in both. The adjusted Rand index corrects for chance, such that it is `1` for
+/// identical segmentations and `0` in expectation for random segmentations.
+pub fn adjusted_rand_index<A, B>(first: &A, second: &B, n: usize) -> f64
+where
+    A: SplitPoints + ?Sized,
+    B: SplitPoints + ?Sized,
+{
+    let first = segments(&first.split_points(), n);
+    let second = segments(&second.split_points(), n);
+
+    let pairs = |count: usize| (count * count.saturating_sub(1)) as f64 / 2.;
+
+    let index: f64 = first
+        .iter()
+        .flat_map(|a| second.iter().map(move |b| pairs(overlap(*a, *b))))
+        .sum();
+    let first_pairs: f64 = first.iter().map(|(start, stop)| pairs(stop - start)).sum();
+    let second_pairs: f64 = second.iter().map(|(start, stop)| pairs(stop - start)).sum();
+
+    let expected_index = first_pairs * second_pairs / pairs(n);
+    let max_index = (first_pairs + second_pairs) / 2.;
+
+    if max_index == expected_index {
+        1.
+    } else {
+        (index - expected_index) / (max_index - expected_index)
+    }
+}
+
+/// Covering of the true segmentation of `n` observations by the estimated one.
+///
+/// The average over true segments, weighted by their lengths, of the largest Jaccard
+/// index of the true segment with an estimated segment. Is `1` for identical
+/// segmentations.
+pub fn covering<A, B>(truth: &A, estimate: &B, n: usize) -> f64
+where
+    A: SplitPoints + ?Sized,
+    B: SplitPoints + ?Sized,
+{
+    let truth = segments(&truth.split_points(), n);
+    let estimate = segments(&estimate.split_points(), n);
+
+    truth
+        .iter()
+        .map(|a| {
+            let max_jaccard = estimate
+                .iter()
+                .map(|b| {
+                    let intersection = overlap(*a, *b) as f64;
+                    let union = ((a.1 - a.0) + (b.1 - b.0)) as f64 - intersection;
+                    intersection / union
+                })
+                .fold(0., f64::max);
+            (a.1 - a.0) as f64 * max_jaccard
+        })
+        .sum::<f64>()
+        / n as f64
+}
+
+/// Segments `[start, stop)` of `n` observations defined by `split_points`.
+///
+/// Panics if the split points are not strictly increasing or not in `(0, n)`.
+fn segments(split_points: &[usize], n: usize) -> Vec<(usize, usize)> {
+    let mut boundaries = Vec::with_capacity(split_points.len() + 2);
+    boundaries.push(0);
+    boundaries.extend_from_slice(split_points);
+    boundaries.push(n);
+
+    if boundaries.windows(2).any(|window| window[0] >= window[1]) {
+        panic!(
+            "Split points must be strictly increasing and in (0, n = {}). Got {:?}.",
+            n, split_points
+        );
+    }
+
+    boundaries
+        .windows(2)
+        .map(|window| (window[0], window[1]))
+        .collect()
+}
+
+fn overlap(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.1.min(b.1).saturating_sub(a.0.max(b.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizer::GridSearch;
+    use crate::{testing, BinarySegmentationTree, Control, Segmentation, SegmentationType};
+    use assert_approx_eq::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(vec![], vec![], 0.)]
+    #[case(vec![10], vec![], f64::INFINITY)]
+    #[case(vec![10, 50], vec![12], 38.)]
+    #[case(vec![10, 50], vec![12, 45], 5.)]
+    #[case(vec![25, 40, 80], vec![25, 40, 80], 0.)]
+    fn test_hausdorff_distance(
+        #[case] first: Vec<usize>,
+        #[case] second: Vec<usize>,
+        #[case] expected: f64,
+    ) {
+        assert_eq!(hausdorff_distance(&first, &second), expected);
+        assert_eq!(hausdorff_distance(&second, &first), expected);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![], 0, (1., 1.))]
+    #[case(vec![50], vec![], 5, (1., 0.))]
+    #[case(vec![], vec![50], 5, (0., 1.))]
+    #[case(vec![50, 100], vec![48, 103], 5, (1., 1.))]
+    #[case(vec![50, 100], vec![48, 103], 2, (0.5, 0.5))]
+    // Each true split point is matched at most once.
+    #[case(vec![50], vec![48, 52], 5, (0.5, 1.))]
+    #[case(vec![50, 55], vec![52], 5, (1., 0.5))]
+    #[case(vec![10, 20, 30], vec![12, 22, 50, 60], 2, (0.5, 2. / 3.))]
+    fn test_precision_recall(
+        #[case] truth: Vec<usize>,
+        #[case] estimate: Vec<usize>,
+        #[case] margin: usize,
+        #[case] expected: (f64, f64),
+    ) {
+        let (precision, recall) = precision_recall(&truth, &estimate, margin);
+        assert_approx_eq!(precision, expected.0);
+        assert_approx_eq!(recall, expected.1);
+    }
+
+    #[rstest]
+    #[case(vec![50, 100], vec![48, 103], 5, 1.)]
+    #[case(vec![10, 20, 30], vec![12, 22, 50, 60], 2, 4. / 7.)]
+    #[case(vec![50], vec![], 5, 0.)]
+    fn test_f1_score(
+        #[case] truth: Vec<usize>,
+        #[case] estimate: Vec<usize>,
+        #[case] margin: usize,
+        #[case] expected: f64,
+    ) {
+        assert_approx_eq!(f1_score(&truth, &estimate, margin), expected);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![], 10, 1.)]
+    #[case(vec![5], vec![5], 10, 1.)]
+    // (16 - 28 / 3) / (41 / 2 - 28 / 3)
+    #[case(vec![5], vec![4], 10, 40. / 67.)]
+    // (10 - 16 / 3) / (16 - 16 / 3)
+    #[case(vec![3, 6], vec![5], 10, 14. / 32.)]
+    #[case(vec![2, 4, 6, 8], vec![], 10, 0.)]
+    fn test_adjusted_rand_index(
+        #[case] first: Vec<usize>,
+        #[case] second: Vec<usize>,
+        #[case] n: usize,
+        #[case] expected: f64,
+    ) {
+        assert_approx_eq!(adjusted_rand_index(&first, &second, n), expected);
+        assert_approx_eq!(adjusted_rand_index(&second, &first, n), expected);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![], 10, 1.)]
+    #[case(vec![5], vec![5], 10, 1.)]
+    // 0.5 * 4 / 5 + 0.5 * 5 / 6
+    #[case(vec![5], vec![4], 10, 49. / 60.)]
+    // 0.5 * 5 / 10 + 0.5 * 5 / 10
+    #[case(vec![5], vec![], 10, 0.5)]
+    // 1 * 5 / 10
+    #[case(vec![], vec![5], 10, 0.5)]
+    fn test_covering(
+        #[case] truth: Vec<usize>,
+        #[case] estimate: Vec<usize>,
+        #[case] n: usize,
+        #[case] expected: f64,
+    ) {
+        assert_approx_eq!(covering(&truth, &estimate, n), expected);
+    }
+
+    #[rstest]
+    #[case(vec![5, 3])]
+    #[case(vec![10])]
+    #[case(vec![0])]
+    #[should_panic(expected = "Split points must be strictly increasing")]
+    fn test_segments_invalid(#[case] split_points: Vec<usize>) {
+        segments(&split_points, 10);
+    }
+
+    #[test]
+    fn test_metrics_binary_segmentation_result() {
+        let X = testing::array();
+        let X_view = X.view();
+
+        let control = Control::default();
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let optimizer = GridSearch { gain };
+        let mut segmentation = Segmentation::new(SegmentationType::SBS, &optimizer);
+        let mut tree = BinarySegmentationTree::new(&X_view);
+        tree.grow(&mut segmentation);
+        let result = BinarySegmentationResult::from_tree(tree);
+
+        let truth = [25, 40, 80];
+        assert_eq!(hausdorff_distance(&truth[..], &result), 0.);
+        assert_eq!(f1_score(&truth[..], &result, 0), 1.);
+        assert_eq!(adjusted_rand_index(&truth[..], &result, 100), 1.);
+        assert_eq!(covering(&truth[..], &result, 100), 1.);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::optimizer::OptimizerResult;
+use crate::ModelSelectionResult;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Stage of the segmentation an `Event::Progress` refers to.
+pub enum Stage {
+    /// Fitting the seeded (SBS) or wild (WBS) segments. Progress is counted in
+    /// segments.
+    Segments,
+    /// Growing the binary segmentation tree. Progress is counted in observations
+    /// belonging to finished leaves.
+    Tree,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Progress of a stage of the segmentation.
+pub struct Progress {
+    pub stage: Stage,
+    pub completed: usize,
+    pub total: usize,
+    /// Time elapsed since the start of the stage.
+    pub elapsed: Duration,
+    /// Estimated time remaining until the stage is finished, extrapolated linearly
+    /// from `elapsed`. `None` if nothing has been completed yet.
+    pub eta: Option<Duration>,
+}
+
+impl Progress {
+    pub fn new(stage: Stage, completed: usize, total: usize, started: Instant) -> Self {
+        let elapsed = started.elapsed();
+        let eta = if completed > 0 {
+            Some(elapsed.mul_f64(total.saturating_sub(completed) as f64 / completed as f64))
+        } else {
+            None
+        };
+
+        Progress {
+            stage,
+            completed,
+            total,
+            elapsed,
+            eta,
+        }
+    }
+
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.completed as f64 / self.total as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Event emitted during the segmentation.
+pub enum Event<'a> {
+    /// The optimizer found the best split of the segment `[start, stop)`.
+    SegmentEvaluated(&'a OptimizerResult),
+    /// Model selection (e.g. the permutation test) for the best split of a node of
+    /// the binary segmentation tree is done.
+    ModelSelection(&'a OptimizerResult, &'a ModelSelectionResult),
+    /// A node of the binary segmentation tree was split. The root has depth `0`.
+    NodeSplit {
+        start: usize,
+        stop: usize,
+        split: usize,
+        depth: usize,
+    },
+    Progress(Progress),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Returned by an `Observer` to continue or cancel the segmentation.
+pub enum Signal {
+    Continue,
+    Cancel,
+}
+
+/// Observer of the segmentation, e.g. to report progress or to cancel.
+///
+/// After an `Observer` returned `Signal::Cancel`, no further segments are
+/// evaluated and no further events are emitted.
+pub trait Observer {
+    fn notify(&mut self, event: &Event) -> Signal;
+}
+
+impl<F> Observer for F
+where
+    F: FnMut(&Event) -> Signal,
+{
+    fn notify(&mut self, event: &Event) -> Signal {
+        self(event)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Error returned if the segmentation was cancelled by an `Observer`.
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Segmentation was cancelled.")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0, 10, None)]
+    #[case(5, 10, Some(1.))]
+    #[case(2, 10, Some(4.))]
+    #[case(10, 10, Some(0.))]
+    fn test_progress_eta(
+        #[case] completed: usize,
+        #[case] total: usize,
+        #[case] expected_ratio: Option<f64>,
+    ) {
+        let progress = Progress::new(Stage::Tree, completed, total, Instant::now());
+        match (progress.eta, expected_ratio) {
+            (None, None) => (),
+            (Some(eta), Some(ratio)) => {
+                assert_eq!(eta, progress.elapsed.mul_f64(ratio))
+            }
+            _ => panic!("Unexpected eta {:?}", progress.eta),
+        }
+        assert_eq!(progress.fraction(), completed as f64 / total as f64);
+    }
+}
@@ -42,6 +42,7 @@ where
             best_split,
             max_gain,
             gain_results: vec![GainResult::FullGainResult(full_gain)],
+            n_initial_guesses: 0,
         })
     }
 
@@ -54,6 +55,7 @@ where
 mod tests {
 
     use super::*;
+    use crate::embedding::burn_in_control;
     use crate::testing;
     use rstest::*;
 
@@ -126,4 +128,54 @@ mod tests {
         let grid_search = GridSearch { gain };
         assert_eq!(grid_search.split_candidates(start, stop).unwrap(), expected);
     }
+
+    #[rstest]
+    #[case(0, 0, vec![], false, vec![1, 2, 3, 4, 5, 6, 7, 8])]
+    #[case(0, 2, vec![], false, vec![3, 4, 5, 6, 7, 8])]
+    #[case(0, 0, vec![4, 2], false, vec![4, 5, 6, 7, 8])]
+    #[case(0, 0, vec![], true, vec![2, 3, 4, 5, 6, 7, 8])]
+    #[case(0, 1, vec![3], true, vec![3, 4, 5, 6, 7, 8])]
+    #[case(3, 4, vec![], false, vec![5, 6, 7, 8])]
+    fn test_split_candidates_embedding_burn_in(
+        #[case] start: usize,
+        #[case] lags: usize,
+        #[case] windows: Vec<usize>,
+        #[case] differences: bool,
+        #[case] expected: Vec<usize>,
+    ) {
+        let X = ndarray::Array2::<f64>::zeros((10, 1));
+        let X_view = X.view();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.09)
+            .with_embedding_lags(lags)
+            .with_embedding_windows(windows)
+            .with_embedding_differences(differences);
+        let control = burn_in_control(&control);
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let grid_search = GridSearch { gain };
+        assert_eq!(grid_search.split_candidates(start, 10).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(0, Some(2.), None, vec![3, 4, 5, 6, 7, 8])]
+    #[case(0, None, Some(vec![(2.5, 11.5)]), vec![1, 2, 3, 6, 7, 8])]
+    #[case(4, Some(2.), None, vec![7, 8])]
+    #[case(0, Some(2.), Some(vec![(0., 3.), (13., 14.)]), vec![4, 5, 6, 7])]
+    fn test_split_candidates_time_index(
+        #[case] start: usize,
+        #[case] minimal_segment_duration: Option<f64>,
+        #[case] forbidden_time_segments: Option<Vec<(f64, f64)>>,
+        #[case] expected: Vec<usize>,
+    ) {
+        let X = ndarray::Array2::<f64>::zeros((10, 1));
+        let X_view = X.view();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.09)
+            .with_time_index(Some(vec![0., 1., 2., 3., 10., 11., 12., 13., 14., 30.]))
+            .with_minimal_segment_duration(minimal_segment_duration)
+            .with_forbidden_time_segments(forbidden_time_segments);
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let grid_search = GridSearch { gain };
+        assert_eq!(grid_search.split_candidates(start, 10).unwrap(), expected);
+    }
 }
@@ -34,6 +34,25 @@ pub trait Optimizer {
                 });
             }
 
+            if let Some(time_index) = &self.control().time_index {
+                if let Some(duration) = self.control().minimal_segment_duration {
+                    split_candidates.retain(|&x| {
+                        (time_index[x - 1] - time_index[start] >= duration)
+                            && (time_index[stop - 1] - time_index[x] >= duration)
+                    });
+                }
+
+                // A split at x separates observations x - 1 and x. It is forbidden if
+                // both lie within a forbidden time segment.
+                if let Some(forbidden_time_segments) = &self.control().forbidden_time_segments {
+                    split_candidates.retain(|&x| {
+                        forbidden_time_segments.iter().all(|segment| {
+                            time_index[x - 1] < segment.0 || time_index[x] > segment.1
+                        })
+                    });
+                }
+            }
+
             if split_candidates.is_empty() {
                 Err("No split_candidates left after filtering out forbidden_segments.")
             } else {
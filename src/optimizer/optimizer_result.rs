@@ -8,6 +8,10 @@ pub struct OptimizerResult {
     pub best_split: usize,
     pub max_gain: f64,
     pub gain_results: Vec<GainResult>,
+    /// Number of leading `gain_results` from fits at initial guesses that do not
+    /// depend on the data, e.g. the first step of `TwoStepSearch`. Used for the
+    /// permutation test in classifier-based changepoint detection.
+    pub n_initial_guesses: usize,
 }
 
 // https://doc.rust-lang.org/rust-by-example/hello/print/print_display.html
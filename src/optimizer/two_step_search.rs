@@ -1,15 +1,41 @@
-use crate::gain::{ApproxGain, GainResult};
+use crate::gain::{ApproxGain, ApproxGainResult, GainResult};
 use crate::optimizer::OptimizerResult;
 use crate::{Control, Gain, ModelSelectionResult, Optimizer};
+use std::cell::RefCell;
+use std::collections::HashMap;
 
+/// Two-step search for the best split.
+///
+/// Approximate gain curves are computed from single fits at
+/// `control.two_step_search_n_guesses` equally spaced initial guesses. If
+/// `control.two_step_search_bracketing` is `true`, a golden-section search around the
+/// best initial guess follows. The gain is then recomputed from a fit at the best
+/// split, up to `control.two_step_search_n_refinements` times or until the best split
+/// no longer changes.
+///
+/// Results of `gain_approx` are cached by `(start, stop, guess)`, such that no fit is
+/// computed twice within `find_best_split`. If `control.warm_start` is `true`, the
+/// cache is kept between calls and the fits on the smallest enclosing segment, e.g. the
+/// parent in binary segmentation, are restricted to `[start, stop)` and used instead of
+/// the initial guesses. See `ApproxGain::gain_approx_from_parent`. As binary
+/// segmentation splits segments in order of their start, fits on segments ending at or
+/// before `start` can no longer be parents and are evicted.
 pub struct TwoStepSearch<T: Gain> {
     pub gain: T,
+    cache: RefCell<HashMap<(usize, usize, usize), GainResult>>,
 }
 
 impl<T> TwoStepSearch<T>
 where
     T: Gain + ApproxGain,
 {
+    pub fn new(gain: T) -> TwoStepSearch<T> {
+        TwoStepSearch {
+            gain,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
     fn _single_find_best_split(
         &self,
         start: usize,
@@ -17,9 +43,180 @@ where
         guess: usize,
         split_candidates: &[usize],
     ) -> GainResult {
-        let mut approx_gain_result = self.gain.gain_approx(start, stop, guess, split_candidates);
+        if let Some(result) = self.cache.borrow().get(&(start, stop, guess)) {
+            return result.clone();
+        }
+
+        let approx_gain_result = self.gain.gain_approx(start, stop, guess, split_candidates);
+        let result = Self::_with_best_split(approx_gain_result, split_candidates);
+        self.cache
+            .borrow_mut()
+            .insert((start, stop, guess), result.clone());
+        result
+    }
+
+    /// Cached fits on the smallest enclosing segment, restricted to `[start, stop)`.
+    ///
+    /// The enclosing segment is chosen without looking at the gains. All its fits at
+    /// guesses in `split_candidates` are returned, such that the permutation test
+    /// accounts for the choice among them.
+    fn _warm_start(
+        &self,
+        start: usize,
+        stop: usize,
+        split_candidates: &[usize],
+    ) -> Vec<GainResult> {
+        let cache = self.cache.borrow();
+        let is_candidate = |(parent_start, parent_stop, guess): &(usize, usize, usize)| {
+            (*parent_start <= start)
+                & (stop <= *parent_stop)
+                & ((*parent_start, *parent_stop) != (start, stop))
+                & split_candidates.contains(guess)
+        };
+
+        let parent = cache
+            .keys()
+            .filter(|key| is_candidate(key))
+            .map(|(parent_start, parent_stop, _)| (*parent_start, *parent_stop))
+            .min_by_key(|(parent_start, parent_stop)| (parent_stop - parent_start, *parent_start));
+        let Some(parent) = parent else {
+            return vec![];
+        };
+
+        let mut keys: Vec<&(usize, usize, usize)> = cache
+            .keys()
+            .filter(|key| is_candidate(key) & ((key.0, key.1) == parent))
+            .collect();
+        keys.sort();
+
+        keys.into_iter()
+            .filter_map(|key| match &cache[key] {
+                GainResult::ApproxGainResult(result) => {
+                    self.gain.gain_approx_from_parent(start, stop, result)
+                }
+                _ => None,
+            })
+            .map(|result| Self::_with_best_split(result, split_candidates))
+            .collect()
+    }
+
+    /// `control.two_step_search_n_guesses` equally spaced initial guesses in
+    /// `[start, stop)`. If there are forbidden segments or a minimal segment duration,
+    /// the guesses are equally spaced quantiles of `split_candidates` instead.
+    fn _initial_guesses(
+        &self,
+        start: usize,
+        stop: usize,
+        split_candidates: &[usize],
+    ) -> Vec<usize> {
+        let n_guesses = self.control().two_step_search_n_guesses;
+        let mut guesses: Vec<usize> = vec![];
+
+        for k in 1..=n_guesses {
+            let guess = if self.control().restricts_split_candidates() {
+                // there is at least one element in split_candidates
+                split_candidates[k * split_candidates.len() / (n_guesses + 1)]
+            } else {
+                start + k * (stop - start) / (n_guesses + 1)
+            };
+
+            // we add this if it is not equal to last
+            if guesses.last().is_none_or(|last| guess > *last) {
+                guesses.push(guess);
+            }
+        }
+        guesses
+    }
 
-        let mut best_split = guess;
+    /// Golden-section search for the guess with the largest maximal gain.
+    ///
+    /// The search starts from the bracket formed by the neighbours of the initial
+    /// guess with the largest maximal gain and is stopped once the bracket is shorter
+    /// than the minimal segment length. Returns the results of the additional fits.
+    fn _bracketing(
+        &self,
+        start: usize,
+        stop: usize,
+        split_candidates: &[usize],
+        initial_results: &[GainResult],
+    ) -> Vec<GainResult> {
+        let mut results = vec![];
+        let mut initial: Vec<(usize, f64)> = initial_results
+            .iter()
+            .filter_map(|result| Some((result.guess()?, result.max_gain()?)))
+            .collect();
+        initial.sort_by_key(|(guess, _)| *guess);
+
+        let best_idx = match initial
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.1.partial_cmp(&b.1).unwrap())
+        {
+            Some((idx, _)) => idx,
+            None => return results,
+        };
+
+        let (mut middle, mut middle_gain) = initial[best_idx];
+        let mut lower = if best_idx > 0 {
+            initial[best_idx - 1].0
+        } else {
+            start
+        };
+        let mut upper = initial.get(best_idx + 1).map_or(stop, |(guess, _)| *guess);
+
+        let tolerance = usize::max(
+            (self.control().minimal_relative_segment_length * self.n() as f64).ceil() as usize,
+            1,
+        );
+        // 2 - golden ratio.
+        let ratio = (3. - 5_f64.sqrt()) / 2.;
+
+        while usize::max(middle - lower, upper - middle) > tolerance {
+            // Probe the larger of the two sub-intervals.
+            let target = if upper - middle >= middle - lower {
+                middle + ((ratio * (upper - middle) as f64).round() as usize).max(1)
+            } else {
+                middle - ((ratio * (middle - lower) as f64).round() as usize).max(1)
+            };
+
+            // Closest split candidate strictly within the bracket.
+            let probe = split_candidates
+                .iter()
+                .filter(|x| (**x > lower) & (**x < upper) & (**x != middle))
+                .min_by_key(|x| x.abs_diff(target));
+            let probe = match probe {
+                Some(probe) => *probe,
+                None => break,
+            };
+
+            let result = self._single_find_best_split(start, stop, probe, split_candidates);
+            let gain = result.max_gain().unwrap();
+            results.push(result);
+
+            if gain > middle_gain {
+                if probe > middle {
+                    lower = middle;
+                } else {
+                    upper = middle;
+                }
+                middle = probe;
+                middle_gain = gain;
+            } else if probe > middle {
+                upper = probe;
+            } else {
+                lower = probe;
+            }
+        }
+
+        results
+    }
+
+    fn _with_best_split(
+        mut approx_gain_result: ApproxGainResult,
+        split_candidates: &[usize],
+    ) -> GainResult {
+        let start = approx_gain_result.start;
+        let mut best_split = approx_gain_result.guess;
         let mut max_gain = -f64::INFINITY;
 
         for index in split_candidates {
@@ -50,50 +247,32 @@ where
 
     fn find_best_split(&self, start: usize, stop: usize) -> Result<OptimizerResult, &str> {
         let split_candidates = self.split_candidates(start, stop)?;
+        let control = self.control();
 
-        let mut guesses = vec![];
         let mut results: Vec<GainResult> = vec![];
 
-        // if there are forbidden segments change the heuristics
-        // pick middle element of split_candidates, 1/4th and 3/4th
-        if let Some(_forbidden_segments) = &self.control().forbidden_segments {
-            // there is at least one element in split_candidates
-            guesses.push(
-                split_candidates
-                    .clone()
-                    .into_iter()
-                    .nth(split_candidates.len() / 4)
-                    .unwrap(),
-            );
-
-            // we add this if it is not equal to last
-            let cand = split_candidates
-                .clone()
-                .into_iter()
-                .nth(split_candidates.len() / 2)
-                .unwrap();
-            if cand > guesses[guesses.len() - 1] {
-                guesses.push(cand)
-            };
+        if control.warm_start {
+            self.cache
+                .borrow_mut()
+                .retain(|(_, parent_stop, _), _| *parent_stop > start);
+            results = self._warm_start(start, stop, &split_candidates);
+        }
 
-            // same
-            let cand = split_candidates
-                .clone()
-                .into_iter()
-                .nth(3 * split_candidates.len() / 4)
-                .unwrap();
-            if cand > guesses[guesses.len() - 1] {
-                guesses.push(cand)
-            };
-        } else {
-            guesses.push((3 * start + stop) / 4);
-            guesses.push((start + stop) / 2);
-            guesses.push((start + 3 * stop) / 4);
+        if results.is_empty() {
+            // Don't use first and last guess if stop - start / 4 < delta.
+            for guess in self
+                ._initial_guesses(start, stop, &split_candidates)
+                .iter()
+                .filter(|x| split_candidates.contains(x))
+            {
+                results.push(self._single_find_best_split(start, stop, *guess, &split_candidates));
+            }
         }
+        let n_initial_guesses = results.len();
 
-        // Don't use first and last guess if stop - start / 4 < delta.
-        for guess in guesses.iter().filter(|x| split_candidates.contains(x)) {
-            results.push(self._single_find_best_split(start, stop, *guess, &split_candidates));
+        if control.two_step_search_bracketing {
+            let mut bracketing_results = self._bracketing(start, stop, &split_candidates, &results);
+            results.append(&mut bracketing_results);
         }
 
         let max_gain = results
@@ -101,14 +280,27 @@ where
             .map(|x| x.max_gain().unwrap())
             .reduce(f64::max)
             .unwrap();
-        let best_split = results
+        let mut best_split = results
             .iter()
             .find(|x| x.max_gain().unwrap() >= max_gain)
             .unwrap()
             .best_split()
             .unwrap();
 
-        results.push(self._single_find_best_split(start, stop, best_split, &split_candidates));
+        // Refit at the best split until it no longer changes.
+        for _ in 0..control.two_step_search_n_refinements {
+            let result = self._single_find_best_split(start, stop, best_split, &split_candidates);
+            let converged = result.best_split() == Some(best_split);
+            best_split = result.best_split().unwrap();
+            results.push(result);
+            if converged {
+                break;
+            }
+        }
+
+        if !control.warm_start {
+            self.cache.borrow_mut().clear();
+        }
 
         Ok(OptimizerResult {
             start,
@@ -116,6 +308,7 @@ where
             best_split: results.last().unwrap().best_split().unwrap(),
             max_gain: results.last().unwrap().max_gain().unwrap(),
             gain_results: results,
+            n_initial_guesses,
         })
     }
 
@@ -128,6 +321,8 @@ where
 mod tests {
 
     use super::*;
+    use crate::classifier::RandomForest;
+    use crate::gain::ClassifierGain;
     use crate::testing;
     use rstest::*;
 
@@ -161,7 +356,7 @@ mod tests {
         let control = Control::default().with_minimal_relative_segment_length(0.01);
 
         let gain = testing::ChangeInMean::new(&X_view, &control);
-        let two_step_search = TwoStepSearch { gain };
+        let two_step_search = TwoStepSearch::new(gain);
 
         assert_eq!(
             two_step_search
@@ -189,7 +384,7 @@ mod tests {
         let control =
             Control::default().with_minimal_relative_segment_length(minimal_relative_segment_lengh);
         let gain = testing::ChangeInMean::new(&X_view, &control);
-        let two_step_search = TwoStepSearch { gain };
+        let two_step_search = TwoStepSearch::new(gain);
 
         let results = two_step_search
             .find_best_split(start, stop)
@@ -203,4 +398,129 @@ mod tests {
         guesses.sort();
         assert_eq!(guesses, expected);
     }
+
+    #[rstest]
+    #[case(false, 0)]
+    #[case(true, 4)]
+    fn test_cache(#[case] warm_start: bool, #[case] expected_len: usize) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default().with_warm_start(warm_start);
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let two_step_search = TwoStepSearch::new(gain);
+
+        let result = two_step_search.find_best_split(0, 100).unwrap();
+        // Fits at the guesses 25, 50, 75 and at the best split 80.
+        assert_eq!(result.best_split, 80);
+        assert_eq!(result.gain_results.len(), 4);
+        assert_eq!(two_step_search.cache.borrow().len(), expected_len);
+
+        let cached_result = two_step_search.find_best_split(0, 100).unwrap();
+        assert_eq!(two_step_search.cache.borrow().len(), expected_len);
+        assert_eq!(cached_result.best_split, result.best_split);
+        assert_eq!(cached_result.max_gain, result.max_gain);
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default().with_warm_start(true);
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let two_step_search = TwoStepSearch::new(gain);
+
+        two_step_search.find_best_split(0, 100).unwrap();
+        two_step_search.find_best_split(0, 40).unwrap();
+        two_step_search.find_best_split(40, 100).unwrap();
+        // Fits on [0, 40) can no longer be parents.
+        assert!(two_step_search
+            .cache
+            .borrow()
+            .keys()
+            .all(|(start, stop, _)| (*start, *stop) != (0, 40)));
+    }
+
+    #[rstest]
+    #[case(false, 4, 80)]
+    #[case(true, 3, 80)]
+    fn test_warm_start(
+        #[case] warm_start: bool,
+        #[case] expected_n_results: usize,
+        #[case] expected_best_split: usize,
+    ) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.01)
+            .with_warm_start(warm_start);
+        let classifier = RandomForest::new(&X_view, &control);
+        let gain = ClassifierGain { classifier };
+        let two_step_search = TwoStepSearch::new(gain);
+
+        two_step_search.find_best_split(0, 100).unwrap();
+        let result = two_step_search.find_best_split(25, 100).unwrap();
+
+        // With warm start, all fits of the parent at guesses within (25, 100) are used
+        // as initial guesses, followed by a refit at the best split.
+        assert_eq!(result.gain_results.len(), expected_n_results);
+        assert_eq!(result.n_initial_guesses, expected_n_results - 1);
+        assert_eq!(result.best_split, expected_best_split);
+        assert!(two_step_search.model_selection(&result).is_significant);
+    }
+
+    #[rstest]
+    #[case(1, vec![50])]
+    #[case(3, vec![25, 50, 75])]
+    #[case(4, vec![20, 40, 60, 80])]
+    fn test_n_guesses(#[case] n_guesses: usize, #[case] expected: Vec<usize>) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default().with_two_step_search_n_guesses(n_guesses);
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let two_step_search = TwoStepSearch::new(gain);
+
+        let result = two_step_search.find_best_split(0, 100).unwrap();
+        let guesses: Vec<usize> = result.gain_results[..result.n_initial_guesses]
+            .iter()
+            .map(|x| x.guess().unwrap())
+            .collect();
+        assert_eq!(guesses, expected);
+    }
+
+    #[rstest]
+    #[case(0, 100)]
+    #[case(10, 90)]
+    #[case(30, 100)]
+    fn test_refinements_converge(#[case] start: usize, #[case] stop: usize) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default().with_two_step_search_n_refinements(10);
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let two_step_search = TwoStepSearch::new(gain);
+
+        let result = two_step_search.find_best_split(start, stop).unwrap();
+        assert!(result.gain_results.len() < result.n_initial_guesses + 10);
+        let last = result.gain_results.last().unwrap();
+        assert_eq!(last.guess(), last.best_split());
+        assert_eq!(result.best_split, last.best_split().unwrap());
+    }
+
+    #[rstest]
+    #[case(0, 100, 80)]
+    #[case(0, 60, 25)]
+    fn test_bracketing(#[case] start: usize, #[case] stop: usize, #[case] expected: usize) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.05)
+            .with_two_step_search_bracketing(true);
+        let gain = testing::ChangeInMean::new(&X_view, &control);
+        let two_step_search = TwoStepSearch::new(gain);
+
+        let result = two_step_search.find_best_split(start, stop).unwrap();
+        assert_eq!(result.n_initial_guesses, 3);
+        // Bracketing results lie between the initial guesses and the refinement.
+        assert!(result.gain_results.len() > result.n_initial_guesses + 1);
+        assert_eq!(result.best_split, expected);
+    }
 }
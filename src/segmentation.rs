@@ -1,3 +1,4 @@
+use crate::observer::{Event, Observer, Progress, Signal, Stage};
 use crate::optimizer::OptimizerResult;
 use crate::ModelSelectionResult;
 use crate::Optimizer;
@@ -6,6 +7,8 @@ use rand::{
     rngs::StdRng,
     SeedableRng,
 };
+use std::time::Instant;
+
 pub enum SegmentationType {
     BS,
     WBS,
@@ -15,20 +18,55 @@ pub enum SegmentationType {
 pub struct Segmentation<'a> {
     pub segments: Vec<OptimizerResult>,
     optimizer: &'a dyn Optimizer,
+    observer: Option<&'a mut dyn Observer>,
+    cancelled: bool,
 }
 
 impl<'a> Segmentation<'a> {
     pub fn new(segmentation_type: SegmentationType, optimizer: &'a dyn Optimizer) -> Self {
-        Segmentation {
-            segments: Self::get_segments(optimizer, segmentation_type),
+        Self::new_with_observer(segmentation_type, optimizer, None)
+    }
+
+    /// Create a `Segmentation` that reports events to `observer`.
+    ///
+    /// The observer is already notified while fitting the seeded or wild segments.
+    /// If it cancels, the remaining segments are skipped.
+    pub fn new_with_observer(
+        segmentation_type: SegmentationType,
+        optimizer: &'a dyn Optimizer,
+        observer: Option<&'a mut dyn Observer>,
+    ) -> Self {
+        let mut segmentation = Segmentation {
+            segments: vec![],
             optimizer,
+            observer,
+            cancelled: false,
+        };
+        segmentation.segments = segmentation.get_segments(segmentation_type);
+        segmentation
+    }
+
+    /// Whether the observer cancelled the segmentation.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Pass `event` to the observer, if any. Returns `false` if the segmentation has
+    /// been cancelled.
+    pub fn notify(&mut self, event: &Event) -> bool {
+        if !self.cancelled {
+            if let Some(observer) = self.observer.as_mut() {
+                if observer.notify(event) == Signal::Cancel {
+                    self.cancelled = true;
+                }
+            }
         }
+        !self.cancelled
     }
 
-    fn get_segments(
-        optimizer: &dyn Optimizer,
-        segmentation_type: SegmentationType,
-    ) -> Vec<OptimizerResult> {
+    fn get_segments(&mut self, segmentation_type: SegmentationType) -> Vec<OptimizerResult> {
+        let optimizer = self.optimizer;
+        let started = Instant::now();
         let mut segments = vec![];
         match segmentation_type {
             SegmentationType::BS => (),
@@ -49,6 +87,7 @@ impl<'a> Segmentation<'a> {
                 let mut segment_step: f64;
                 let mut start: usize;
                 let mut stop: usize;
+                let mut intervals = vec![];
                 for k in 1..(n_layers as i32) {
                     alpha_k = optimizer.control().seeded_segments_alpha.powi(k); // (1/alpha)^(k-1)
                     segment_length = (optimizer.n() as f64) * alpha_k; // l_k
@@ -60,25 +99,44 @@ impl<'a> Segmentation<'a> {
                         // start + segment_length > n through floating point errors in
                         // n_segments, e.g. for n = 20'000, alpha_k = 1/sqrt(2), k=6
                         stop = (start + (segment_length as f32).ceil() as usize).min(optimizer.n());
-                        if let Ok(optimizer_result) = optimizer.find_best_split(start, stop) {
-                            segments.push(optimizer_result)
-                        }
+                        intervals.push((start, stop));
+                    }
+                }
+
+                for (idx, (start, stop)) in intervals.iter().enumerate() {
+                    if let Ok(optimizer_result) = optimizer.find_best_split(*start, *stop) {
+                        self.notify(&Event::SegmentEvaluated(&optimizer_result));
+                        segments.push(optimizer_result)
+                    }
+                    let progress =
+                        Progress::new(Stage::Segments, idx + 1, intervals.len(), started);
+                    if !self.notify(&Event::Progress(progress)) {
+                        break;
                     }
                 }
             }
             SegmentationType::WBS => {
                 let mut rng = StdRng::seed_from_u64(optimizer.control().seed);
                 let dist = Uniform::from(0..(optimizer.n() + 1));
+                let number_of_wild_segments = optimizer.control().number_of_wild_segments;
 
                 let mut start: usize;
                 let mut stop: usize;
 
-                while segments.len() < optimizer.control().number_of_wild_segments {
+                while segments.len() < number_of_wild_segments && !self.cancelled {
                     start = dist.sample(&mut rng);
                     stop = dist.sample(&mut rng);
                     if start < stop {
                         if let Ok(optimizer_result) = optimizer.find_best_split(start, stop) {
-                            segments.push(optimizer_result)
+                            self.notify(&Event::SegmentEvaluated(&optimizer_result));
+                            segments.push(optimizer_result);
+                            let progress = Progress::new(
+                                Stage::Segments,
+                                segments.len(),
+                                number_of_wild_segments,
+                                started,
+                            );
+                            self.notify(&Event::Progress(progress));
                         }
                     }
                 }
@@ -93,6 +151,7 @@ impl<'a> Segmentation<'a> {
         match self.optimizer.find_best_split(start, stop) {
             Err(e) => Err(e),
             Ok(optimizer_result) => {
+                self.notify(&Event::SegmentEvaluated(&optimizer_result));
                 let mut idx_opt = self.segments.len();
                 let mut best_gain = optimizer_result.max_gain;
 
@@ -115,8 +174,13 @@ impl<'a> Segmentation<'a> {
         }
     }
 
-    pub fn model_selection(&self, optimizer_result: &OptimizerResult) -> ModelSelectionResult {
-        self.optimizer.model_selection(optimizer_result)
+    pub fn model_selection(&mut self, optimizer_result: &OptimizerResult) -> ModelSelectionResult {
+        let model_selection_result = self.optimizer.model_selection(optimizer_result);
+        self.notify(&Event::ModelSelection(
+            optimizer_result,
+            &model_selection_result,
+        ));
+        model_selection_result
     }
 }
 
@@ -221,4 +285,36 @@ mod tests {
         let result = segmentation.find_best_split(0, 100).unwrap();
         assert_eq!((result.best_split, result.max_gain), expected);
     }
+
+    #[rstest]
+    #[case(SegmentationType::SBS)]
+    #[case(SegmentationType::WBS)]
+    fn test_segments_cancelled(#[case] segmentation_type: SegmentationType) {
+        let control = Control::default()
+            .with_number_of_wild_segments(5)
+            .with_minimal_relative_segment_length(0.2);
+        let optimizer = testing::TrivialOptimizer { control: &control };
+
+        let mut n_evaluated = 0;
+        let mut n_events = 0;
+        let mut observer = |event: &Event| {
+            n_events += 1;
+            if let Event::SegmentEvaluated(_) = event {
+                n_evaluated += 1;
+                if n_evaluated == 3 {
+                    return Signal::Cancel;
+                }
+            }
+            Signal::Continue
+        };
+
+        let segmentation =
+            Segmentation::new_with_observer(segmentation_type, &optimizer, Some(&mut observer));
+        assert!(segmentation.is_cancelled());
+        assert_eq!(segmentation.segments.len(), 3);
+        drop(segmentation);
+        // Two events (segment evaluated and progress) for each of the first two
+        // segments and one for the third segment.
+        assert_eq!(n_events, 5);
+    }
 }
@@ -0,0 +1,457 @@
+//! Simulate piecewise stationary time series with known change points.
+//!
+//! A `Simulation` is a sequence of segments, each with a length and a `Distribution`.
+//! Observations are independent across time, unless `ar_coefficient` is nonzero, in
+//! which case the noise follows an AR(1) process that continues across segments.
+//!
+//! ```
+//! use changeforest::simulate::{self, Distribution, Simulation};
+//! use ndarray::{Array1, Array2};
+//!
+//! // Change in mean at 100 and change in noise distribution at 200.
+//! let simulation = Simulation::new(0)
+//!     .with_segment(100, Distribution::Gaussian { mean: Array1::zeros(2), covariance: Array2::eye(2) })
+//!     .with_segment(100, Distribution::Gaussian { mean: Array1::ones(2), covariance: Array2::eye(2) })
+//!     .with_segment(100, Distribution::Laplace { mean: Array1::ones(2), covariance: Array2::eye(2) })
+//!     .with_ar_coefficient(0.3);
+//! assert_eq!(simulation.split_points(), vec![100, 200]);
+//! assert_eq!(simulation.sample().shape(), &[300, 2]);
+//!
+//! let simulation = simulate::mean_shift(300, &[100, 200], 5, 1., 0);
+//! ```
+use ndarray::{Array1, Array2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Gamma, StandardNormal};
+
+#[derive(Clone, Debug)]
+/// Distribution of the observations within a segment.
+pub enum Distribution {
+    /// Multivariate Gaussian with `mean` and `covariance`.
+    Gaussian {
+        mean: Array1<f64>,
+        covariance: Array2<f64>,
+    },
+    /// `mean + L e`, where `L L^T = covariance` and `e` has independent Laplace
+    /// distributed entries with unit variance.
+    Laplace {
+        mean: Array1<f64>,
+        covariance: Array2<f64>,
+    },
+    /// Dirichlet distribution with concentration parameters `alpha`. Observations lie
+    /// on the simplex. Not affected by `ar_coefficient`.
+    Dirichlet { alpha: Array1<f64> },
+}
+
+impl Distribution {
+    fn n_features(&self) -> usize {
+        match self {
+            Distribution::Gaussian { mean, .. } | Distribution::Laplace { mean, .. } => mean.len(),
+            Distribution::Dirichlet { alpha } => alpha.len(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Piecewise stationary time series with known change points.
+pub struct Simulation {
+    /// Lengths and distributions of the segments.
+    pub segments: Vec<(usize, Distribution)>,
+    /// Coefficient of the AR(1) noise process. Observations are independent if zero.
+    pub ar_coefficient: f64,
+    /// Seed of the random number generator.
+    pub seed: u64,
+}
+
+impl Simulation {
+    pub fn new(seed: u64) -> Simulation {
+        Simulation {
+            segments: vec![],
+            ar_coefficient: 0.,
+            seed,
+        }
+    }
+
+    /// Append a segment of `length` observations drawn from `distribution`.
+    pub fn with_segment(mut self, length: usize, distribution: Distribution) -> Self {
+        if length == 0 {
+            panic!("length must be positive.");
+        }
+
+        if let Distribution::Gaussian { mean, covariance }
+        | Distribution::Laplace { mean, covariance } = &distribution
+        {
+            if covariance.shape() != [mean.len(), mean.len()] {
+                panic!(
+                    "covariance must have shape ({0}, {0}). Got {1:?}.",
+                    mean.len(),
+                    covariance.shape()
+                );
+            }
+        }
+
+        if let Distribution::Dirichlet { alpha } = &distribution {
+            if alpha.iter().any(|&a| a <= 0. || !a.is_finite()) {
+                panic!("alpha must be positive. Got {}.", alpha);
+            }
+        }
+
+        if let Some((_, first)) = self.segments.first() {
+            if first.n_features() != distribution.n_features() {
+                panic!(
+                    "All segments must have the same number of features. Got {} and {}.",
+                    first.n_features(),
+                    distribution.n_features()
+                );
+            }
+        }
+
+        self.segments.push((length, distribution));
+        self
+    }
+
+    /// Set the coefficient of the AR(1) noise process.
+    ///
+    /// The standardized noise follows `e_t = phi e_{t-1} + sqrt(1 - phi^2) z_t`, such
+    /// that its variance does not depend on `phi`. The noise process continues across
+    /// segments.
+    pub fn with_ar_coefficient(mut self, ar_coefficient: f64) -> Self {
+        if !(-1. < ar_coefficient && ar_coefficient < 1.) {
+            panic!("ar_coefficient must be in (-1, 1). Got {}.", ar_coefficient);
+        }
+
+        self.ar_coefficient = ar_coefficient;
+        self
+    }
+
+    /// Total number of observations.
+    pub fn n(&self) -> usize {
+        self.segments.iter().map(|(length, _)| length).sum()
+    }
+
+    /// True change points, i.e., the starts of all segments but the first.
+    pub fn split_points(&self) -> Vec<usize> {
+        self.segments
+            .iter()
+            .scan(0, |start, (length, _)| {
+                *start += length;
+                Some(*start)
+            })
+            .take(self.segments.len().saturating_sub(1))
+            .collect()
+    }
+
+    /// Draw a time series of shape `(n, d)`.
+    pub fn sample(&self) -> Array2<f64> {
+        let d = self
+            .segments
+            .first()
+            .map_or(0, |(_, dist)| dist.n_features());
+        let mut X = Array2::<f64>::zeros((self.n(), d));
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let phi = self.ar_coefficient;
+        let innovation_scale = (1. - phi * phi).sqrt();
+        let mut noise = Array1::<f64>::zeros(d);
+        let mut is_first = true;
+
+        let mut start = 0;
+        for (length, distribution) in self.segments.iter() {
+            match distribution {
+                Distribution::Gaussian { mean, covariance }
+                | Distribution::Laplace { mean, covariance } => {
+                    let cholesky = cholesky(covariance);
+                    let is_gaussian = matches!(distribution, Distribution::Gaussian { .. });
+
+                    for t in start..(start + length) {
+                        let innovations = Array1::from_shape_fn(d, |_| {
+                            if is_gaussian {
+                                rng.sample::<f64, _>(StandardNormal)
+                            } else {
+                                laplace(&mut rng)
+                            }
+                        });
+
+                        if is_first {
+                            noise = innovations;
+                            is_first = false;
+                        } else {
+                            noise = phi * &noise + innovation_scale * &innovations;
+                        }
+
+                        X.row_mut(t).assign(&(mean + &cholesky.dot(&noise)));
+                    }
+                }
+                Distribution::Dirichlet { alpha } => {
+                    let gammas: Vec<Gamma<f64>> =
+                        alpha.iter().map(|&a| Gamma::new(a, 1.).unwrap()).collect();
+
+                    for t in start..(start + length) {
+                        let mut row = X.row_mut(t);
+                        for (value, gamma) in row.iter_mut().zip(gammas.iter()) {
+                            *value = rng.sample(gamma);
+                        }
+                        let sum = row.sum();
+                        row.mapv_inplace(|x| x / sum);
+                    }
+                }
+            }
+            start += length;
+        }
+
+        X
+    }
+}
+
+/// Changes in the mean of all `d` features by `delta` at `split_points`.
+///
+/// The mean alternates between `0` and `delta`. The noise is standard Gaussian.
+pub fn mean_shift(n: usize, split_points: &[usize], d: usize, delta: f64, seed: u64) -> Simulation {
+    piecewise(n, split_points, seed, |idx| Distribution::Gaussian {
+        mean: Array1::from_elem(d, (idx % 2) as f64 * delta),
+        covariance: Array2::eye(d),
+    })
+}
+
+/// Changes in the correlation of `d` features at `split_points`.
+///
+/// The covariance alternates between the identity and the equicorrelation matrix with
+/// unit variances and correlation `rho`. The mean is zero.
+pub fn covariance_change(
+    n: usize,
+    split_points: &[usize],
+    d: usize,
+    rho: f64,
+    seed: u64,
+) -> Simulation {
+    piecewise(n, split_points, seed, |idx| {
+        let correlation = (idx % 2) as f64 * rho;
+        Distribution::Gaussian {
+            mean: Array1::zeros(d),
+            covariance: Array2::from_shape_fn(
+                (d, d),
+                |(i, j)| {
+                    if i == j {
+                        1.
+                    } else {
+                        correlation
+                    }
+                },
+            ),
+        }
+    })
+}
+
+/// Changes in the noise distribution of `d` features at `split_points`.
+///
+/// The noise alternates between standard Gaussian and Laplace with unit variance. Mean
+/// and covariance do not change.
+pub fn distribution_switch(n: usize, split_points: &[usize], d: usize, seed: u64) -> Simulation {
+    piecewise(n, split_points, seed, |idx| {
+        let (mean, covariance) = (Array1::zeros(d), Array2::eye(d));
+        if idx % 2 == 0 {
+            Distribution::Gaussian { mean, covariance }
+        } else {
+            Distribution::Laplace { mean, covariance }
+        }
+    })
+}
+
+/// Changes in the concentration parameters of Dirichlet distributed observations.
+///
+/// Segment `idx` has concentration parameters `alphas[idx % alphas.len()]`.
+pub fn dirichlet(
+    n: usize,
+    split_points: &[usize],
+    alphas: &[Array1<f64>],
+    seed: u64,
+) -> Simulation {
+    if alphas.is_empty() {
+        panic!("alphas must not be empty.");
+    }
+
+    piecewise(n, split_points, seed, |idx| Distribution::Dirichlet {
+        alpha: alphas[idx % alphas.len()].clone(),
+    })
+}
+
+fn piecewise<F>(n: usize, split_points: &[usize], seed: u64, distribution: F) -> Simulation
+where
+    F: Fn(usize) -> Distribution,
+{
+    let mut boundaries = vec![0];
+    boundaries.extend_from_slice(split_points);
+    boundaries.push(n);
+
+    if boundaries.windows(2).any(|window| window[0] >= window[1]) {
+        panic!(
+            "split_points must be strictly increasing and in (0, n = {}). Got {:?}.",
+            n, split_points
+        );
+    }
+
+    boundaries
+        .windows(2)
+        .enumerate()
+        .fold(Simulation::new(seed), |simulation, (idx, window)| {
+            simulation.with_segment(window[1] - window[0], distribution(idx))
+        })
+}
+
+/// Laplace distributed random variable with unit variance.
+fn laplace<R: Rng>(rng: &mut R) -> f64 {
+    let u: f64 = rng.gen_range(-0.5..0.5);
+    -u.signum() * (1. - 2. * u.abs()).ln() / 2_f64.sqrt()
+}
+
+/// Lower triangular `L` with `L L^T = covariance`.
+fn cholesky(covariance: &Array2<f64>) -> Array2<f64> {
+    let d = covariance.nrows();
+    let mut L = Array2::<f64>::zeros((d, d));
+
+    for i in 0..d {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| L[[i, k]] * L[[j, k]]).sum();
+            if i == j {
+                let value = covariance[[i, i]] - sum;
+                if value <= 0. {
+                    panic!("covariance must be positive definite.");
+                }
+                L[[i, i]] = value.sqrt();
+            } else {
+                L[[i, j]] = (covariance[[i, j]] - sum) / L[[j, j]];
+            }
+        }
+    }
+
+    L
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::*;
+    use ndarray::{arr2, s, Axis};
+    use rstest::*;
+
+    #[test]
+    fn test_cholesky() {
+        let covariance = arr2(&[[4., 2., 0.], [2., 2., 1.], [0., 1., 5.]]);
+        let L = cholesky(&covariance);
+        assert!(L.dot(&L.t()).abs_diff_eq(&covariance, 1e-12));
+        assert_eq!(L[[0, 1]], 0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "positive definite")]
+    fn test_cholesky_not_positive_definite() {
+        cholesky(&arr2(&[[1., 2.], [2., 1.]]));
+    }
+
+    #[test]
+    fn test_simulation_split_points() {
+        let simulation = mean_shift(100, &[20, 50], 3, 1., 0);
+        assert_eq!(simulation.n(), 100);
+        assert_eq!(simulation.split_points(), vec![20, 50]);
+        assert_eq!(simulation.sample().shape(), &[100, 3]);
+        assert!(Simulation::new(0).split_points().is_empty());
+    }
+
+    #[test]
+    fn test_simulation_seeded() {
+        let first = mean_shift(100, &[50], 2, 1., 7).sample();
+        assert_eq!(first, mean_shift(100, &[50], 2, 1., 7).sample());
+        assert_ne!(first, mean_shift(100, &[50], 2, 1., 8).sample());
+    }
+
+    #[test]
+    fn test_mean_shift() {
+        let X = mean_shift(20000, &[10000], 2, 3., 0).sample();
+        let left = X.slice(s![..10000, ..]).mean_axis(Axis(0)).unwrap();
+        let right = X.slice(s![10000.., ..]).mean_axis(Axis(0)).unwrap();
+        assert!(left.iter().all(|x| x.abs() < 0.05));
+        assert!(right.iter().all(|x| (x - 3.).abs() < 0.05));
+    }
+
+    #[test]
+    fn test_covariance_change() {
+        let X = covariance_change(20000, &[10000], 2, 0.8, 0).sample();
+        let correlation =
+            |X: ndarray::ArrayView2<'_, f64>| (&X.column(0) * &X.column(1)).mean().unwrap();
+        assert!(correlation(X.slice(s![..10000, ..])).abs() < 0.05);
+        assert_approx_eq!(correlation(X.slice(s![10000.., ..])), 0.8, 0.05);
+    }
+
+    #[test]
+    fn test_distribution_switch() {
+        let X = distribution_switch(40000, &[20000], 1, 0).sample();
+        // Both have unit variance. The kurtosis is 3 for Gaussian and 6 for Laplace.
+        let moment =
+            |X: ndarray::ArrayView2<'_, f64>, k: i32| X.mapv(|x| x.powi(k)).mean().unwrap();
+        assert_approx_eq!(moment(X.slice(s![..20000, ..]), 2), 1., 0.05);
+        assert_approx_eq!(moment(X.slice(s![20000.., ..]), 2), 1., 0.05);
+        assert_approx_eq!(moment(X.slice(s![..20000, ..]), 4), 3., 0.3);
+        assert_approx_eq!(moment(X.slice(s![20000.., ..]), 4), 6., 0.6);
+    }
+
+    #[test]
+    fn test_dirichlet() {
+        let alphas = [
+            Array1::from(vec![1., 1., 1.]),
+            Array1::from(vec![8., 1., 1.]),
+        ];
+        let X = dirichlet(20000, &[10000], &alphas, 0).sample();
+        assert!(X.iter().all(|&x| (0. ..=1.).contains(&x)));
+        assert!(X.sum_axis(Axis(1)).iter().all(|&x| (x - 1.).abs() < 1e-12));
+
+        let right = X.slice(s![10000.., ..]).mean_axis(Axis(0)).unwrap();
+        assert_approx_eq!(right[0], 0.8, 0.01);
+    }
+
+    #[rstest]
+    #[case(0.)]
+    #[case(0.5)]
+    #[case(-0.7)]
+    fn test_ar_coefficient(#[case] phi: f64) {
+        let X = mean_shift(20000, &[], 1, 0., 0)
+            .with_ar_coefficient(phi)
+            .sample();
+        let x = X.column(0);
+        let lag_one = (&x.slice(s![1..]) * &x.slice(s![..-1])).mean().unwrap();
+        assert_approx_eq!(x.mapv(|x| x * x).mean().unwrap(), 1., 0.05);
+        assert_approx_eq!(lag_one, phi, 0.05);
+    }
+
+    #[rstest]
+    #[case(1.)]
+    #[case(-1.)]
+    #[case(f64::NAN)]
+    #[should_panic(expected = "ar_coefficient must be in (-1, 1)")]
+    fn test_ar_coefficient_invalid(#[case] phi: f64) {
+        Simulation::new(0).with_ar_coefficient(phi);
+    }
+
+    #[test]
+    #[should_panic(expected = "same number of features")]
+    fn test_simulation_inconsistent_features() {
+        Simulation::new(0)
+            .with_segment(
+                10,
+                Distribution::Dirichlet {
+                    alpha: Array1::ones(3),
+                },
+            )
+            .with_segment(
+                10,
+                Distribution::Dirichlet {
+                    alpha: Array1::ones(2),
+                },
+            );
+    }
+
+    #[test]
+    #[should_panic(expected = "split_points must be strictly increasing")]
+    fn test_simulation_invalid_split_points() {
+        mean_shift(100, &[50, 20], 1, 1., 0);
+    }
+}
@@ -0,0 +1,421 @@
+//! Calibration and power studies of change point detection methods.
+//!
+//! A `Study` draws replications from each `Simulation` and runs
+//! `wrapper::changeforest` for each method and segmentation type. For simulations
+//! without change points, the share of replications with at least one detected change
+//! point is the empirical type-I error of the model selection. For simulations with
+//! change points, the power is the share of true change points detected within
+//! `margin` observations.
+//!
+//! ```
+//! use changeforest::simulate;
+//! use changeforest::study::Study;
+//!
+//! let result = Study::new(5)
+//!     .with_simulation("null", simulate::mean_shift(200, &[], 2, 0., 0))
+//!     .with_simulation("mean_shift", simulate::mean_shift(200, &[100], 2, 2., 0))
+//!     .with_methods(&["change_in_mean"])
+//!     .with_segmentation_types(&["bs"])
+//!     .run();
+//!
+//! println!("{}", result.to_csv());
+//! ```
+use crate::metrics;
+use crate::simulate::Simulation;
+use crate::wrapper::changeforest;
+use crate::Control;
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct Study {
+    /// Named simulations to draw data from.
+    pub simulations: Vec<(String, Simulation)>,
+    /// Methods passed to `wrapper::changeforest`.
+    pub methods: Vec<String>,
+    /// Segmentation types passed to `wrapper::changeforest`.
+    pub segmentation_types: Vec<String>,
+    /// Number of replications per simulation, method and segmentation type.
+    pub n_replications: usize,
+    /// Tolerance for a detected change point to count as a true positive.
+    pub margin: usize,
+    /// Hyperparameters. In replication `r`, `control.seed + r` is used as seed.
+    pub control: Control,
+}
+
+impl Study {
+    pub fn new(n_replications: usize) -> Study {
+        Study {
+            simulations: vec![],
+            methods: vec!["random_forest".to_string()],
+            segmentation_types: vec!["bs".to_string()],
+            n_replications,
+            margin: 5,
+            control: Control::default(),
+        }
+    }
+
+    pub fn with_simulation(mut self, name: &str, simulation: Simulation) -> Self {
+        self.simulations.push((name.to_string(), simulation));
+        self
+    }
+
+    pub fn with_methods(mut self, methods: &[&str]) -> Self {
+        self.methods = methods.iter().map(|method| method.to_string()).collect();
+        self
+    }
+
+    pub fn with_segmentation_types(mut self, segmentation_types: &[&str]) -> Self {
+        self.segmentation_types = segmentation_types
+            .iter()
+            .map(|segmentation_type| segmentation_type.to_string())
+            .collect();
+        self
+    }
+
+    pub fn with_margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_control(mut self, control: Control) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Run all replications.
+    ///
+    /// In replication `r`, data is drawn from the simulation with seed
+    /// `simulation.seed + r`. The same data is used for all methods and segmentation
+    /// types.
+    pub fn run(&self) -> StudyResult {
+        let mut replications = vec![];
+
+        for (name, simulation) in self.simulations.iter() {
+            for replication in 0..self.n_replications {
+                let mut simulation = simulation.clone();
+                simulation.seed += replication as u64;
+                let X = simulation.sample();
+                let control = self
+                    .control
+                    .clone()
+                    .with_seed(self.control.seed + replication as u64);
+
+                for method in self.methods.iter() {
+                    for segmentation_type in self.segmentation_types.iter() {
+                        let now = Instant::now();
+                        let result = changeforest(&X.view(), method, segmentation_type, &control);
+                        let runtime = now.elapsed().as_secs_f64();
+
+                        replications.push(Replication {
+                            simulation: name.clone(),
+                            method: method.clone(),
+                            segmentation_type: segmentation_type.clone(),
+                            replication,
+                            true_split_points: simulation.split_points(),
+                            split_points: result.split_points(),
+                            runtime,
+                        });
+                    }
+                }
+            }
+        }
+
+        StudyResult {
+            replications,
+            margin: self.margin,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Outcome of a single replication.
+pub struct Replication {
+    pub simulation: String,
+    pub method: String,
+    pub segmentation_type: String,
+    pub replication: usize,
+    pub true_split_points: Vec<usize>,
+    pub split_points: Vec<usize>,
+    /// Runtime in seconds.
+    pub runtime: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Aggregate over the replications of a simulation, method and segmentation type.
+pub struct Summary {
+    pub simulation: String,
+    pub method: String,
+    pub segmentation_type: String,
+    pub n_replications: usize,
+    /// Share of replications with at least one detected change point. Only for
+    /// simulations without change points.
+    pub type_i_error: Option<f64>,
+    /// Average share of true change points detected within `margin`. Only for
+    /// simulations with change points.
+    pub power: Option<f64>,
+    /// Average Hausdorff distance between true and detected change points, over
+    /// replications with at least one detected change point. Only for simulations
+    /// with change points.
+    pub localization_error: Option<f64>,
+    /// Average runtime in seconds.
+    pub runtime: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct StudyResult {
+    pub replications: Vec<Replication>,
+    pub margin: usize,
+}
+
+impl StudyResult {
+    /// Aggregate replications by simulation, method and segmentation type, in the
+    /// order in which they were run.
+    pub fn summaries(&self) -> Vec<Summary> {
+        let mut keys: Vec<(&str, &str, &str)> = vec![];
+        for replication in self.replications.iter() {
+            let key = (
+                replication.simulation.as_str(),
+                replication.method.as_str(),
+                replication.segmentation_type.as_str(),
+            );
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        keys.into_iter()
+            .map(|(simulation, method, segmentation_type)| {
+                let replications: Vec<&Replication> = self
+                    .replications
+                    .iter()
+                    .filter(|replication| {
+                        replication.simulation == simulation
+                            && replication.method == method
+                            && replication.segmentation_type == segmentation_type
+                    })
+                    .collect();
+                let n_replications = replications.len();
+                let is_null = replications[0].true_split_points.is_empty();
+
+                let mean = |values: Vec<f64>| {
+                    if values.is_empty() {
+                        None
+                    } else {
+                        Some(values.iter().sum::<f64>() / values.len() as f64)
+                    }
+                };
+
+                let type_i_error = if is_null {
+                    mean(
+                        replications
+                            .iter()
+                            .map(|r| (!r.split_points.is_empty()) as usize as f64)
+                            .collect(),
+                    )
+                } else {
+                    None
+                };
+
+                let (power, localization_error) = if is_null {
+                    (None, None)
+                } else {
+                    let power = mean(
+                        replications
+                            .iter()
+                            .map(|r| {
+                                metrics::precision_recall(
+                                    &r.true_split_points,
+                                    &r.split_points,
+                                    self.margin,
+                                )
+                                .1
+                            })
+                            .collect(),
+                    );
+                    let localization_error = mean(
+                        replications
+                            .iter()
+                            .filter(|r| !r.split_points.is_empty())
+                            .map(|r| {
+                                metrics::hausdorff_distance(&r.true_split_points, &r.split_points)
+                            })
+                            .collect(),
+                    );
+                    (power, localization_error)
+                };
+
+                Summary {
+                    simulation: simulation.to_string(),
+                    method: method.to_string(),
+                    segmentation_type: segmentation_type.to_string(),
+                    n_replications,
+                    type_i_error,
+                    power,
+                    localization_error,
+                    runtime: mean(replications.iter().map(|r| r.runtime).collect()).unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    /// Summaries as CSV with a header. Missing values are empty.
+    pub fn to_csv(&self) -> String {
+        let format_option = |value: Option<f64>| value.map_or(String::new(), |x| x.to_string());
+
+        let mut csv = String::from(
+            "simulation,method,segmentation_type,n_replications,type_i_error,power,localization_error,runtime\n",
+        );
+        for summary in self.summaries() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                summary.simulation,
+                summary.method,
+                summary.segmentation_type,
+                summary.n_replications,
+                format_option(summary.type_i_error),
+                format_option(summary.power),
+                format_option(summary.localization_error),
+                summary.runtime
+            ));
+        }
+        csv
+    }
+
+    /// Summaries as a JSON array of objects. Missing values are `null`.
+    pub fn to_json(&self) -> String {
+        let format_option =
+            |value: Option<f64>| value.map_or("null".to_string(), |x| x.to_string());
+
+        let objects: Vec<String> = self
+            .summaries()
+            .iter()
+            .map(|summary| {
+                format!(
+                    "{{\"simulation\":{},\"method\":{},\"segmentation_type\":{},\"n_replications\":{},\"type_i_error\":{},\"power\":{},\"localization_error\":{},\"runtime\":{}}}",
+                    json_string(&summary.simulation),
+                    json_string(&summary.method),
+                    json_string(&summary.segmentation_type),
+                    summary.n_replications,
+                    format_option(summary.type_i_error),
+                    format_option(summary.power),
+                    format_option(summary.localization_error),
+                    summary.runtime
+                )
+            })
+            .collect();
+        format!("[{}]", objects.join(","))
+    }
+}
+
+fn json_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulate;
+
+    fn replication(
+        simulation: &str,
+        true_split_points: Vec<usize>,
+        split_points: Vec<usize>,
+    ) -> Replication {
+        Replication {
+            simulation: simulation.to_string(),
+            method: "change_in_mean".to_string(),
+            segmentation_type: "bs".to_string(),
+            replication: 0,
+            true_split_points,
+            split_points,
+            runtime: 1.,
+        }
+    }
+
+    #[test]
+    fn test_study_summaries() {
+        let result = StudyResult {
+            replications: vec![
+                replication("null", vec![], vec![]),
+                replication("null", vec![], vec![]),
+                replication("null", vec![], vec![30]),
+                replication("null", vec![], vec![]),
+                replication("shift", vec![50, 100], vec![52, 100]),
+                replication("shift", vec![50, 100], vec![70]),
+                replication("shift", vec![50, 100], vec![]),
+            ],
+            margin: 5,
+        };
+
+        let summaries = result.summaries();
+        assert_eq!(summaries.len(), 2);
+
+        assert_eq!(summaries[0].simulation, "null");
+        assert_eq!(summaries[0].n_replications, 4);
+        assert_eq!(summaries[0].type_i_error, Some(0.25));
+        assert_eq!(summaries[0].power, None);
+        assert_eq!(summaries[0].localization_error, None);
+
+        assert_eq!(summaries[1].simulation, "shift");
+        assert_eq!(summaries[1].n_replications, 3);
+        assert_eq!(summaries[1].type_i_error, None);
+        assert_eq!(summaries[1].power, Some(1. / 3.));
+        // Hausdorff distances 2 and 30. The replication without detections is ignored.
+        assert_eq!(summaries[1].localization_error, Some(16.));
+        assert_eq!(summaries[1].runtime, 1.);
+    }
+
+    #[test]
+    fn test_study_output() {
+        let result = StudyResult {
+            replications: vec![
+                replication("null", vec![], vec![]),
+                replication("sh\"ift", vec![50], vec![50]),
+            ],
+            margin: 5,
+        };
+
+        assert_eq!(
+            result.to_csv(),
+            "simulation,method,segmentation_type,n_replications,type_i_error,power,localization_error,runtime\n\
+             null,change_in_mean,bs,1,0,,,1\n\
+             sh\"ift,change_in_mean,bs,1,,1,0,1\n"
+        );
+        assert_eq!(
+            result.to_json(),
+            "[{\"simulation\":\"null\",\"method\":\"change_in_mean\",\"segmentation_type\":\"bs\",\"n_replications\":1,\"type_i_error\":0,\"power\":null,\"localization_error\":null,\"runtime\":1},\
+             {\"simulation\":\"sh\\\"ift\",\"method\":\"change_in_mean\",\"segmentation_type\":\"bs\",\"n_replications\":1,\"type_i_error\":null,\"power\":1,\"localization_error\":0,\"runtime\":1}]"
+        );
+    }
+
+    #[test]
+    fn test_study_run() {
+        let result = Study::new(3)
+            .with_simulation("null", simulate::mean_shift(200, &[], 2, 0., 0))
+            .with_simulation("mean_shift", simulate::mean_shift(200, &[100], 2, 3., 0))
+            .with_methods(&["change_in_mean", "knn"])
+            .with_segmentation_types(&["bs", "sbs"])
+            .run();
+
+        assert_eq!(result.replications.len(), 2 * 3 * 2 * 2);
+        // Replications draw different data.
+        assert_eq!(result.replications[0].replication, 0);
+        assert_eq!(result.replications[4].replication, 1);
+
+        let summaries = result.summaries();
+        assert_eq!(summaries.len(), 2 * 2 * 2);
+        for summary in summaries.iter() {
+            assert_eq!(summary.n_replications, 3);
+            assert!(summary.runtime > 0.);
+            if summary.segmentation_type != "bs" {
+                continue;
+            }
+            if summary.simulation == "null" {
+                assert_eq!(summary.type_i_error, Some(0.));
+            } else {
+                assert_eq!(summary.power, Some(1.));
+                assert_eq!(summary.localization_error, Some(0.));
+            }
+        }
+    }
+}
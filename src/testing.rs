@@ -121,6 +121,7 @@ impl<'a> Optimizer for TrivialOptimizer<'a> {
             best_split: (3 * start + stop) / 4,
             max_gain: ((stop - start) * (start + 10)) as f64,
             gain_results: vec![],
+            n_initial_guesses: 0,
         })
     }
 
@@ -188,7 +189,7 @@ impl<'a> Classifier for RandomClassifier<'a> {
         );
         predictions.slice_mut(s![..(guess - start)]).assign(&left);
         predictions.slice_mut(s![(guess - start)..]).assign(&right);
-        predictions.mapv_inplace(|x| f64::min(f64::max(0., x), 1.));
+        predictions.mapv_inplace(|x| x.clamp(0., 1.));
         predictions
     }
 
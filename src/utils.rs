@@ -1,19 +1,540 @@
-pub fn log_eta(x: f64) -> f64 {
-    // 1e-6 ~ 0.00247, 1 - 1e-6 ~ 0.99752
-    // log_eta(1) = 0
-    (0.0024787521766663585 + 0.9975212478233336 * x).ln()
+use crate::Calibration;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+
+/// Logarithm of `x` shrunk towards one, `ln(eta + (1 - eta) * x)`.
+///
+/// `log_eta(1, eta) = 0` and `log_eta(0, eta) = ln(eta)`. See
+/// `Control.likelihood_shrinkage`.
+pub fn log_eta(x: f64, eta: f64) -> f64 {
+    (eta + (1. - eta) * x).ln()
+}
+
+/// Recalibrate out-of-sample probabilities `predictions` of observations having label
+/// `1`. The first `split` observations have label `0`, the remaining label `1`.
+///
+/// Calibrations are relative to the prediction of an uninformative leave-one-out
+/// classifier, the share of other observations with label `1`. This is the prediction
+/// for which `Classifier::full_likelihood` results in a log-likelihood ratio of zero.
+/// Fitted calibrations are cross-fitted on observations with even and odd indices.
+pub fn calibrate(
+    predictions: &Array1<f64>,
+    split: usize,
+    calibration: &Calibration,
+) -> Array1<f64> {
+    let n = predictions.len();
+    if matches!(calibration, Calibration::None) || n < 3 {
+        return predictions.clone();
+    }
+
+    let labels = Array1::from_shape_fn(n, |idx| (idx >= split) as usize as f64);
+    // Logits of the uninformative leave-one-out predictions.
+    let n_ones = (n - split) as f64;
+    let centers = labels.mapv(|label| logit((n_ones - label) / (n - 1) as f64));
+
+    if let Calibration::Temperature(Some(temperature)) = calibration {
+        return Array1::from_shape_fn(n, |idx| {
+            sigmoid((logit(predictions[idx]) - centers[idx]) / temperature + centers[idx])
+        });
+    }
+
+    let mut calibrated = predictions.clone();
+    for fold in 0..2 {
+        let (train, test): (Vec<usize>, Vec<usize>) = (0..n).partition(|idx| idx % 2 != fold);
+        let select = |values: &Array1<f64>| -> Array1<f64> {
+            train.iter().map(|&idx| values[idx]).collect()
+        };
+        let (train_predictions, train_labels) = (select(predictions), select(&labels));
+
+        match calibration {
+            Calibration::Isotonic => {
+                let (x, y) = isotonic_regression(
+                    train_predictions.as_slice().unwrap(),
+                    train_labels.as_slice().unwrap(),
+                );
+                // The isotonic regression estimates the probability of label `1` relative
+                // to the share of label `1` in the training fold.
+                let base_rate = logit(train_labels.mean().unwrap());
+                for idx in test {
+                    // Piecewise constant interpolation.
+                    let position = x.partition_point(|&value| value <= predictions[idx]);
+                    let fitted = y[position.saturating_sub(1)];
+                    calibrated[idx] = sigmoid(logit(fitted) - base_rate + centers[idx]);
+                }
+            }
+            _ => {
+                let temperature = fit_temperature(
+                    &train_predictions.view(),
+                    &train_labels.view(),
+                    &select(&centers).view(),
+                );
+                for idx in test {
+                    calibrated[idx] = sigmoid(
+                        (logit(predictions[idx]) - centers[idx]) / temperature + centers[idx],
+                    );
+                }
+            }
+        }
+    }
+    calibrated
+}
+
+fn logit(p: f64) -> f64 {
+    let p = p.clamp(1e-12, 1. - 1e-12);
+    (p / (1. - p)).ln()
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1. / (1. + (-x).exp())
+}
+
+/// Temperature minimizing the log loss of
+/// `sigmoid((logit(predictions) - centers) / temperature + centers)`.
+///
+/// `centers` are logits that are not affected by the temperature. The log loss is
+/// convex in the inverse temperature, which is found by golden-section search in
+/// `[0, 100]`.
+pub fn fit_temperature(
+    predictions: &ArrayView1<'_, f64>,
+    labels: &ArrayView1<'_, f64>,
+    centers: &ArrayView1<'_, f64>,
+) -> f64 {
+    let logits = predictions.mapv(logit) - centers;
+    let log_loss = |inverse_temperature: f64| -> f64 {
+        logits
+            .iter()
+            .zip(centers.iter())
+            .zip(labels.iter())
+            .map(|((&logit, &center), &label)| {
+                // -ln(sigmoid(z)) = ln(1 + exp(-z)), computed stably.
+                let z = logit * inverse_temperature + center;
+                let z = if label == 1. { z } else { -z };
+                (-z).max(0.) + (-z.abs()).exp().ln_1p()
+            })
+            .sum()
+    };
+
+    let ratio = (5_f64.sqrt() - 1.) / 2.;
+    let (mut low, mut high) = (0., 100.);
+    for _ in 0..100 {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if log_loss(left) <= log_loss(right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+
+    let inverse_temperature: f64 = (low + high) / 2.;
+    1. / inverse_temperature.max(1e-12)
+}
+
+/// Isotonic (non-decreasing) regression of `y` on `x` by pool adjacent violators.
+///
+/// Returns the sorted `x` and the fitted values at the sorted `x`.
+pub fn isotonic_regression(x: &[f64], y: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut order: Vec<usize> = (0..x.len()).collect();
+    order.sort_by(|&a, &b| x[a].total_cmp(&x[b]));
+
+    // Blocks of (sum of values, number of values).
+    let mut blocks: Vec<(f64, usize)> = Vec::with_capacity(x.len());
+    for &idx in order.iter() {
+        blocks.push((y[idx], 1));
+        while blocks.len() > 1 {
+            let (sum, count) = blocks[blocks.len() - 1];
+            let (previous_sum, previous_count) = blocks[blocks.len() - 2];
+            if previous_sum / previous_count as f64 > sum / count as f64 {
+                blocks.pop();
+                let last = blocks.len() - 1;
+                blocks[last] = (previous_sum + sum, previous_count + count);
+            } else {
+                break;
+            }
+        }
+    }
+
+    let fitted = blocks
+        .iter()
+        .flat_map(|&(sum, count)| std::iter::repeat_n(sum / count as f64, count))
+        .collect();
+    (order.iter().map(|&idx| x[idx]).collect(), fitted)
+}
+
+/// Median of `values`. Reorders `values`. Returns `f64::NAN` if `values` is empty.
+///
+/// `f64::NAN` entries are sorted after all other values, see `f64::total_cmp`.
+pub fn median(values: &mut [f64]) -> f64 {
+    let n = values.len();
+    if n == 0 {
+        return f64::NAN;
+    }
+
+    values.sort_unstable_by(|a, b| a.total_cmp(b));
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.
+    }
+}
+
+/// Robust estimate of the noise level of each column of `X`.
+///
+/// Uses the median absolute deviation (MAD) of first differences. This is robust to
+/// (few) mean shifts. For Gaussian noise with standard deviation `sigma`, the first
+/// differences have standard deviation `sqrt(2) * sigma`, and `1.4826 * MAD` is a
+/// consistent estimate of the standard deviation. Columns with an estimate of zero,
+/// e.g. constant columns, get a scale of one.
+pub fn robust_scale(X: &ArrayView2<'_, f64>) -> Array1<f64> {
+    let n = X.nrows();
+    let mut scale = Array1::<f64>::ones(X.ncols());
+
+    if n < 2 {
+        return scale;
+    }
+
+    for (idx, column) in X.axis_iter(Axis(1)).enumerate() {
+        let mut differences: Vec<f64> = (1..n).map(|i| column[i] - column[i - 1]).collect();
+        let center = median(&mut differences);
+        let mut deviations: Vec<f64> = differences.iter().map(|x| (x - center).abs()).collect();
+        let value = 1.4826 * median(&mut deviations) / std::f64::consts::SQRT_2;
+        if value > 0. {
+            scale[idx] = value;
+        }
+    }
+
+    scale
+}
+
+/// Panic if `X` contains entries that are not non-negative integers.
+///
+/// Used to validate input of count data models.
+pub fn assert_counts(X: &ArrayView2<'_, f64>) {
+    for ((i, j), value) in X.indexed_iter() {
+        if !(value.is_finite() && (*value >= 0.) && (value.fract() == 0.)) {
+            panic!("X must contain non-negative integer counts. Got {value} at ({i}, {j}).");
+        }
+    }
+}
+
+/// Data-driven block length for block permutations of the serially dependent `values`.
+///
+/// Uses the rule of Politis, D. N. (2003). Adaptive bandwidth choice. Journal of
+/// Nonparametric Statistics 15 517–533: Let `m` be the smallest lag such that the
+/// absolute sample autocorrelations at lags `m + 1, ..., m + K` are all below
+/// `2 sqrt(log10(n) / n)`, with `K = max(5, sqrt(log10(n)))`. The block length is
+/// `2 m`, but at least `ceil(n^(1/3))`, the optimal rate for block bootstrap variance
+/// estimation, and at most `n / 2`.
+///
+/// Mean shifts in `values` also induce autocorrelation. Remove them first, e.g. with
+/// `demean_segments`.
+pub fn automatic_block_length(values: &ArrayView1<'_, f64>) -> usize {
+    let n = values.len();
+    if n < 4 {
+        return 1;
+    }
+
+    let minimal_block_length = (n as f64).cbrt().ceil() as usize;
+    let maximal_block_length = n / 2;
+
+    let mean = values.mean().unwrap();
+    let centered = values.mapv(|x| x - mean);
+    let variance = centered.dot(&centered);
+    if variance <= 0. {
+        return minimal_block_length.min(maximal_block_length);
+    }
+
+    let log_n = (n as f64).log10();
+    let bound = 2. * (log_n / n as f64).sqrt();
+    let K = usize::max(5, log_n.sqrt().ceil() as usize);
+
+    let autocorrelations: Vec<f64> = (1..maximal_block_length)
+        .map(|lag| {
+            centered
+                .slice(ndarray::s![lag..])
+                .dot(&centered.slice(ndarray::s![..(n - lag)]))
+                / variance
+        })
+        .collect();
+
+    // autocorrelations[k] is the autocorrelation at lag k + 1. The condition holds
+    // trivially for m = autocorrelations.len().
+    let m = (0..=autocorrelations.len())
+        .find(|m| {
+            autocorrelations
+                .iter()
+                .skip(*m)
+                .take(K)
+                .all(|autocorrelation| autocorrelation.abs() < bound)
+        })
+        .unwrap();
+
+    (2 * m).max(minimal_block_length).min(maximal_block_length)
+}
+
+/// `values` with the mean of each segment between consecutive `breaks` subtracted.
+///
+/// Breaks outside of `(0, values.len())` are ignored.
+pub fn demean_segments(values: &ArrayView1<'_, f64>, breaks: &[usize]) -> Array1<f64> {
+    let n = values.len();
+    let mut breaks: Vec<usize> = breaks
+        .iter()
+        .copied()
+        .filter(|b| (0 < *b) & (*b < n))
+        .collect();
+    breaks.push(0);
+    breaks.push(n);
+    breaks.sort_unstable();
+    breaks.dedup();
+
+    let mut result = values.to_owned();
+    for window in breaks.windows(2) {
+        let mut segment = result.slice_mut(ndarray::s![window[0]..window[1]]);
+        let mean = segment.mean().unwrap();
+        segment -= mean;
+    }
+    result
+}
+
+/// Residual sum of squares of the least squares fit of `y` on `Z`.
+///
+/// Takes the sufficient statistics `gram = Z^T Z`, `Zy = Z^T y` and `yy = y^T y`. Uses a
+/// Cholesky decomposition `Z^T Z = L L^T`, such that `RSS = y^T y - |L^{-1} Z^T y|^2`.
+/// Columns of `Z` that are (numerically) linearly dependent on previous columns are
+/// ignored.
+pub fn residual_sum_of_squares(
+    gram: &ArrayView2<'_, f64>,
+    Zy: &ArrayView1<'_, f64>,
+    yy: f64,
+) -> f64 {
+    let p = Zy.len();
+    let mut L = Array2::<f64>::zeros((p, p));
+    let mut z = Array1::<f64>::zeros(p);
+
+    for j in 0..p {
+        let mut pivot = gram[[j, j]];
+        for k in 0..j {
+            pivot -= L[[j, k]].powi(2);
+        }
+
+        // Skip columns that are linearly dependent on previous columns.
+        if pivot <= 1e-10 * f64::max(gram[[j, j]], 1.) {
+            continue;
+        }
+        L[[j, j]] = pivot.sqrt();
+
+        for i in (j + 1)..p {
+            let mut value = gram[[i, j]];
+            for k in 0..j {
+                value -= L[[i, k]] * L[[j, k]];
+            }
+            L[[i, j]] = value / L[[j, j]];
+        }
+
+        let mut value = Zy[j];
+        for k in 0..j {
+            value -= L[[j, k]] * z[k];
+        }
+        z[j] = value / L[[j, j]];
+    }
+
+    f64::max(yy - z.mapv(|x| x.powi(2)).sum(), 0.)
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use assert_approx_eq::*;
+    use ndarray_rand::rand_distr::{Normal, Uniform};
+    use ndarray_rand::RandomExt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use rstest::*;
 
     #[rstest]
     #[case(1., 0.)]
     #[case(0., -6.)]
     fn test_log_eta(#[case] x: f64, #[case] expected: f64) {
-        assert_eq!(log_eta(x), expected);
+        assert_eq!(log_eta(x, (-6_f64).exp()), expected);
+    }
+
+    #[test]
+    fn test_isotonic_regression() {
+        let (x, fitted) = isotonic_regression(&[0.3, 0.1, 0.2, 0.4, 0.5], &[1., 0., 1., 0., 1.]);
+        assert_eq!(x, vec![0.1, 0.2, 0.3, 0.4, 0.5]);
+        assert_eq!(fitted, vec![0., 2. / 3., 2. / 3., 2. / 3., 1.]);
+    }
+
+    #[rstest]
+    #[case(2.)]
+    #[case(0.5)]
+    fn test_fit_temperature(#[case] temperature: f64) {
+        // Labels drawn from calibrated probabilities, predictions with logits scaled by
+        // the temperature.
+        let mut rng = StdRng::seed_from_u64(0);
+        let logits = Array1::random_using(5000, Normal::new(0., 2.).unwrap(), &mut rng);
+        let uniform = Array1::random_using(5000, Uniform::new(0., 1.), &mut rng);
+        let labels = Array1::from_shape_fn(5000, |idx| {
+            (uniform[idx] < sigmoid(logits[idx])) as usize as f64
+        });
+        let predictions = logits.mapv(|x| sigmoid(x * temperature));
+
+        let fitted = fit_temperature(
+            &predictions.view(),
+            &labels.view(),
+            &Array1::zeros(5000).view(),
+        );
+        assert!((fitted / temperature - 1.).abs() < 0.1, "{fitted}");
+    }
+
+    #[rstest]
+    #[case(Calibration::None)]
+    #[case(Calibration::Temperature(Some(2.)))]
+    #[case(Calibration::Temperature(None))]
+    #[case(Calibration::Isotonic)]
+    fn test_calibrate(#[case] calibration: Calibration) {
+        let predictions = Array1::from_shape_fn(100, |idx| if idx < 50 { 0.01 } else { 0.99 });
+        let calibrated = calibrate(&predictions, 50, &calibration);
+        assert_eq!(calibrated.len(), 100);
+        assert!(calibrated.iter().all(|&p| (0. ..=1.).contains(&p)));
+        assert!(calibrated[0] < 0.5);
+        assert!(calibrated[99] > 0.5);
+
+        match calibration {
+            Calibration::None => assert_eq!(calibrated, predictions),
+            Calibration::Temperature(Some(_)) => {
+                let center = logit(50. / 99.);
+                assert_approx_eq!(calibrated[0], sigmoid((logit(0.01) - center) / 2. + center))
+            }
+            // Perfectly separating predictions are made more confident.
+            Calibration::Temperature(None) | Calibration::Isotonic => {
+                assert!(calibrated[0] < 0.01);
+                assert!(calibrated[99] > 0.99)
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(Calibration::Temperature(Some(2.)))]
+    #[case(Calibration::Temperature(None))]
+    #[case(Calibration::Isotonic)]
+    fn test_calibrate_uninformative(#[case] calibration: Calibration) {
+        // Predictions of an uninformative leave-one-out classifier are not changed.
+        let predictions = Array1::from_shape_fn(10, |idx| if idx < 4 { 6. / 9. } else { 5. / 9. });
+        let calibrated = calibrate(&predictions, 4, &calibration);
+        assert!(calibrated.abs_diff_eq(&predictions, 1e-6), "{calibrated}");
+    }
+
+    #[rstest]
+    #[case(vec![], f64::NAN)]
+    #[case(vec![3., 1., 2.], 2.)]
+    #[case(vec![4., 1., 3., 2.], 2.5)]
+    #[case(vec![3., f64::NAN, 1.], 3.)]
+    #[case(vec![f64::NAN, 1.], f64::NAN)]
+    fn test_median(#[case] mut values: Vec<f64>, #[case] expected: f64) {
+        let result = median(&mut values);
+        assert!(result == expected || (result.is_nan() && expected.is_nan()));
+    }
+
+    #[rstest]
+    // y = 1 + 2 x, perfect fit.
+    #[case(ndarray::array![[1., 0.], [1., 1.], [1., 2.]], ndarray::array![1., 3., 5.], 0.)]
+    // Regress [0, 1, 0, 1] on an intercept.
+    #[case(ndarray::array![[1.], [1.], [1.], [1.]], ndarray::array![0., 1., 0., 1.], 1.)]
+    // Second column is a duplicate of the first and gets ignored.
+    #[case(ndarray::array![[1., 1.], [1., 1.], [1., 1.], [1., 1.]], ndarray::array![0., 1., 0., 1.], 1.)]
+    fn test_residual_sum_of_squares(
+        #[case] Z: Array2<f64>,
+        #[case] y: Array1<f64>,
+        #[case] expected: f64,
+    ) {
+        let gram = Z.t().dot(&Z);
+        let Zy = Z.t().dot(&y);
+        let yy = y.dot(&y);
+        assert!((residual_sum_of_squares(&gram.view(), &Zy.view(), yy) - expected).abs() < 1e-10);
+    }
+
+    #[rstest]
+    #[case(ndarray::array![[0., 1.], [2., 10.]])]
+    #[should_panic(expected = "non-negative integer counts. Got -1 at (1, 0)")]
+    #[case(ndarray::array![[0., 1.], [-1., 10.]])]
+    #[should_panic(expected = "non-negative integer counts. Got 0.5 at (0, 1)")]
+    #[case(ndarray::array![[0., 0.5], [1., 10.]])]
+    #[should_panic(expected = "non-negative integer counts. Got NaN at (0, 0)")]
+    #[case(ndarray::array![[f64::NAN, 1.], [1., 10.]])]
+    fn test_assert_counts(#[case] X: Array2<f64>) {
+        assert_counts(&X.view());
+    }
+
+    #[test]
+    fn test_automatic_block_length() {
+        // Independent values get the minimal block length ceil(64^(1/3)) = 4.
+        let mut rng = StdRng::seed_from_u64(0);
+        let values = Array1::random_using(64, Normal::new(0., 1.).unwrap(), &mut rng);
+        assert_eq!(automatic_block_length(&values.view()), 4);
+
+        // Slowly varying values are strongly autocorrelated. The autocorrelation at lag
+        // k is 1 - 7k / 64 for k <= 16, which is below 2 sqrt(log10(64) / 64) ~ 0.34
+        // for lags 7 to 11, such that m = 6.
+        let values = Array1::from_shape_fn(64, |i| ((i / 16) % 2) as f64);
+        assert_eq!(automatic_block_length(&values.view()), 12);
+
+        let values = ndarray::array![0., 1.];
+        assert_eq!(automatic_block_length(&values.view()), 1);
+    }
+
+    #[test]
+    fn test_automatic_block_length_mean_shift() {
+        // The mean shift at 32 dominates the autocorrelations of independent values.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut values = Array1::random_using(64, Normal::new(0., 1.).unwrap(), &mut rng);
+        values.slice_mut(ndarray::s![32..]).mapv_inplace(|x| x + 5.);
+        assert_eq!(automatic_block_length(&values.view()), 24);
+
+        let residuals = demean_segments(&values.view(), &[32]);
+        assert_eq!(automatic_block_length(&residuals.view()), 4);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![-1.5, -0.5, 0.5, 1.5])]
+    #[case(vec![2], vec![-0.5, 0.5, -0.5, 0.5])]
+    #[case(vec![2, 0, 4, 2, 7], vec![-0.5, 0.5, -0.5, 0.5])]
+    #[case(vec![3, 1], vec![0., -0.5, 0.5, 0.])]
+    fn test_demean_segments(#[case] breaks: Vec<usize>, #[case] expected: Vec<f64>) {
+        let values = ndarray::array![1., 2., 3., 4.];
+        assert_eq!(
+            demean_segments(&values.view(), &breaks),
+            Array1::from_vec(expected)
+        );
+    }
+
+    #[test]
+    fn test_robust_scale() {
+        // First differences of the first column are [1, -1, 1, -1, 1] with a single
+        // jump of 10 added. The second column is constant.
+        let X = ndarray::array![
+            [0., 1.],
+            [1., 1.],
+            [0., 1.],
+            [11., 1.],
+            [10., 1.],
+            [11., 1.]
+        ];
+        let scale = robust_scale(&X.view());
+        // differences [1, -1, 11, -1, 1], median 1, deviations [0, 2, 10, 2, 0], MAD 2.
+        let expected = ndarray::array![1.4826 * 2. / std::f64::consts::SQRT_2, 1.];
+        assert_eq!(scale, expected);
+    }
+
+    #[test]
+    fn test_robust_scale_nan() {
+        // differences [1, NaN, NaN, -1, 1], median 1, deviations [0, NaN, NaN, 2, 0],
+        // MAD 2.
+        let X = ndarray::array![[0.], [1.], [f64::NAN], [11.], [10.], [11.]];
+        let scale = robust_scale(&X.view());
+        assert_eq!(
+            scale,
+            ndarray::array![1.4826 * 2. / std::f64::consts::SQRT_2]
+        );
     }
 }
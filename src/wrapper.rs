@@ -1,9 +1,18 @@
-use crate::classifier::{kNN, RandomForest};
+use crate::classifier::{kNN, Classifier, Ensemble, ExtraTrees, FnClassifier, RandomForest};
 use crate::control::Control;
-use crate::gain::{ChangeInMean, ClassifierGain};
+use crate::embedding::{burn_in_control, embed};
+use crate::gain::{
+    ChangeInAutoregression, ChangeInLocation, ChangeInMean, ChangeInMultinomial,
+    ChangeInNegativeBinomialRate, ChangeInPoissonRate, ChangeInRegression, ClassifierGain,
+    ConditionalClassifierGain, Gain, PanelGain,
+};
+use crate::observer::Cancelled;
 use crate::optimizer::{GridSearch, TwoStepSearch};
 use crate::segmentation::{Segmentation, SegmentationType};
-use crate::{BinarySegmentationResult, BinarySegmentationTree};
+use crate::{
+    BinarySegmentationResult, BinarySegmentationTree, ChangePoint, ModelSelectionResult, Observer,
+    Optimizer, PanelResult,
+};
 use ndarray;
 
 pub fn changeforest(
@@ -12,51 +21,482 @@ pub fn changeforest(
     segmentation_type: &str,
     control: &Control,
 ) -> BinarySegmentationResult {
-    let segmentation_type_enum: SegmentationType;
-    let mut tree: BinarySegmentationTree;
+    changeforest_observed(X, method, segmentation_type, control, None)
+        .expect("Segmentation without observer cannot be cancelled.")
+}
 
-    if segmentation_type == "bs" {
-        segmentation_type_enum = SegmentationType::BS;
-    } else if segmentation_type == "sbs" {
-        segmentation_type_enum = SegmentationType::SBS;
-    } else if segmentation_type == "wbs" {
-        segmentation_type_enum = SegmentationType::WBS;
-    } else {
-        panic!("segmentation_type must be one of 'bs', 'sbs', 'wbs'")
-    }
+/// Like `changeforest`, but report events to `observer`.
+///
+/// Returns `Err(Cancelled)` if the observer cancelled the segmentation.
+pub fn changeforest_with_observer(
+    X: &ndarray::ArrayView2<'_, f64>,
+    method: &str,
+    segmentation_type: &str,
+    control: &Control,
+    observer: &mut dyn Observer,
+) -> Result<BinarySegmentationResult, Cancelled> {
+    changeforest_observed(X, method, segmentation_type, control, Some(observer))
+}
+
+fn changeforest_observed(
+    X: &ndarray::ArrayView2<'_, f64>,
+    method: &str,
+    segmentation_type: &str,
+    control: &Control,
+    observer: Option<&mut dyn Observer>,
+) -> Result<BinarySegmentationResult, Cancelled> {
+    let segmentation_type = segmentation_type_from_str(segmentation_type);
 
     if method == "knn" {
-        let classifier = kNN::new(X, control);
+        let X_embedded = embed(X, control);
+        let control = burn_in_control(control);
+        let X_embedded_view = X_embedded.view();
+        let classifier = kNN::new(&X_embedded_view, &control);
         let gain = ClassifierGain { classifier };
-        let optimizer = TwoStepSearch { gain };
-        let mut segmentation = Segmentation::new(segmentation_type_enum, &optimizer);
-        tree = BinarySegmentationTree::new(X);
-        tree.grow(&mut segmentation);
-        BinarySegmentationResult::from_tree(tree).with_segments(segmentation)
+        let optimizer = TwoStepSearch::new(gain);
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
     } else if method == "random_forest" {
-        let classifier = RandomForest::new(X, control);
+        let X_embedded = embed(X, control);
+        let control = burn_in_control(control);
+        let X_embedded_view = X_embedded.view();
+        let classifier = RandomForest::new(&X_embedded_view, &control);
+        let gain = ClassifierGain { classifier };
+        let optimizer = TwoStepSearch::new(gain);
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
+    } else if method == "extra_trees" {
+        let X_embedded = embed(X, control);
+        let control = burn_in_control(control);
+        let X_embedded_view = X_embedded.view();
+        let classifier = ExtraTrees::new(&X_embedded_view, &control);
+        let gain = ClassifierGain { classifier };
+        let optimizer = TwoStepSearch::new(gain);
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
+    } else if method.contains('+') {
+        // Ensemble of classifiers, e.g. "knn+random_forest".
+        let X_embedded = embed(X, control);
+        let control = burn_in_control(control);
+        let X_embedded_view = X_embedded.view();
+        let classifier = ensemble_from_str(method, &X_embedded_view, &control);
         let gain = ClassifierGain { classifier };
-        let optimizer = TwoStepSearch { gain };
-        let mut segmentation = Segmentation::new(segmentation_type_enum, &optimizer);
-        tree = BinarySegmentationTree::new(X);
-        tree.grow(&mut segmentation);
-        BinarySegmentationResult::from_tree(tree).with_segments(segmentation)
+        let optimizer = TwoStepSearch::new(gain);
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
     } else if method == "change_in_mean" {
         let gain = ChangeInMean::new(X, control);
+        let scale = gain.scale().cloned();
         let optimizer = GridSearch { gain };
-        let mut segmentation = Segmentation::new(segmentation_type_enum, &optimizer);
-        tree = BinarySegmentationTree::new(X);
-        tree.grow(&mut segmentation);
-        BinarySegmentationResult::from_tree(tree).with_segments(segmentation)
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
+            .map(|result| result.with_scale(scale))
+    } else if method == "change_in_location" {
+        let gain = ChangeInLocation::new(X, control);
+        let optimizer = GridSearch { gain };
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
+    } else if method == "change_in_poisson_rate" {
+        let gain = ChangeInPoissonRate::new(X, control);
+        let optimizer = GridSearch { gain };
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
+    } else if method == "change_in_negative_binomial_rate" {
+        let gain = ChangeInNegativeBinomialRate::new(X, control);
+        let optimizer = GridSearch { gain };
+        binary_segmentation_observed(X, &optimizer, segmentation_type, observer)
+    } else {
+        panic!(
+            "method should be one of 'knn', 'random_forest', 'extra_trees', 'knn+random_forest', 'change_in_mean', 'change_in_location', 'change_in_poisson_rate' or 'change_in_negative_binomial_rate'. Got {method}",
+        );
+    }
+}
+
+/// Classifier-based change point detection with a user-supplied classifier.
+///
+/// `f` receives the (embedded) features of a segment and labels indicating membership
+/// to the second part of the segment and must return out-of-sample probabilities of
+/// the labels being `1`. See `FnClassifier`.
+pub fn changeforest_fn<F>(
+    X: &ndarray::ArrayView2<'_, f64>,
+    f: F,
+    segmentation_type: &str,
+    control: &Control,
+) -> BinarySegmentationResult
+where
+    F: Fn(ndarray::ArrayView2<'_, f64>, ndarray::ArrayView1<'_, f64>) -> ndarray::Array1<f64>,
+{
+    let segmentation_type = segmentation_type_from_str(segmentation_type);
+    let X_embedded = embed(X, control);
+    let control = burn_in_control(control);
+    let X_embedded_view = X_embedded.view();
+    let classifier = FnClassifier::new(&X_embedded_view, f, &control);
+    let gain = ClassifierGain { classifier };
+    let optimizer = TwoStepSearch::new(gain);
+    binary_segmentation(X, &optimizer, segmentation_type)
+}
+
+/// Detect changes in the coefficients of a linear regression of `y` on `X`.
+///
+/// Within each segment, `y` is regressed on `X` and an intercept. The model
+/// selection uses `control.penalty` with the number of coefficients, the noise
+/// variance and the location of the change as additional parameters.
+pub fn changeforest_regression(
+    X: &ndarray::ArrayView2<'_, f64>,
+    y: &ndarray::ArrayView1<'_, f64>,
+    segmentation_type: &str,
+    control: &Control,
+) -> BinarySegmentationResult {
+    if X.nrows() != y.len() {
+        panic!(
+            "X and y must have the same number of observations. Got {} and {}",
+            X.nrows(),
+            y.len()
+        );
+    }
+
+    let segmentation_type = segmentation_type_from_str(segmentation_type);
+    let data = ndarray::concatenate(
+        ndarray::Axis(1),
+        &[X.view(), y.view().insert_axis(ndarray::Axis(1))],
+    )
+    .unwrap();
+    let data_view = data.view();
+
+    let gain = ChangeInRegression::new(&data_view, X.ncols(), control);
+    let optimizer = GridSearch { gain };
+    binary_segmentation(&data_view, &optimizer, segmentation_type)
+}
+
+/// Detect changes in the conditional distribution of `y` given `X`.
+///
+/// Compares the classifier log-likelihood ratios of a classifier predicting segment
+/// membership from `(X, y)` to those of a classifier using `X` only. `method` is one
+/// of `"knn"` or `"random_forest"`.
+pub fn changeforest_conditional(
+    X: &ndarray::ArrayView2<'_, f64>,
+    y: &ndarray::ArrayView2<'_, f64>,
+    method: &str,
+    segmentation_type: &str,
+    control: &Control,
+) -> BinarySegmentationResult {
+    if X.nrows() != y.nrows() {
+        panic!(
+            "X and y must have the same number of observations. Got {} and {}",
+            X.nrows(),
+            y.nrows()
+        );
+    }
+
+    let segmentation_type = segmentation_type_from_str(segmentation_type);
+    let data = ndarray::concatenate(ndarray::Axis(1), &[X.view(), y.view()]).unwrap();
+    let data_view = data.view();
+
+    if method == "knn" {
+        let joint = kNN::new(&data_view, control);
+        let marginal = kNN::new(X, control);
+        let gain = ConditionalClassifierGain { joint, marginal };
+        let optimizer = TwoStepSearch::new(gain);
+        binary_segmentation(&data_view, &optimizer, segmentation_type)
+    } else if method == "random_forest" {
+        let joint = RandomForest::new(&data_view, control);
+        let marginal = RandomForest::new(X, control);
+        let gain = ConditionalClassifierGain { joint, marginal };
+        let optimizer = TwoStepSearch::new(gain);
+        binary_segmentation(&data_view, &optimizer, segmentation_type)
+    } else {
+        panic!("method should be one of 'knn' or 'random_forest'. Got {method}");
+    }
+}
+
+/// Detect changes in the autoregressive structure of each feature of `X`.
+///
+/// Each column of `X` is modelled as an independent AR(`order`) process with intercept,
+/// see `ChangeInAutoregression`. This can detect changes in the dynamics of a time
+/// series that leave its marginal distribution unchanged.
+pub fn changeforest_autoregression(
+    X: &ndarray::ArrayView2<'_, f64>,
+    order: usize,
+    segmentation_type: &str,
+    control: &Control,
+) -> BinarySegmentationResult {
+    let segmentation_type = segmentation_type_from_str(segmentation_type);
+    let gain = ChangeInAutoregression::new(X, order, control);
+    let optimizer = GridSearch { gain };
+    binary_segmentation(X, &optimizer, segmentation_type)
+}
+
+/// Detect changes in the category frequencies of integer-coded categorical features.
+///
+/// Each column of `X` is a categorical feature. The gain is the multinomial
+/// log-likelihood ratio, see `ChangeInMultinomial`.
+pub fn changeforest_categorical(
+    X: &ndarray::ArrayView2<'_, usize>,
+    segmentation_type: &str,
+    control: &Control,
+) -> BinarySegmentationResult {
+    let segmentation_type = segmentation_type_from_str(segmentation_type);
+    let gain = ChangeInMultinomial::new(X, control);
+    let optimizer = GridSearch { gain };
+    // The binary segmentation tree only requires the number of observations.
+    let empty = ndarray::Array2::<f64>::zeros((X.nrows(), 0));
+    binary_segmentation(&empty.view(), &optimizer, segmentation_type)
+}
+
+/// Detect change points shared by a panel of series.
+///
+/// Row `i` of each series in `Xs` corresponds to the same point in time. Series may
+/// have different lengths, i.e., end early. The gains of the series are summed inside
+/// a single optimizer, see `PanelGain`. `method` is one of `"change_in_mean"`,
+/// `"change_in_location"`, `"change_in_poisson_rate"`,
+/// `"change_in_negative_binomial_rate"` or a classifier, e.g. `"random_forest"` or
+/// `"knn+random_forest"`, in which case the classifier log-likelihood ratios are summed.
+///
+/// The significance of a shared split point in a series is the model selection result
+/// of the series alone, restricted to the shared split point within its segment.
+pub fn changeforest_panel(
+    Xs: &[ndarray::ArrayView2<'_, f64>],
+    method: &str,
+    segmentation_type: &str,
+    control: &Control,
+) -> PanelResult {
+    if Xs.is_empty() {
+        panic!("Xs must contain at least one series.");
+    }
+
+    let segmentation_type = segmentation_type_from_str(segmentation_type);
+
+    if method == "change_in_mean" {
+        let gains = Xs.iter().map(|X| ChangeInMean::new(X, control)).collect();
+        likelihood_panel(Xs, gains, method, segmentation_type, control)
+    } else if method == "change_in_location" {
+        let gains = Xs
+            .iter()
+            .map(|X| ChangeInLocation::new(X, control))
+            .collect();
+        likelihood_panel(Xs, gains, method, segmentation_type, control)
+    } else if method == "change_in_poisson_rate" {
+        let gains = Xs
+            .iter()
+            .map(|X| ChangeInPoissonRate::new(X, control))
+            .collect();
+        likelihood_panel(Xs, gains, method, segmentation_type, control)
+    } else if method == "change_in_negative_binomial_rate" {
+        let gains = Xs
+            .iter()
+            .map(|X| ChangeInNegativeBinomialRate::new(X, control))
+            .collect();
+        likelihood_panel(Xs, gains, method, segmentation_type, control)
+    } else if ["knn", "random_forest", "extra_trees"].contains(&method) || method.contains('+') {
+        let X_longest = Xs.iter().max_by_key(|X| X.nrows()).unwrap();
+        let Xs_embedded: Vec<ndarray::Array2<f64>> = Xs.iter().map(|X| embed(X, control)).collect();
+        let Xs_embedded_view: Vec<ndarray::ArrayView2<'_, f64>> =
+            Xs_embedded.iter().map(|X| X.view()).collect();
+        let embedded_control = burn_in_control(control);
+        let gain = PanelGain::new(
+            Xs_embedded_view
+                .iter()
+                .map(|X| ClassifierGain {
+                    classifier: ensemble_from_str(method, X, &embedded_control),
+                })
+                .collect(),
+        );
+        let optimizer = TwoStepSearch::new(gain);
+        let result = binary_segmentation(X_longest, &optimizer, segmentation_type);
+
+        let series_model_selection_results = series_model_selection(
+            &result,
+            &optimizer.gain,
+            Xs,
+            control,
+            |X, control, segment| {
+                let X_embedded = embed(X, control);
+                let X_embedded_view = X_embedded.view();
+                let classifier = ensemble_from_str(method, &X_embedded_view, control);
+                let optimizer = TwoStepSearch::new(ClassifierGain { classifier });
+                forced_model_selection(&optimizer, segment)
+            },
+        );
+        PanelResult {
+            result,
+            series_model_selection_results,
+        }
+    } else {
+        panic!(
+            "method for changeforest_panel should be one of 'knn', 'random_forest', 'extra_trees', an ensemble such as 'knn+random_forest', 'change_in_mean', 'change_in_location', 'change_in_poisson_rate' or 'change_in_negative_binomial_rate'. Got {method}",
+        );
+    }
+}
+
+/// Panel change point detection with the likelihood-based `gains` of the series in
+/// `Xs`, which were constructed for `method`.
+fn likelihood_panel<T: Gain>(
+    Xs: &[ndarray::ArrayView2<'_, f64>],
+    gains: Vec<T>,
+    method: &str,
+    segmentation_type: SegmentationType,
+    control: &Control,
+) -> PanelResult {
+    let X_longest = Xs.iter().max_by_key(|X| X.nrows()).unwrap();
+    let optimizer = GridSearch {
+        gain: PanelGain::new(gains),
+    };
+    let result = binary_segmentation(X_longest, &optimizer, segmentation_type);
+
+    let series_model_selection_results = series_model_selection(
+        &result,
+        &optimizer.gain,
+        Xs,
+        control,
+        |X, control, segment| {
+            if method == "change_in_mean" {
+                let gain = ChangeInMean::new(X, control);
+                forced_model_selection(&GridSearch { gain }, segment)
+            } else if method == "change_in_location" {
+                let gain = ChangeInLocation::new(X, control);
+                forced_model_selection(&GridSearch { gain }, segment)
+            } else if method == "change_in_poisson_rate" {
+                let gain = ChangeInPoissonRate::new(X, control);
+                forced_model_selection(&GridSearch { gain }, segment)
+            } else {
+                let gain = ChangeInNegativeBinomialRate::new(X, control);
+                forced_model_selection(&GridSearch { gain }, segment)
+            }
+        },
+    );
+    PanelResult {
+        result,
+        series_model_selection_results,
+    }
+}
+
+/// Model selection of each series at each shared split point of `result`.
+///
+/// For a split point `split` of segment `[start, stop)`, `f` is called with the series,
+/// a control where `split` is the only split candidate and the segment
+/// `(start, series_stop)` restricted to the series.
+fn series_model_selection<T, F>(
+    result: &BinarySegmentationResult,
+    gain: &PanelGain<T>,
+    Xs: &[ndarray::ArrayView2<'_, f64>],
+    control: &Control,
+    f: F,
+) -> Vec<Vec<Option<ModelSelectionResult>>>
+where
+    T: Gain,
+    F: Fn(&ndarray::ArrayView2<'_, f64>, &Control, (usize, usize)) -> ModelSelectionResult,
+{
+    result
+        .change_points()
+        .iter()
+        .map(
+            |&ChangePoint {
+                 start, stop, split, ..
+             }| {
+                Xs.iter()
+                    .enumerate()
+                    .map(|(idx, X)| {
+                        let series_stop = gain.series_stop(idx, start, stop, split)?;
+                        let series_control = control
+                            .clone()
+                            .with_forbidden_segments(Some(vec![
+                                (start, split - 1),
+                                (split, series_stop),
+                            ]))
+                            .with_minimal_segment_duration(None)
+                            .with_forbidden_time_segments(None)
+                            .with_time_index(None);
+                        Some(f(X, &series_control, (start, series_stop)))
+                    })
+                    .collect()
+            },
+        )
+        .collect()
+}
+
+/// Model selection of the best split of segment `(start, stop)`, not significant if
+/// there are no split candidates.
+fn forced_model_selection(
+    optimizer: &dyn Optimizer,
+    (start, stop): (usize, usize),
+) -> ModelSelectionResult {
+    match optimizer.find_best_split(start, stop) {
+        Ok(optimizer_result) => optimizer.model_selection(&optimizer_result),
+        Err(_) => ModelSelectionResult::default(),
+    }
+}
+
+fn segmentation_type_from_str(segmentation_type: &str) -> SegmentationType {
+    if segmentation_type == "bs" {
+        SegmentationType::BS
+    } else if segmentation_type == "sbs" {
+        SegmentationType::SBS
+    } else if segmentation_type == "wbs" {
+        SegmentationType::WBS
     } else {
-        panic!("method should be one of 'knn', 'random_forest' or 'change_in_mean'. Got {method}",);
+        panic!("segmentation_type must be one of 'bs', 'sbs', 'wbs'")
+    }
+}
+
+fn binary_segmentation(
+    X: &ndarray::ArrayView2<'_, f64>,
+    optimizer: &dyn Optimizer,
+    segmentation_type: SegmentationType,
+) -> BinarySegmentationResult {
+    binary_segmentation_observed(X, optimizer, segmentation_type, None)
+        .expect("Segmentation without observer cannot be cancelled.")
+}
+
+fn binary_segmentation_observed(
+    X: &ndarray::ArrayView2<'_, f64>,
+    optimizer: &dyn Optimizer,
+    segmentation_type: SegmentationType,
+    observer: Option<&mut dyn Observer>,
+) -> Result<BinarySegmentationResult, Cancelled> {
+    optimizer.control().check_time_index(X.nrows());
+    let time_index = optimizer.control().time_index.clone();
+
+    // Shorten the lifetime of the observer to the one of the optimizer.
+    let observer = observer.map(|observer| observer as &mut dyn Observer);
+    let mut segmentation = Segmentation::new_with_observer(segmentation_type, optimizer, observer);
+    let mut tree = BinarySegmentationTree::new(X);
+    tree.grow(&mut segmentation);
+    if segmentation.is_cancelled() {
+        return Err(Cancelled);
     }
+    Ok(BinarySegmentationResult::from_tree(tree)
+        .with_segments(segmentation)
+        .with_time_index(time_index))
+}
+
+/// Ensemble of the classifiers in `method`, e.g. `"knn+random_forest"` or `"knn"`.
+fn ensemble_from_str<'a>(
+    method: &str,
+    X: &'a ndarray::ArrayView2<'a, f64>,
+    control: &'a Control,
+) -> Ensemble<'a> {
+    let classifiers: Vec<Box<dyn Classifier>> = method
+        .split('+')
+        .map(|name| -> Box<dyn Classifier> {
+            match name {
+                "knn" => Box::new(kNN::new(X, control)),
+                "random_forest" => Box::new(RandomForest::new(X, control)),
+                "extra_trees" => Box::new(ExtraTrees::new(X, control)),
+                _ => panic!(
+                    "Ensembles can only combine 'knn', 'random_forest' and 'extra_trees'. Got {name} in {method}"
+                ),
+            }
+        })
+        .collect();
+    Ensemble::new(classifiers)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metrics;
+    use crate::observer::{Event, Signal};
+    use crate::simulate;
     use crate::testing;
+    use crate::Calibration;
+    use ndarray::{Array1, Array2};
+    use ndarray_rand::rand_distr::{Distribution, Normal, Poisson, WeightedIndex};
+    use ndarray_rand::RandomExt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use rstest::*;
 
     #[rstest]
@@ -66,9 +506,16 @@ mod tests {
     #[case("change_in_mean", "bs")]
     #[case("change_in_mean", "wbs")]
     #[case("change_in_mean", "sbs")]
+    #[case("change_in_location", "bs")]
+    #[case("change_in_location", "wbs")]
+    #[case("change_in_location", "sbs")]
     #[case("random_forest", "bs")]
     //#[case("random_forest", "wbs")]
     #[case("random_forest", "sbs")]
+    #[case("extra_trees", "bs")]
+    #[case("extra_trees", "sbs")]
+    #[case("knn+random_forest", "bs")]
+    #[case("knn+random_forest", "sbs")]
     fn test_binary_segmentation_wrapper(#[case] method: &str, #[case] segmentation_type: &str) {
         let X = testing::array();
         let control = Control::default().with_minimal_relative_segment_length(0.1);
@@ -79,4 +526,454 @@ mod tests {
             vec![25, 40, 80]
         );
     }
+
+    #[rstest]
+    #[case("knn", "bs")]
+    #[case("random_forest", "bs")]
+    #[case("random_forest", "sbs")]
+    fn test_changeforest_warm_start(#[case] method: &str, #[case] segmentation_type: &str) {
+        let X = testing::array();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.1)
+            .with_warm_start(true);
+
+        assert_eq!(
+            changeforest(&X.view(), method, segmentation_type, &control).split_points(),
+            vec![25, 40, 80]
+        );
+    }
+
+    #[rstest]
+    #[case(5, 1, false)]
+    #[case(3, 3, true)]
+    #[case(1, 3, true)]
+    fn test_changeforest_two_step_search_parameters(
+        #[case] n_guesses: usize,
+        #[case] n_refinements: usize,
+        #[case] bracketing: bool,
+    ) {
+        let X = testing::array();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.1)
+            .with_two_step_search_n_guesses(n_guesses)
+            .with_two_step_search_n_refinements(n_refinements)
+            .with_two_step_search_bracketing(bracketing);
+
+        assert_eq!(
+            changeforest(&X.view(), "random_forest", "bs", &control).split_points(),
+            vec![25, 40, 80]
+        );
+    }
+
+    #[rstest]
+    #[case("knn", 0.05, Calibration::None)]
+    #[case("random_forest", 0.05, Calibration::None)]
+    #[case("knn", (-6_f64).exp(), Calibration::Temperature(Some(2.)))]
+    #[case("knn", (-6_f64).exp(), Calibration::Temperature(None))]
+    #[case("random_forest", (-6_f64).exp(), Calibration::Temperature(Some(2.)))]
+    #[case("random_forest", (-6_f64).exp(), Calibration::Temperature(None))]
+    #[case("knn", (-6_f64).exp(), Calibration::Isotonic)]
+    #[case("random_forest", (-6_f64).exp(), Calibration::Isotonic)]
+    fn test_changeforest_calibration(
+        #[case] method: &str,
+        #[case] likelihood_shrinkage: f64,
+        #[case] calibration: Calibration,
+    ) {
+        let X = testing::array();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.1)
+            .with_likelihood_shrinkage(likelihood_shrinkage)
+            .with_calibration(calibration);
+
+        assert_eq!(
+            changeforest(&X.view(), method, "bs", &control).split_points(),
+            vec![25, 40, 80]
+        );
+    }
+
+    #[test]
+    fn test_changeforest_fn() {
+        // Wrap the random forest in a closure.
+        let X = testing::array();
+        let control = Control::default().with_minimal_relative_segment_length(0.1);
+        let f = |X: ndarray::ArrayView2<'_, f64>, y: ndarray::ArrayView1<'_, f64>| {
+            let mut forest = biosphere::RandomForest::new(control.random_forest_parameters.clone());
+            forest.fit_predict_oob(&X, &y)
+        };
+
+        assert_eq!(
+            changeforest_fn(&X.view(), f, "bs", &control).split_points(),
+            changeforest(&X.view(), "random_forest", "bs", &control).split_points()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can only combine")]
+    fn test_changeforest_ensemble_invalid_method() {
+        let X = testing::array();
+        let control = Control::default();
+        changeforest(&X.view(), "knn+change_in_mean", "bs", &control);
+    }
+
+    #[test]
+    fn test_change_in_mean_standardize() {
+        let X = testing::array();
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.1)
+            .with_standardize(true);
+
+        let result = changeforest(&X.view(), "change_in_mean", "bs", &control);
+        assert_eq!(result.split_points(), vec![25, 40, 80]);
+        assert_eq!(result.scale.unwrap().len(), 5);
+
+        let control = Control::default();
+        let result = changeforest(&X.view(), "change_in_mean", "bs", &control);
+        assert!(result.scale.is_none());
+    }
+
+    #[rstest]
+    #[case("bs")]
+    #[case("wbs")]
+    #[case("sbs")]
+    fn test_changeforest_regression(#[case] segmentation_type: &str) {
+        let X = testing::array();
+        // The first column of X has mean shifts at 25 and 40. The response depends
+        // on the second column with a slope that changes at 60.
+        let mut y = X.column(1).to_owned() + X.column(4);
+        y.slice_mut(ndarray::s![60..]).assign(
+            &(X.slice(ndarray::s![60.., 1]).mapv(|x| -2. * x) + X.slice(ndarray::s![60.., 4])),
+        );
+        let control = Control::default().with_minimal_relative_segment_length(0.1);
+
+        let result = changeforest_regression(
+            &X.slice(ndarray::s![.., 0..4]),
+            &y.view(),
+            segmentation_type,
+            &control,
+        );
+        assert_eq!(result.split_points(), vec![60]);
+    }
+
+    #[rstest]
+    #[case("change_in_poisson_rate")]
+    #[case("change_in_negative_binomial_rate")]
+    fn test_changeforest_counts(#[case] method: &str) {
+        // Poisson counts with rates 1, 4 and 1 for the first feature and 2 throughout
+        // for the second feature.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut X = Array2::random_using((300, 2), Poisson::new(2.).unwrap(), &mut rng);
+        X.slice_mut(ndarray::s![.., 0])
+            .assign(&Array1::random_using(
+                300,
+                Poisson::new(1.).unwrap(),
+                &mut rng,
+            ));
+        X.slice_mut(ndarray::s![100..200, 0])
+            .assign(&Array1::random_using(
+                100,
+                Poisson::new(4.).unwrap(),
+                &mut rng,
+            ));
+        let control = Control::default();
+
+        let result = changeforest(&X.view(), method, "bs", &control);
+        assert!(metrics::hausdorff_distance(&result, &[100, 200][..]) <= 5.);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-negative integer counts")]
+    fn test_changeforest_counts_invalid_input() {
+        let X = testing::array();
+        changeforest(
+            &X.view(),
+            "change_in_poisson_rate",
+            "bs",
+            &Control::default(),
+        );
+    }
+
+    #[rstest]
+    #[case("knn")]
+    #[case("random_forest")]
+    fn test_changeforest_embedding(#[case] method: &str) {
+        // AR(1) with coefficient 0.7 before and -0.7 after 150. The marginal
+        // distribution is unchanged, such that classifiers need the lagged features
+        // to detect the change.
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = Array1::random_using(300, Normal::new(0., 1.).unwrap(), &mut rng);
+        let mut X = Array2::<f64>::zeros((300, 1));
+        for t in 1..300 {
+            let coefficient = if t < 150 { 0.7 } else { -0.7 };
+            X[[t, 0]] = coefficient * X[[t - 1, 0]] + noise[t];
+        }
+        let control = Control::default()
+            .with_minimal_relative_segment_length(0.05)
+            .with_embedding_lags(1);
+
+        let result = changeforest(&X.view(), method, "bs", &control);
+        assert_eq!(result.split_points(), vec![150]);
+    }
+
+    #[rstest]
+    #[case("knn")]
+    #[case("random_forest")]
+    fn test_changeforest_embedding_burn_in(#[case] method: &str) {
+        // All equally spaced initial guesses of the two-step search lie within the
+        // burn-in of 59 observations.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut X = Array2::random_using((100, 2), Normal::new(0., 1.).unwrap(), &mut rng);
+        X.slice_mut(ndarray::s![70.., ..]).mapv_inplace(|x| x + 5.);
+        let control = Control::default().with_embedding_windows(vec![60]);
+
+        // The rolling means keep changing after the change at 70, such that further
+        // splits are found.
+        let split_points = changeforest(&X.view(), method, "bs", &control).split_points();
+        assert!(split_points.contains(&70));
+        assert!(split_points.iter().all(|split| *split > 59));
+    }
+
+    #[rstest]
+    #[case("change_in_mean")]
+    #[case("change_in_location")]
+    fn test_changeforest_embedding_not_embedded(#[case] method: &str) {
+        // Methods that are not fit on embedded features ignore the burn-in.
+        let X = testing::array();
+        let control = Control::default().with_embedding_lags(60);
+
+        let result = changeforest(&X.view(), method, "bs", &control);
+        assert_eq!(
+            result.split_points(),
+            changeforest(&X.view(), method, "bs", &Control::default()).split_points()
+        );
+        assert_eq!(result.split_points()[0], 25);
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    fn test_changeforest_autoregression(#[case] order: usize) {
+        // AR(1) with coefficients 0.8, -0.8 and 0.8 in [0, 100), [100, 200) and
+        // [200, 300).
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = Array1::random_using(300, Normal::new(0., 1.).unwrap(), &mut rng);
+        let mut X = Array2::<f64>::zeros((300, 1));
+        for t in 1..300 {
+            let coefficient = if (100..200).contains(&t) { -0.8 } else { 0.8 };
+            X[[t, 0]] = coefficient * X[[t - 1, 0]] + noise[t];
+        }
+        let control = Control::default();
+
+        let result = changeforest_autoregression(&X.view(), order, "bs", &control);
+        assert_eq!(result.split_points(), vec![100, 200]);
+    }
+
+    #[rstest]
+    #[case("bs")]
+    #[case("sbs")]
+    #[case("wbs")]
+    fn test_changeforest_categorical(#[case] segmentation_type: &str) {
+        // States 0, 1, 2 with frequencies (0.8, 0.1, 0.1), (0.1, 0.1, 0.8) and
+        // (0.8, 0.1, 0.1) in [0, 100), [100, 200) and [200, 300).
+        let mut rng = StdRng::seed_from_u64(0);
+        let before = WeightedIndex::new([0.8, 0.1, 0.1]).unwrap();
+        let after = WeightedIndex::new([0.1, 0.1, 0.8]).unwrap();
+        let mut X = Array2::from_shape_fn((300, 2), |_| before.sample(&mut rng));
+        X.slice_mut(ndarray::s![100..200, 0])
+            .mapv_inplace(|_| after.sample(&mut rng));
+        let control = Control::default();
+
+        let result = changeforest_categorical(&X.view(), segmentation_type, &control);
+        assert!(metrics::hausdorff_distance(&result, &[100, 200][..]) <= 5.);
+    }
+
+    #[rstest]
+    #[case("knn")]
+    #[case("random_forest")]
+    fn test_changeforest_conditional(#[case] method: &str) {
+        // The mean of x_0 shifts at 100. y = x_1 + noise for t < 200 and
+        // y = x_1 + 2 + noise for t >= 200, such that the distribution of X does not
+        // change at 200, while the distribution of y | X does not change at 100.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut X = Array2::random_using((300, 2), Normal::new(0., 1.).unwrap(), &mut rng);
+        X.slice_mut(ndarray::s![100.., 0]).mapv_inplace(|x| x + 3.);
+        let mut y = Array2::random_using((300, 1), Normal::new(0., 0.5).unwrap(), &mut rng);
+        for i in 0..300 {
+            let shift = if i < 200 { 0. } else { 2. };
+            y[[i, 0]] += X[[i, 1]] + shift;
+        }
+        let control = Control::default().with_minimal_relative_segment_length(0.1);
+
+        let result = changeforest_conditional(&X.view(), &y.view(), method, "bs", &control);
+        assert!(metrics::hausdorff_distance(&result, &[200][..]) <= 5.);
+    }
+
+    #[rstest]
+    #[case("change_in_mean", "bs")]
+    #[case("knn", "sbs")]
+    #[case("random_forest", "wbs")]
+    fn test_changeforest_with_observer(#[case] method: &str, #[case] segmentation_type: &str) {
+        let X = testing::array();
+        let X_view = X.view();
+        let control = Control::default().with_number_of_wild_segments(10);
+
+        let mut n_events = 0;
+        let mut observer = |_: &Event| {
+            n_events += 1;
+            Signal::Continue
+        };
+        let result =
+            changeforest_with_observer(&X_view, method, segmentation_type, &control, &mut observer)
+                .unwrap();
+        assert!(n_events > 0);
+        assert_eq!(
+            result.split_points(),
+            changeforest(&X_view, method, segmentation_type, &control).split_points()
+        );
+
+        let mut observer = |event: &Event| match event {
+            Event::Progress(_) => Signal::Cancel,
+            _ => Signal::Continue,
+        };
+        assert_eq!(
+            changeforest_with_observer(&X_view, method, segmentation_type, &control, &mut observer)
+                .unwrap_err(),
+            Cancelled
+        );
+    }
+
+    #[rstest]
+    #[case("change_in_mean", None, vec![25, 40, 80], vec![25., 40., 53.])]
+    #[case("change_in_mean", Some(5.), vec![25, 40], vec![25., 40.])]
+    #[case("random_forest", Some(5.), vec![25, 40], vec![25., 40.])]
+    fn test_changeforest_time_index(
+        #[case] method: &str,
+        #[case] minimal_segment_duration: Option<f64>,
+        #[case] expected: Vec<usize>,
+        #[case] expected_timestamps: Vec<f64>,
+    ) {
+        let X = testing::array();
+        // Observations every hour, and every six minutes from observation 50 onwards.
+        // The last segment [80, 100) is shorter than 5 hours.
+        let time_index: Vec<f64> = (0..100)
+            .map(|i| {
+                if i < 50 {
+                    i as f64
+                } else {
+                    50. + (i - 50) as f64 / 10.
+                }
+            })
+            .collect();
+        let control = Control::default()
+            .with_time_index(Some(time_index))
+            .with_minimal_segment_duration(minimal_segment_duration);
+
+        let result = changeforest(&X.view(), method, "bs", &control);
+        assert_eq!(result.split_points(), expected);
+        assert_eq!(result.split_timestamps(), Some(expected_timestamps));
+    }
+
+    #[test]
+    #[should_panic(expected = "time_index must have one entry per observation")]
+    fn test_changeforest_time_index_invalid_length() {
+        let X = testing::array();
+        let control = Control::default().with_time_index(Some(vec![0., 1., 2.]));
+        changeforest(&X.view(), "change_in_mean", "bs", &control);
+    }
+
+    #[rstest]
+    #[case("change_in_mean")]
+    #[case("change_in_location")]
+    #[case("knn")]
+    fn test_changeforest_panel(#[case] method: &str) {
+        // Three series with a shift in mean at 100, one shorter series without and one
+        // shorter series with a shift at 100.
+        let mut Xs: Vec<Array2<f64>> = (0..3)
+            .map(|seed| simulate::mean_shift(200, &[100], 5, 1., seed).sample())
+            .collect();
+        Xs.push(
+            simulate::mean_shift(200, &[], 5, 0., 100)
+                .sample()
+                .slice(ndarray::s![..150, ..])
+                .to_owned(),
+        );
+        Xs.push(
+            simulate::mean_shift(200, &[100], 5, 1., 101)
+                .sample()
+                .slice(ndarray::s![..130, ..])
+                .to_owned(),
+        );
+        let Xs_view: Vec<ndarray::ArrayView2<'_, f64>> = Xs.iter().map(|X| X.view()).collect();
+
+        let control = Control::default();
+        let result = changeforest_panel(&Xs_view, method, "bs", &control);
+        assert_eq!(result.split_points(), vec![100]);
+        assert_eq!(
+            result.series_is_significant(),
+            vec![vec![true, true, true, false, true]]
+        );
+    }
+
+    #[test]
+    fn test_changeforest_panel_not_covered() {
+        let X = testing::array();
+        let Xs = vec![X.view(), X.slice(ndarray::s![..30, ..])];
+
+        let control = Control::default();
+        let result = changeforest_panel(&Xs, "change_in_mean", "bs", &control);
+        assert_eq!(result.split_points(), vec![25, 40, 80]);
+        // The short series only covers the first split point.
+        let covered: Vec<Vec<bool>> = result
+            .series_model_selection_results
+            .iter()
+            .map(|results| results.iter().map(|result| result.is_some()).collect())
+            .collect();
+        assert_eq!(
+            covered,
+            vec![vec![true, true], vec![true, false], vec![true, false]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Xs must contain at least one series.")]
+    fn test_changeforest_panel_empty() {
+        changeforest_panel(&[], "change_in_mean", "bs", &Control::default());
+    }
+
+    #[test]
+    fn test_changeforest_panel_counts() {
+        // Poisson counts with rates 1 and 4 before and after 100 in each series.
+        let mut rng = StdRng::seed_from_u64(0);
+        let Xs: Vec<Array2<f64>> = (0..3)
+            .map(|_| {
+                let mut X = Array2::random_using((200, 2), Poisson::new(1.).unwrap(), &mut rng);
+                X.slice_mut(ndarray::s![100.., ..])
+                    .assign(&Array2::random_using(
+                        (100, 2),
+                        Poisson::new(4.).unwrap(),
+                        &mut rng,
+                    ));
+                X
+            })
+            .collect();
+        let Xs_view: Vec<ndarray::ArrayView2<'_, f64>> = Xs.iter().map(|X| X.view()).collect();
+
+        let control = Control::default();
+        for method in ["change_in_poisson_rate", "change_in_negative_binomial_rate"] {
+            let result = changeforest_panel(&Xs_view, method, "bs", &control);
+            assert!(metrics::hausdorff_distance(&result.result, &[100][..]) <= 5.);
+            assert_eq!(result.series_is_significant(), vec![vec![true; 3]]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "method for changeforest_panel should be one of")]
+    fn test_changeforest_panel_invalid_method() {
+        let X = testing::array();
+        changeforest_panel(
+            &[X.view()],
+            "change_in_regression",
+            "bs",
+            &Control::default(),
+        );
+    }
 }
@@ -1,9 +1,9 @@
 // Allow capital X for arrays.
 #![allow(non_snake_case)]
 
-use changeforest::{wrapper::changeforest, Control};
+use changeforest::{metrics, simulate, wrapper::changeforest, Control};
 use csv::ReaderBuilder;
-use ndarray::Array2;
+use ndarray::{Array1, Array2};
 use ndarray_csv::Array2Reader;
 use rstest::*;
 use std::fs::File;
@@ -15,9 +15,16 @@ use std::fs::File;
 #[case("change_in_mean", "bs")]
 #[case("change_in_mean", "wbs")]
 #[case("change_in_mean", "sbs")]
+#[case("change_in_location", "bs")]
+#[case("change_in_location", "wbs")]
+#[case("change_in_location", "sbs")]
 #[case("random_forest", "bs")]
 #[case("random_forest", "wbs")]
 #[case("random_forest", "sbs")]
+#[case("extra_trees", "bs")]
+#[case("extra_trees", "wbs")]
+#[case("extra_trees", "sbs")]
+#[case("knn+random_forest", "bs")]
 fn test_integration_iris(#[case] method: &str, #[case] segmentation_type: &str) {
     let file = File::open("testdata/iris.csv").unwrap();
     let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
@@ -36,6 +43,9 @@ fn test_integration_iris(#[case] method: &str, #[case] segmentation_type: &str)
 #[case("change_in_mean", "bs")]
 #[case("change_in_mean", "sbs")]
 #[case("change_in_mean", "wbs")]
+#[case("change_in_location", "bs")]
+#[case("change_in_location", "sbs")]
+#[case("change_in_location", "wbs")]
 #[case("random_forest", "bs")]
 #[case("random_forest", "sbs")]
 #[case("random_forest", "wbs")]
@@ -50,3 +60,39 @@ fn test_integration_letters(#[case] method: &str, #[case] segmentation_type: &st
 
     let _ = changeforest(&X.view(), method, segmentation_type, &control);
 }
+
+#[rstest]
+#[case("knn")]
+#[case("change_in_mean")]
+#[case("change_in_location")]
+#[case("random_forest")]
+#[case("extra_trees")]
+fn test_integration_iris_metrics(#[case] method: &str) {
+    let file = File::open("testdata/iris.csv").unwrap();
+    let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
+    let X: Array2<f64> = reader.deserialize_array2((150, 4)).unwrap();
+
+    let control = Control::default().with_minimal_relative_segment_length(0.1);
+    let result = changeforest(&X.view(), method, "bs", &control);
+
+    let truth = vec![50, 100];
+    assert!(metrics::hausdorff_distance(&truth, &result) <= 5.);
+    assert_eq!(metrics::f1_score(&truth, &result, 5), 1.);
+    assert!(metrics::adjusted_rand_index(&truth, &result, 150) > 0.9);
+    assert!(metrics::covering(&truth, &result, 150) > 0.9);
+}
+
+#[rstest]
+#[case("change_in_mean", simulate::mean_shift(600, &[200, 400], 5, 1., 0))]
+#[case("random_forest", simulate::covariance_change(600, &[200, 400], 5, 0.8, 0))]
+#[case("random_forest", simulate::mean_shift(600, &[200, 400], 5, 1., 0).with_ar_coefficient(0.3))]
+#[case("knn", simulate::dirichlet(600, &[300], &[Array1::from(vec![1., 1., 1.]), Array1::from(vec![4., 1., 1.])], 0))]
+fn test_integration_simulate(#[case] method: &str, #[case] simulation: simulate::Simulation) {
+    let X = simulation.sample();
+    let control = Control::default();
+    let result = changeforest(&X.view(), method, "bs", &control);
+
+    // All true change points are detected within 10 observations.
+    let (_, recall) = metrics::precision_recall(&simulation.split_points(), &result, 10);
+    assert_eq!(recall, 1.);
+}